@@ -0,0 +1,159 @@
+// 成功したビルドの成果物の保存
+//
+// impl/pnr は毎回上書きされるので、.fs・レポート・実行した Tcl を
+// <project_root>/.gowin-mcp/artifacts/<run_id>/ にコピーし、manifest.json に
+// git のコミット / 変更の有無、デバイス、ビルドオプション、各ファイルのハッシュを残す。
+// program_fs は artifact_id でここから過去のビルドを書き込める。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{git, gprj, report, stale};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ArtifactFile {
+    pub name: String,
+    pub sha256: String,
+    pub size: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Manifest {
+    pub artifact_id: String,
+    pub created_at_ms: u64,
+    pub tool: String,
+    pub tcl_file: String,
+    pub git: git::GitInfo,
+    pub device: Option<String>,
+    pub part_number: Option<String>,
+    // impl/project_process_config.json の中身
+    pub options: Option<serde_json::Value>,
+    // files のうち書き込みに使う .fs
+    pub fs_file: String,
+    pub files: Vec<ArtifactFile>,
+}
+
+pub fn artifacts_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".gowin-mcp").join("artifacts")
+}
+
+// 保存対象: ビットストリーム（.fs / .bin）とレポート
+fn is_artifact(name: &str) -> bool {
+    let low = name.to_ascii_lowercase();
+    [".fs", ".bin", ".tr", ".html", ".rpt.txt", ".log"]
+        .iter()
+        .any(|s| low.ends_with(s))
+}
+
+// impl/project_process_config.json（ビルドオプション）
+pub fn build_options(project_root: &Path) -> Option<serde_json::Value> {
+    let bytes = std::fs::read(
+        project_root
+            .join("impl")
+            .join("project_process_config.json"),
+    )
+    .ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+pub async fn store(
+    project_root: &Path,
+    artifact_id: &str,
+    tool: &str,
+    tcl_file: &Path,
+    created_at_ms: u64,
+) -> Result<Manifest> {
+    let fs = report::find_latest_fs(project_root)
+        .ok_or_else(|| anyhow!("impl/pnr に .fs が見つかりません"))?;
+    let pnr_dir = fs.parent().unwrap_or(project_root);
+    let dir = artifacts_dir(project_root).join(crate::safe_file_stem(artifact_id));
+    crate::ensure_dir(&dir).await?;
+
+    let mut sources: Vec<(String, PathBuf)> = std::fs::read_dir(pnr_dir)
+        .with_context(|| format!("read_dir({})", pnr_dir.display()))?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter_map(|p| Some((p.file_name()?.to_string_lossy().to_string(), p)))
+        .filter(|(name, _)| is_artifact(name))
+        .collect();
+    sources.sort();
+    sources.push(("build.tcl".into(), tcl_file.to_path_buf()));
+
+    let mut files = Vec::new();
+    for (name, src) in sources {
+        let dest = dir.join(&name);
+        let size = tokio::fs::copy(&src, &dest)
+            .await
+            .with_context(|| format!("copy({} -> {})", src.display(), dest.display()))?;
+        files.push(ArtifactFile {
+            sha256: stale::hash_file(&dest)?,
+            name,
+            size,
+        });
+    }
+
+    let (device, part_number) = match gprj::find(project_root) {
+        Some(p) => std::fs::read_to_string(&p)
+            .ok()
+            .and_then(|t| gprj::device(&t))
+            .map(|(d, pn)| (Some(d), pn))
+            .unwrap_or((None, None)),
+        None => (None, None),
+    };
+    let options = build_options(project_root);
+
+    let manifest = Manifest {
+        artifact_id: artifact_id.to_string(),
+        created_at_ms,
+        tool: tool.to_string(),
+        tcl_file: tcl_file.display().to_string(),
+        git: git::info(project_root),
+        device,
+        part_number,
+        options,
+        fs_file: fs
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        files,
+    };
+    tokio::fs::write(
+        dir.join("manifest.json"),
+        serde_json::to_vec_pretty(&manifest)?,
+    )
+    .await
+    .with_context(|| format!("write({})", dir.join("manifest.json").display()))?;
+    Ok(manifest)
+}
+
+// (manifest, .fs の絶対パス)。コピー後に書き換えられていればエラー
+pub fn load(project_root: &Path, artifact_id: &str) -> Result<(Manifest, PathBuf)> {
+    let dir = artifacts_dir(project_root).join(crate::safe_file_stem(artifact_id));
+    let path = dir.join("manifest.json");
+    let bytes = std::fs::read(&path).with_context(|| {
+        format!(
+            "artifact_id {artifact_id} が見つかりません（{}）",
+            path.display()
+        )
+    })?;
+    let manifest: Manifest =
+        serde_json::from_slice(&bytes).with_context(|| format!("parse({})", path.display()))?;
+    let fs = dir.join(&manifest.fs_file);
+    let expected = manifest
+        .files
+        .iter()
+        .find(|f| f.name == manifest.fs_file)
+        .map(|f| f.sha256.as_str());
+    let actual = stale::hash_file(&fs)?;
+    if expected != Some(actual.as_str()) {
+        return Err(anyhow!(
+            "{} のハッシュが manifest と一致しません（保存後に変更された可能性があります）",
+            fs.display()
+        ));
+    }
+    Ok((manifest, fs))
+}
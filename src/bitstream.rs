@@ -0,0 +1,188 @@
+// Gowin .fs（'0'/'1' テキスト形式のビットストリーム）の解析
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::device;
+
+// ビットストリーム先頭のコマンド（1行 = 1コマンド）
+const CMD_IDCODE: u8 = 0x06;
+const CMD_FRAME_COUNT: u8 = 0x3B;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FsInfo {
+    pub part_number: Option<String>,
+    pub device: Option<String>,
+    pub device_version: Option<String>,
+    pub gowin_version: Option<String>,
+    pub created_time: Option<String>,
+    pub idcode: Option<String>,
+    pub idcode_family: Option<String>,
+    pub idcode_devices: Vec<String>,
+    pub frame_count: Option<u32>,
+    pub size_bits: u64,
+    pub size_bytes: u64,
+    pub crc32: String,
+    pub crc_check: Option<bool>,
+    pub compressed: Option<bool>,
+    pub encrypted: Option<bool>,
+}
+
+#[derive(Debug, Clone)]
+pub struct FsBitstream {
+    pub info: FsInfo,
+    pub data: Vec<u8>,
+    pub idcode: Option<u32>,
+}
+
+pub fn parse_fs(text: &str) -> Result<FsBitstream> {
+    let mut header: Vec<(String, String)> = Vec::new();
+    let mut data: Vec<u8> = Vec::new();
+    let mut size_bits: u64 = 0;
+    let mut idcode: Option<u32> = None;
+    let mut frame_count: Option<u32> = None;
+
+    for (lineno, line) in text.lines().enumerate() {
+        let l = line.trim();
+        if l.is_empty() {
+            continue;
+        }
+        if let Some(comment) = l.strip_prefix("//") {
+            if let Some((k, v)) = comment.split_once(':') {
+                header.push((k.trim().to_lowercase(), v.trim().to_string()));
+            }
+            continue;
+        }
+
+        if l.len() % 8 != 0 || !l.bytes().all(|b| b == b'0' || b == b'1') {
+            return Err(anyhow!(
+                ".fs の {} 行目が 0/1 の 8 の倍数桁ではありません",
+                lineno + 1
+            ));
+        }
+        let row: Vec<u8> = l
+            .as_bytes()
+            .chunks(8)
+            .map(|c| c.iter().fold(0u8, |acc, b| (acc << 1) | (b - b'0')))
+            .collect();
+        size_bits += l.len() as u64;
+
+        // フレーム数のコマンドまでがヘッダ部
+        if frame_count.is_none() {
+            match row.first() {
+                Some(&CMD_IDCODE) if row.len() >= 8 => {
+                    idcode = Some(u32::from_be_bytes([row[4], row[5], row[6], row[7]]));
+                }
+                Some(&CMD_FRAME_COUNT) if row.len() >= 4 => {
+                    frame_count = Some(u32::from(u16::from_be_bytes([row[2], row[3]])));
+                }
+                _ => {}
+            }
+        }
+
+        data.extend_from_slice(&row);
+    }
+
+    if data.is_empty() {
+        return Err(anyhow!(".fs にビット列がありません"));
+    }
+
+    let get = |key: &str| -> Option<String> {
+        header
+            .iter()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.clone())
+            .filter(|v| !v.is_empty())
+    };
+    let is_off = |v: &str| {
+        let low = v.to_lowercase();
+        low == "off" || low == "no" || low == "false" || low == "none" || low == "0"
+    };
+    let flag = |key: &str| -> Option<bool> { get(key).map(|v| !is_off(&v)) };
+    // 鍵は 0 以外を含む 16 進文字列のときだけ設定済みとみなす。それ以外の値は判断しない
+    let key_set = || -> Option<bool> {
+        let key = get("encryption key")?;
+        if is_off(&key) {
+            return Some(false);
+        }
+        let hex: String = key
+            .trim_start_matches("0x")
+            .trim_start_matches("0X")
+            .chars()
+            .filter(|c| !c.is_whitespace() && *c != '_' && *c != '-')
+            .collect();
+        (!hex.is_empty() && hex.chars().all(|c| c.is_ascii_hexdigit()))
+            .then(|| hex.chars().any(|c| c != '0'))
+    };
+
+    let entry = idcode.and_then(device::lookup_idcode);
+
+    let info = FsInfo {
+        part_number: get("part number"),
+        device: get("device"),
+        device_version: get("device version"),
+        gowin_version: get("gowin version"),
+        created_time: get("created time"),
+        idcode: idcode.map(device::format_idcode),
+        idcode_family: entry.map(|e| e.family.to_string()),
+        idcode_devices: entry
+            .map(|e| e.devices.iter().map(|d| d.to_string()).collect())
+            .unwrap_or_default(),
+        frame_count,
+        size_bits,
+        size_bytes: data.len() as u64,
+        crc32: format!("{:08x}", crc32(&data)),
+        crc_check: flag("crc check"),
+        compressed: flag("compress").or_else(|| flag("compression")),
+        encrypted: flag("encryption").or_else(key_set),
+    };
+
+    Ok(FsBitstream { info, data, idcode })
+}
+
+// ビットストリームが device と一致するかを検査し、不一致の理由を返す
+pub fn device_mismatches(bs: &FsBitstream, device: &str) -> Vec<String> {
+    let mut reasons = Vec::new();
+    let Some(want) = device::device_base(device) else {
+        return reasons;
+    };
+
+    let from_header = bs
+        .info
+        .device
+        .as_deref()
+        .or(bs.info.part_number.as_deref())
+        .and_then(|d| device::device_base(d).map(|b| (d, b)));
+    if let Some((name, base)) = from_header
+        && base != want
+    {
+        reasons.push(format!(
+            ".fs のヘッダのデバイス {name} が device={device} と一致しません"
+        ));
+    }
+
+    if let Some(idcode) = bs.idcode
+        && device::idcode_matches_device(idcode, device) == Some(false)
+    {
+        reasons.push(format!(
+            ".fs の IDCODE {} ({}) が device={device} と一致しません",
+            device::format_idcode(idcode),
+            bs.info.idcode_devices.join("/"),
+        ));
+    }
+
+    reasons
+}
+
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for &b in data {
+        crc ^= u32::from(b);
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
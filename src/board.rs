@@ -0,0 +1,142 @@
+// ボードのプロファイル（組み込み + .gowin-mcp/config.toml の [boards.<名前>]）
+//
+//   [boards.myboard]
+//   device = "GW1NR-9C"                       # programmer_cli の --device
+//   part_number = "GW1NR-LV9QN88PC6/I5"       # .gprj の品番と照合する
+//   cable = "Gowin USB Cable(FT2CH)"
+//   frequency = "15MHz"
+//   flash = "embflash"                        # target: "flash" の書き込み先
+//   [boards.myboard.pins]
+//   clk = { pin = "52", io_type = "LVCMOS33" }
+//
+// 同じ名前なら config のものが組み込みより優先。名前は大文字小文字と - _ 空白を無視して比べる。
+
+use std::{collections::BTreeMap, path::Path};
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{config, device};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BoardPin {
+    pub pin: String,
+    pub io_type: Option<String>,
+    pub pull_mode: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Board {
+    #[serde(default)]
+    pub name: String,
+    // builtin / config
+    #[serde(default)]
+    pub origin: String,
+    pub description: Option<String>,
+    pub device: String,
+    pub part_number: Option<String>,
+    // 品番から求めたパッケージ（QN88P など）
+    #[serde(default)]
+    pub package: Option<String>,
+    pub cable: Option<String>,
+    pub frequency: Option<String>,
+    // embflash / exflash
+    pub flash: Option<String>,
+    // 信号名 → 既定のピン
+    #[serde(default)]
+    pub pins: BTreeMap<String, BoardPin>,
+}
+
+fn pin(pin: &str, io_type: &str, pull_mode: Option<&str>) -> BoardPin {
+    BoardPin {
+        pin: pin.into(),
+        io_type: Some(io_type.into()),
+        pull_mode: pull_mode.map(str::to_string),
+    }
+}
+
+fn builtin() -> Vec<Board> {
+    let board =
+        |name: &str, description: &str, device: &str, part_number: &str, flash: &str| Board {
+            name: name.into(),
+            origin: "builtin".into(),
+            description: Some(description.into()),
+            device: device.into(),
+            part_number: Some(part_number.into()),
+            package: None,
+            cable: Some("Gowin USB Cable(FT2CH)".into()),
+            frequency: Some("15MHz".into()),
+            flash: Some(flash.into()),
+            pins: BTreeMap::new(),
+        };
+
+    // LED はアクティブ Low、ボタンは押すと Low
+    let mut tangnano9k = board(
+        "tangnano9k",
+        "Sipeed Tang Nano 9K",
+        "GW1NR-9C",
+        "GW1NR-LV9QN88PC6/I5",
+        "embflash",
+    );
+    tangnano9k.pins = BTreeMap::from([
+        ("clk".into(), pin("52", "LVCMOS33", None)),
+        ("btn[0]".into(), pin("3", "LVCMOS18", Some("UP"))),
+        ("btn[1]".into(), pin("4", "LVCMOS18", Some("UP"))),
+        ("led[0]".into(), pin("10", "LVCMOS18", None)),
+        ("led[1]".into(), pin("11", "LVCMOS18", None)),
+        ("led[2]".into(), pin("13", "LVCMOS18", None)),
+        ("led[3]".into(), pin("14", "LVCMOS18", None)),
+        ("led[4]".into(), pin("15", "LVCMOS18", None)),
+        ("led[5]".into(), pin("16", "LVCMOS18", None)),
+        ("uart_tx".into(), pin("17", "LVCMOS33", None)),
+        ("uart_rx".into(), pin("18", "LVCMOS33", Some("UP"))),
+    ]);
+
+    let mut tangprimer25k = board(
+        "tangprimer25k",
+        "Sipeed Tang Primer 25K",
+        "GW5A-25A",
+        "GW5A-LV25MG121NC1/I0",
+        "exflash",
+    );
+    tangprimer25k.pins = BTreeMap::from([("clk".into(), pin("E2", "LVCMOS33", None))]);
+
+    vec![tangnano9k, tangprimer25k]
+}
+
+fn key(name: &str) -> String {
+    name.chars()
+        .filter(|c| !matches!(c, '-' | '_' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+// 組み込み → config の順（config が同名の組み込みを置き換える）
+pub fn all(project_root: &Path) -> Result<Vec<Board>> {
+    let mut boards = builtin();
+    for (name, mut b) in config::load(project_root)?.boards {
+        b.name = name;
+        b.origin = "config".into();
+        boards.retain(|o| key(&o.name) != key(&b.name));
+        boards.push(b);
+    }
+    for b in &mut boards {
+        b.package = b.part_number.as_deref().and_then(device::package);
+    }
+    Ok(boards)
+}
+
+pub fn find(project_root: &Path, name: &str) -> Result<Board> {
+    let boards = all(project_root)?;
+    if let Some(b) = boards.iter().find(|b| key(&b.name) == key(name)) {
+        return Ok(b.clone());
+    }
+    let hint = match device::suggest(name, boards.iter().map(|b| b.name.as_str())).as_slice() {
+        [] => String::new(),
+        s => format!("もしかして: {}。", s.join(" / ")),
+    };
+    Err(anyhow!(
+        "board {name} はありません。{hint}一覧は gowin.list_boards（独自のボードは .gowin-mcp/config.toml の [boards.<名前>]）"
+    ))
+}
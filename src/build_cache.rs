@@ -0,0 +1,156 @@
+// インクリメンタルビルド用のキャッシュ
+//
+// キーはソース（config / .gprj の一覧）、.gprj 自体とそこに載っているファイル（.cst / .sdc を含む。
+// config の sources を指定していても含める）、impl/project_process_config.json、
+// 実行する Tcl の内容、追加の環境変数、IDE のパスのハッシュ。
+// 成功したビルドのキーと impl/pnr の成果物のハッシュを
+// <project_root>/.gowin-mcp/cache/build_cache.json に残し、
+// 次回キーが同じで成果物も手付かずなら合成/配置配線を省略する。
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{gprj, report, stale};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CacheEntry {
+    pub key: String,
+    pub finished_at_ms: u64,
+    pub duration_ms: u128,
+    pub pnr_dir: String,
+    // pnr_dir からの相対パス → sha256
+    pub artifacts: BTreeMap<String, String>,
+    pub stdout: String,
+    pub stderr: String,
+    // ビルド時に保存した成果物（.gowin-mcp/artifacts/<id>/）
+    #[serde(default)]
+    pub artifact_id: Option<String>,
+}
+
+fn cache_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(".gowin-mcp")
+        .join("cache")
+        .join("build_cache.json")
+}
+
+pub fn compute_key(
+    project_root: &Path,
+    tcl_file: &Path,
+    env: Option<&HashMap<String, String>>,
+    gowin_ide_app_path: &str,
+) -> Result<String> {
+    let mut inputs: BTreeMap<String, String> = BTreeMap::new();
+
+    let (_, mut files) = stale::project_sources(project_root)?;
+    // config の sources に制約ファイルや .gprj が無くても、ビルド結果はそれらで変わる
+    if let Some(gprj_path) = gprj::find(project_root) {
+        files.extend(gprj::source_files(&gprj_path)?);
+        files.push(gprj_path);
+    }
+    files.push(
+        project_root
+            .join("impl")
+            .join("project_process_config.json"),
+    );
+    for p in files {
+        let rel = p
+            .strip_prefix(project_root)
+            .unwrap_or(&p)
+            .display()
+            .to_string();
+        let hash = stale::hash_file(&p).unwrap_or_else(|_| "missing".into());
+        inputs.insert(format!("file:{rel}"), hash);
+    }
+
+    inputs.insert("tcl".into(), stale::hash_file(tcl_file)?);
+    if let Some(env) = env {
+        for (k, v) in env {
+            inputs.insert(format!("env:{k}"), v.clone());
+        }
+    }
+    inputs.insert("ide".into(), gowin_ide_app_path.to_string());
+
+    let mut hasher = Sha256::new();
+    for (k, v) in &inputs {
+        hasher.update(k.as_bytes());
+        hasher.update([0]);
+        hasher.update(v.as_bytes());
+        hasher.update([b'\n']);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+fn hash_dir(dir: &Path) -> BTreeMap<String, String> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return BTreeMap::new();
+    };
+    entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .filter_map(|p| {
+            let name = p.file_name()?.to_string_lossy().to_string();
+            Some((name, stale::hash_file(&p).ok()?))
+        })
+        .collect()
+}
+
+// キーが一致し、成果物が前回のビルド直後のままならヒット
+pub fn lookup(project_root: &Path, key: &str) -> Option<CacheEntry> {
+    let bytes = std::fs::read(cache_path(project_root)).ok()?;
+    let entry: CacheEntry = serde_json::from_slice(&bytes).ok()?;
+    if entry.key != key || entry.artifacts.is_empty() {
+        return None;
+    }
+    let current = hash_dir(Path::new(&entry.pnr_dir));
+    entry
+        .artifacts
+        .iter()
+        .all(|(name, hash)| current.get(name) == Some(hash))
+        .then_some(entry)
+}
+
+pub fn store(
+    project_root: &Path,
+    key: &str,
+    finished_at_ms: u64,
+    duration_ms: u128,
+    stdout: &str,
+    stderr: &str,
+    artifact_id: Option<&str>,
+) -> Result<()> {
+    let Some(pnr_dir) = report::find_latest_fs(project_root)
+        .as_deref()
+        .and_then(Path::parent)
+        .map(Path::to_path_buf)
+    else {
+        return Ok(());
+    };
+    let entry = CacheEntry {
+        key: key.to_string(),
+        finished_at_ms,
+        duration_ms,
+        pnr_dir: pnr_dir.display().to_string(),
+        artifacts: hash_dir(&pnr_dir),
+        stdout: stdout.to_string(),
+        stderr: stderr.to_string(),
+        artifact_id: artifact_id.map(str::to_string),
+    };
+    let path = cache_path(project_root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("mkdir -p {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(&entry)?)
+        .with_context(|| format!("write({})", path.display()))
+}
@@ -0,0 +1,195 @@
+// programmer_cli の出力からケーブル情報を抽出する
+
+use std::collections::HashSet;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::device;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CableInfo {
+    pub name: String,
+    pub cable_type: Option<String>,
+    pub index: u32,
+    pub serial: Option<String>,
+    pub location: Option<String>,
+    pub idcode: Option<String>,
+}
+
+pub fn parse_cables(text: &str) -> Vec<CableInfo> {
+    let mut found: Vec<CableInfo> = Vec::new();
+    let mut seen = HashSet::new();
+
+    for line in text.lines() {
+        let l = line.trim();
+        if l.is_empty() {
+            continue;
+        }
+
+        let names = cable_names_in_line(l);
+        let serial = attr_value(l, &["serial number", "serial", "s/n", "sn"]);
+        let location = attr_value(l, &["location", "loc"]);
+        let idcode = device::find_idcodes(l).first().copied();
+
+        if names.is_empty() {
+            // 続く行の属性は直前のケーブルに付ける
+            if let Some(last) = found.last_mut() {
+                if last.serial.is_none() {
+                    last.serial = serial;
+                }
+                if last.location.is_none() {
+                    last.location = location;
+                }
+                if last.idcode.is_none() {
+                    last.idcode = idcode.map(device::format_idcode);
+                }
+            }
+            continue;
+        }
+
+        let explicit_index = leading_index(l)
+            .or_else(|| attr_value(l, &["cable index", "index"]).and_then(|v| v.parse().ok()));
+        for name in names {
+            // 同名ケーブル（同じボードが複数）はシリアル等で区別する
+            let key = format!("{name}|{serial:?}|{location:?}|{explicit_index:?}");
+            if !seen.insert(key) {
+                continue;
+            }
+            let index = explicit_index.unwrap_or(found.len() as u32);
+            found.push(CableInfo {
+                cable_type: cable_type(&name),
+                name,
+                index,
+                serial: serial.clone(),
+                location: location.clone(),
+                idcode: idcode.map(device::format_idcode),
+            });
+        }
+    }
+
+    found
+}
+
+fn cable_names_in_line(l: &str) -> Vec<String> {
+    let mut found = Vec::new();
+    let mut seen = HashSet::new();
+    let stripped = l
+        .trim_start_matches(|c: char| {
+            c.is_ascii_digit()
+                || c == '['
+                || c == ']'
+                || c == '.'
+                || c == ')'
+                || c == '-'
+                || c.is_whitespace()
+        })
+        .trim();
+
+    // "..." をざっくり抽出
+    let mut rest = stripped;
+    while let Some(start) = rest.find('"') {
+        let after = &rest[start + 1..];
+        if let Some(end) = after.find('"') {
+            let v = after[..end].trim();
+            let low = v.to_ascii_lowercase();
+            if (low.contains("cable") || low.contains("gowin")) && seen.insert(v.to_string()) {
+                found.push(v.to_string());
+            }
+            rest = &after[end + 1..];
+        } else {
+            break;
+        }
+    }
+
+    // 典型名（"Gowin USB Cable(FT2CH)" まで。後ろに続く属性は含めない）
+    let low = stripped.to_ascii_lowercase();
+    const TYPICAL: &str = "gowin usb cable";
+    if let Some(idx) = low.find(TYPICAL) {
+        let after = &stripped[idx + TYPICAL.len()..];
+        let suffix_len = match after.strip_prefix('(').and_then(|a| a.find(')')) {
+            Some(close) => close + 2,
+            None => 0,
+        };
+        let name = &stripped[idx..idx + TYPICAL.len() + suffix_len];
+        if seen.insert(name.to_string()) {
+            found.push(name.to_string());
+        }
+    }
+
+    // Cable: xxx（"Cable Serial: ..." などの属性行は除く）
+    if let Some(idx) = low.find("cable") {
+        let tail = &stripped[idx..];
+        if let Some(pos) = tail.find(':').or_else(|| tail.find('=')) {
+            let key = tail[..pos].to_ascii_lowercase();
+            let is_attr = ["serial", "location", "index", "type", "number", "(", ")"]
+                .iter()
+                .any(|k| key.contains(k));
+            let v = tail[pos + 1..]
+                .split([',', ';', '\t'])
+                .next()
+                .unwrap_or("")
+                .trim()
+                .trim_matches('"');
+            let already = found.iter().any(|f| v.starts_with(f.as_str()));
+            if !is_attr && !already && !v.is_empty() && seen.insert(v.to_string()) {
+                found.push(v.to_string());
+            }
+        }
+    }
+
+    found
+}
+
+// "Gowin USB Cable(FT2CH)" → "FT2CH"
+fn cable_type(name: &str) -> Option<String> {
+    let start = name.rfind('(')?;
+    let end = name[start..].find(')')? + start;
+    let t = name[start + 1..end].trim();
+    (!t.is_empty()).then(|| t.to_string())
+}
+
+// "[0] ..." / "0. ..." / "1) ..." の先頭番号
+fn leading_index(l: &str) -> Option<u32> {
+    let t = l.trim_start_matches('[');
+    let digits: String = t.chars().take_while(|c| c.is_ascii_digit()).collect();
+    if digits.is_empty() {
+        return None;
+    }
+    let next = t[digits.len()..].chars().next()?;
+    matches!(next, ']' | '.' | ')' | ':').then(|| digits.parse().ok())?
+}
+
+// "SN: ABC123" / "serial=ABC123" のような key: value を探す
+fn attr_value(l: &str, keys: &[&str]) -> Option<String> {
+    let low = l.to_ascii_lowercase();
+    for key in keys {
+        let mut from = 0;
+        while let Some(pos) = low[from..].find(key) {
+            let start = from + pos;
+            let end = start + key.len();
+            from = end;
+            // 単語の途中（"Location" 中の "loc" など）は無視
+            let before_ok = low[..start]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_ascii_alphanumeric());
+            let rest = low[end..].trim_start();
+            let sep = rest.chars().next();
+            if !before_ok || !matches!(sep, Some(':') | Some('=')) {
+                continue;
+            }
+            let offset = l.len() - rest.len() + 1;
+            let v: String = l[offset..]
+                .trim_start()
+                .chars()
+                .take_while(|c| !c.is_whitespace() && !matches!(c, ',' | ';' | ')' | ']'))
+                .collect();
+            let v = v.trim_matches('"').to_string();
+            if !v.is_empty() {
+                return Some(v);
+            }
+        }
+    }
+    None
+}
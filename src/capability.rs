@@ -0,0 +1,239 @@
+// programmer_cli --help の解析結果（対応フラグ・操作一覧）とそのキャッシュ
+//
+// 解析結果はプロジェクトではなく programmer_cli のインストールのものなので、キャッシュは
+// ユーザーごとのディレクトリ（GOWIN_MCP_CACHE_DIR、既定は $XDG_CACHE_HOME か ~/.cache の下の
+// gw-synth-flash-mcp）の programmer_caps.json に "<programmer_cli のパス>@<mtime>" をキーとして保存する。
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::programmer::Operation;
+
+pub const CACHE_DIR_ENV: &str = "GOWIN_MCP_CACHE_DIR";
+const CACHE_FILE: &str = "programmer_caps.json";
+
+// 一覧表示の候補（対応しているものだけ使う）
+const LIST_CABLES_CANDIDATES: &[&str] = &[
+    "--list-cables",
+    "--list_cables",
+    "--cableList",
+    "--listCable",
+    "--cables",
+    "-l",
+    "--list",
+    "--scan",
+    "--enumerate",
+];
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OperationEntry {
+    pub index: u32,
+    pub name: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ProgrammerCaps {
+    pub programmer_cli: String,
+    pub mtime_ms: u64,
+    pub help_exit_code: i32,
+    pub flags: Vec<String>,
+    pub operations: Vec<OperationEntry>,
+    pub list_cables_flag: Option<String>,
+}
+
+impl ProgrammerCaps {
+    pub fn from_help(programmer_cli: &Path, mtime_ms: u64, exit_code: i32, text: &str) -> Self {
+        let (flags, operations) = parse_help(text);
+        let list_cables_flag = LIST_CABLES_CANDIDATES
+            .iter()
+            .find(|c| flags.iter().any(|f| f == *c))
+            .map(|c| c.to_string());
+        Self {
+            programmer_cli: programmer_cli.display().to_string(),
+            mtime_ms,
+            help_exit_code: exit_code,
+            flags,
+            operations,
+            list_cables_flag,
+        }
+    }
+
+    // --help からフラグが取れなかった場合は「不明」として候補をそのまま使う
+    pub fn known(&self) -> bool {
+        !self.flags.is_empty()
+    }
+
+    pub fn pick_flag<'a>(&self, candidates: &[&'a str]) -> Option<&'a str> {
+        if !self.known() {
+            return candidates.first().copied();
+        }
+        candidates
+            .iter()
+            .find(|c| self.flags.iter().any(|f| f == *c))
+            .copied()
+    }
+
+    pub fn list_cables_candidates(&self) -> Vec<Vec<String>> {
+        match (&self.list_cables_flag, self.known()) {
+            (Some(flag), _) => vec![vec![flag.clone()]],
+            (None, true) => Vec::new(),
+            (None, false) => LIST_CABLES_CANDIDATES
+                .iter()
+                .map(|c| vec![c.to_string()])
+                .collect(),
+        }
+    }
+
+    pub fn operation_index(&self, op: Operation) -> Option<u32> {
+        let want = normalize_op(op.label());
+        self.operations
+            .iter()
+            .find(|o| normalize_op(&o.name) == want)
+            .map(|o| o.index)
+            .or_else(|| op.default_index())
+    }
+
+    pub fn run_flag(&self) -> &'static str {
+        self.pick_flag(&["--run", "-r"]).unwrap_or("--run")
+    }
+}
+
+fn normalize_op(s: &str) -> String {
+    s.chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .collect::<String>()
+        .to_ascii_lowercase()
+}
+
+fn parse_help(text: &str) -> (Vec<String>, Vec<OperationEntry>) {
+    let mut flags: Vec<String> = Vec::new();
+    let mut operations: Vec<OperationEntry> = Vec::new();
+
+    for line in text.lines() {
+        for token in line.split(|c: char| c.is_whitespace() || c == ',' || c == '|') {
+            let flag = token
+                .split(['=', '<', '[', '('])
+                .next()
+                .unwrap_or("")
+                .trim_end_matches([':', '.']);
+            let body = flag.trim_start_matches('-');
+            let dashes = flag.len() - body.len();
+            if (dashes == 1 || dashes == 2)
+                && body.starts_with(|c: char| c.is_ascii_alphabetic())
+                && body
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+                && !flags.iter().any(|f| f == flag)
+            {
+                flags.push(flag.to_string());
+            }
+        }
+
+        // " 2: SRAM Program" / "2 - SRAM Program" / "[2] SRAM Program"
+        let t = line.trim().trim_start_matches('[');
+        let digits: String = t.chars().take_while(|c| c.is_ascii_digit()).collect();
+        if digits.is_empty() {
+            continue;
+        }
+        let rest = &t[digits.len()..];
+        let after_sep = rest.strip_prefix([':', '-', ')', ']', '.']).unwrap_or(rest);
+        if !after_sep.starts_with(char::is_whitespace) {
+            continue;
+        }
+        let name = after_sep.trim();
+        if name.starts_with(|c: char| c.is_ascii_alphabetic())
+            && let Ok(index) = digits.parse()
+            && !operations.iter().any(|o| o.index == index)
+        {
+            operations.push(OperationEntry {
+                index,
+                name: name.to_string(),
+            });
+        }
+    }
+
+    (flags, operations)
+}
+
+fn cache_dir() -> PathBuf {
+    let env = |name: &str| std::env::var(name).ok().filter(|p| !p.trim().is_empty());
+    if let Some(dir) = env(CACHE_DIR_ENV) {
+        return PathBuf::from(dir);
+    }
+    env("XDG_CACHE_HOME")
+        .map(PathBuf::from)
+        .or_else(|| env("HOME").map(|home| Path::new(&home).join(".cache")))
+        .unwrap_or_else(std::env::temp_dir)
+        .join("gw-synth-flash-mcp")
+}
+
+fn mtime_ms(path: &Path) -> Result<u64> {
+    let modified = std::fs::metadata(path)
+        .and_then(|m| m.modified())
+        .with_context(|| format!("metadata({})", path.display()))?;
+    Ok(modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0))
+}
+
+// キャッシュがあれば使い、なければ --help を実行して解析・保存する。
+// 戻り値の bool はキャッシュから読んだかどうか。
+pub async fn load_or_probe(
+    programmer_cli: &Path,
+    timeout_sec: u64,
+    refresh: bool,
+) -> Result<(ProgrammerCaps, bool)> {
+    let mtime = mtime_ms(programmer_cli)?;
+    let key = format!("{}@{mtime}", programmer_cli.display());
+    let path = cache_dir().join(CACHE_FILE);
+
+    let mut cache: BTreeMap<String, ProgrammerCaps> = match tokio::fs::read(&path).await {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    };
+
+    if !refresh && let Some(caps) = cache.get(&key) {
+        return Ok((caps.clone(), true));
+    }
+
+    let exec = crate::exec_with_timeout(
+        programmer_cli,
+        &["--help".to_string()],
+        None,
+        None,
+        timeout_sec,
+    )
+    .await?;
+    let caps = ProgrammerCaps::from_help(
+        programmer_cli,
+        mtime,
+        exec.exit_code,
+        &format!("{}\n{}", exec.stdout, exec.stderr),
+    );
+
+    // 同じバイナリの古いエントリは捨てる
+    let prefix = format!("{}@", programmer_cli.display());
+    cache.retain(|k, _| !k.starts_with(&prefix));
+    cache.insert(key, caps.clone());
+    if let Some(dir) = path.parent() {
+        crate::ensure_dir(dir).await?;
+    }
+    // 複数のサーバーが同時に書いても壊れないように、一時ファイルから置き換える
+    let tmp = path.with_extension(format!("json.{}.tmp", std::process::id()));
+    tokio::fs::write(&tmp, serde_json::to_vec_pretty(&cache)?)
+        .await
+        .with_context(|| format!("write({})", tmp.display()))?;
+    tokio::fs::rename(&tmp, &path)
+        .await
+        .with_context(|| format!("rename({} -> {})", tmp.display(), path.display()))?;
+
+    Ok((caps, false))
+}
@@ -0,0 +1,212 @@
+// 2 つのビルドの比較（gowin.compare_runs）
+//
+// ビルド履歴（history）のリソース使用率・クロックごとのタイミング・警告コードと、
+// 履歴に残した Tcl と impl/project_process_config.json の差分を返す。
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    path::Path,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{artifacts, history::HistoryEntry};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ResourceDelta {
+    pub resource: String,
+    pub base_used: Option<u64>,
+    pub used: Option<u64>,
+    pub base_percent: Option<f64>,
+    pub percent: Option<f64>,
+    pub delta_percent: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClockDelta {
+    pub clock: String,
+    pub base_constraint_mhz: Option<f64>,
+    pub constraint_mhz: Option<f64>,
+    pub base_fmax_mhz: Option<f64>,
+    pub fmax_mhz: Option<f64>,
+    pub delta_fmax_mhz: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct WarningDelta {
+    pub code: String,
+    pub base_count: usize,
+    pub count: usize,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct OptionChange {
+    pub key: String,
+    pub before: Option<serde_json::Value>,
+    pub after: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TextDiff {
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+fn round2(v: f64) -> f64 {
+    (v * 100.0).round() / 100.0
+}
+
+pub fn utilization(base: &HistoryEntry, target: &HistoryEntry) -> Vec<ResourceDelta> {
+    let mut names: Vec<&str> = Vec::new();
+    for u in base.utilization.iter().chain(&target.utilization) {
+        if !names.contains(&u.resource.as_str()) {
+            names.push(&u.resource);
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let b = base.utilization.iter().find(|u| u.resource == name);
+            let t = target.utilization.iter().find(|u| u.resource == name);
+            ResourceDelta {
+                resource: name.to_string(),
+                base_used: b.map(|u| u.used),
+                used: t.map(|u| u.used),
+                base_percent: b.map(|u| u.percent),
+                percent: t.map(|u| u.percent),
+                delta_percent: b.zip(t).map(|(b, t)| round2(t.percent - b.percent)),
+            }
+        })
+        .collect()
+}
+
+pub fn clocks(base: &HistoryEntry, target: &HistoryEntry) -> Vec<ClockDelta> {
+    let empty = Vec::new();
+    let base_clocks = base.timing.as_ref().map(|t| &t.clocks).unwrap_or(&empty);
+    let target_clocks = target.timing.as_ref().map(|t| &t.clocks).unwrap_or(&empty);
+    let mut names: Vec<&str> = Vec::new();
+    for c in base_clocks.iter().chain(target_clocks) {
+        if !names.contains(&c.clock.as_str()) {
+            names.push(&c.clock);
+        }
+    }
+    names
+        .into_iter()
+        .map(|name| {
+            let b = base_clocks.iter().find(|c| c.clock == name);
+            let t = target_clocks.iter().find(|c| c.clock == name);
+            let base_fmax_mhz = b.and_then(|c| c.fmax_mhz);
+            let fmax_mhz = t.and_then(|c| c.fmax_mhz);
+            ClockDelta {
+                clock: name.to_string(),
+                base_constraint_mhz: b.and_then(|c| c.constraint_mhz),
+                constraint_mhz: t.and_then(|c| c.constraint_mhz),
+                base_fmax_mhz,
+                fmax_mhz,
+                delta_fmax_mhz: base_fmax_mhz.zip(fmax_mhz).map(|(b, t)| round2(t - b)),
+            }
+        })
+        .collect()
+}
+
+// 件数が変わった警告コードだけ
+pub fn warnings(base: &HistoryEntry, target: &HistoryEntry) -> Vec<WarningDelta> {
+    let codes: BTreeSet<&String> = base
+        .warning_codes
+        .keys()
+        .chain(target.warning_codes.keys())
+        .collect();
+    codes
+        .into_iter()
+        .map(|code| WarningDelta {
+            code: code.clone(),
+            base_count: base.warning_codes.get(code).copied().unwrap_or(0),
+            count: target.warning_codes.get(code).copied().unwrap_or(0),
+        })
+        .filter(|w| w.base_count != w.count)
+        .collect()
+}
+
+// 行単位の追加/削除（順序は問わない）
+pub fn text_diff(before: &str, after: &str) -> TextDiff {
+    let count = |text: &str| {
+        let mut m: BTreeMap<String, usize> = BTreeMap::new();
+        for line in text.lines().map(str::trim_end).filter(|l| !l.is_empty()) {
+            *m.entry(line.to_string()).or_default() += 1;
+        }
+        m
+    };
+    let (b, a) = (count(before), count(after));
+    let mut diff = TextDiff {
+        added: Vec::new(),
+        removed: Vec::new(),
+    };
+    for (line, n) in &a {
+        let m = b.get(line).copied().unwrap_or(0);
+        diff.added
+            .extend(std::iter::repeat_n(line.clone(), n.saturating_sub(m)));
+    }
+    for (line, n) in &b {
+        let m = a.get(line).copied().unwrap_or(0);
+        diff.removed
+            .extend(std::iter::repeat_n(line.clone(), n.saturating_sub(m)));
+    }
+    diff
+}
+
+// ネストしたキーは "a.b" にして比べる
+fn flatten(prefix: &str, value: &serde_json::Value, out: &mut BTreeMap<String, serde_json::Value>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                let key = if prefix.is_empty() {
+                    k.clone()
+                } else {
+                    format!("{prefix}.{k}")
+                };
+                flatten(&key, v, out);
+            }
+        }
+        other => {
+            out.insert(prefix.to_string(), other.clone());
+        }
+    }
+}
+
+pub fn options(
+    before: Option<&serde_json::Value>,
+    after: Option<&serde_json::Value>,
+) -> Vec<OptionChange> {
+    let mut b = BTreeMap::new();
+    let mut a = BTreeMap::new();
+    if let Some(v) = before {
+        flatten("", v, &mut b);
+    }
+    if let Some(v) = after {
+        flatten("", v, &mut a);
+    }
+    let keys: BTreeSet<&String> = b.keys().chain(a.keys()).collect();
+    keys.into_iter()
+        .filter(|k| b.get(*k) != a.get(*k))
+        .map(|k| OptionChange {
+            key: k.clone(),
+            before: b.get(k).cloned(),
+            after: a.get(k).cloned(),
+        })
+        .collect()
+}
+
+// ビルドの (Tcl, ビルドオプション)。履歴に残した写しを使い、
+// 写しのない古い履歴は成果物から読む。どちらもなければ None
+pub fn build_inputs(
+    project_root: &Path,
+    entry: &HistoryEntry,
+) -> Option<(String, Option<serde_json::Value>)> {
+    if let Some(tcl) = entry.tcl_text.as_ref() {
+        return Some((tcl.clone(), entry.options.clone()));
+    }
+    let (manifest, fs) = artifacts::load(project_root, entry.artifact_id.as_deref()?).ok()?;
+    let tcl = std::fs::read_to_string(fs.parent()?.join("build.tcl")).ok()?;
+    Some((tcl, manifest.options))
+}
@@ -0,0 +1,44 @@
+// プロジェクト設定 <project_root>/.gowin-mcp/config.toml（任意）
+//
+//   # 古いビットストリームの検出で見るソース（ファイル / ディレクトリ）。
+//   # 未指定なら .gprj のファイル一覧を使う
+//   sources = ["src", "constraints/top.cst"]
+//
+//   # 目録（gowin.list_devices）にないが programmer_cli が受け付けるデバイス名
+//   extra_devices = ["GW1N-9X"]
+//
+//   # 独自のボード（書式は board.rs）
+//   [boards.myboard]
+//   device = "GW1NR-9C"
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+use serde::Deserialize;
+
+use crate::board;
+
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct ProjectConfig {
+    pub sources: Option<Vec<String>>,
+    pub extra_devices: Option<Vec<String>>,
+    #[serde(default)]
+    pub boards: BTreeMap<String, board::Board>,
+}
+
+pub fn path(project_root: &Path) -> PathBuf {
+    project_root.join(".gowin-mcp").join("config.toml")
+}
+
+// ファイルが無ければ既定値
+pub fn load(project_root: &Path) -> Result<ProjectConfig> {
+    let path = path(project_root);
+    match std::fs::read_to_string(&path) {
+        Ok(text) => toml::from_str(&text)
+            .map_err(|e| anyhow!("設定 {} を解釈できません: {e}", path.display())),
+        Err(_) => Ok(ProjectConfig::default()),
+    }
+}
@@ -0,0 +1,451 @@
+// 制約の網羅性チェック（合成後ネットリスト .vg と .cst / .sdc の突き合わせ）
+//
+// ポートはソースではなくネットリストのトップから取る（合成で消えたポートは制約不要）。
+// - IO_LOC のないポート（ビット単位）→ unconstrained_ports（ツールが勝手に配置する）
+// - クロックピン（DFF の CLK、BSRAM の CLKA など）につながるネットを、バッファ
+//   （IBUF / BUFG / DQCE など）と assign をたどって元（ポート / PLL などの出力ピン / ネット）
+//   まで遡り、元や途中のネットを対象にした create_clock / create_generated_clock がなければ
+//   unconstrained_clocks
+// - どのポート / ネット / ピン / インスタンスにも当たらない .cst / .sdc の参照 → unmatched_constraints
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{cst, hdl, sdc};
+
+// クロックを受けるピン
+const CLOCK_PINS: [&str; 9] = [
+    "CLK", "CLKA", "CLKB", "PCLK", "FCLK", "ICLK", "OCLK", "CLKIN", "HCLKIN",
+];
+
+// (セル, 入力ピン, 出力ピン)。クロックはそのまま通り抜ける
+const BUFFERS: [(&str, &str, &str); 7] = [
+    ("IBUF", "I", "O"),
+    ("BUFG", "I", "O"),
+    ("BUFS", "I", "O"),
+    ("TLVDS_IBUF", "I", "O"),
+    ("ELVDS_IBUF", "I", "O"),
+    ("DQCE", "CLKIN", "CLKOUT"),
+    ("DHCEN", "CLKIN", "CLKOUT"),
+];
+
+// 新しいクロックを作るセル（出力には create_generated_clock / create_clock が要る）
+const GENERATORS: [&str; 12] = [
+    "rPLL", "PLL", "PLLVR", "PLLA", "CLKDIV", "CLKDIV2", "OSC", "OSCH", "OSCZ", "OSCF", "OSCO",
+    "OSCA",
+];
+
+#[derive(Debug, Clone)]
+pub struct Instance {
+    pub cell: String,
+    pub name: String,
+    // ピン → ネット
+    pub pins: Vec<(String, String)>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Netlist {
+    pub module: String,
+    pub ports: Vec<hdl::Port>,
+    pub instances: Vec<Instance>,
+    // assign 左辺 → 右辺
+    pub assigns: BTreeMap<String, String>,
+    pub nets: BTreeSet<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PortBit {
+    pub port: String,
+    pub direction: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClockNet {
+    // 元: ポート名 / "インスタンス/ピン" / ネット名
+    pub root: String,
+    // port / pin / net
+    pub kind: String,
+    // 元からクロックピンまでのネット
+    pub nets: Vec<String>,
+    // つながっているクロックピンの数
+    pub sinks: usize,
+    // 対象にしている .sdc のクロック名（空なら未制約）
+    pub constrained_by: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Unmatched {
+    // cst / sdc
+    pub file: String,
+    pub line: usize,
+    // get_ports / get_nets / get_pins / get_cells / IO_LOC / IO_PORT など
+    pub kind: String,
+    pub reference: String,
+    pub text: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    pub port_bits: Vec<PortBit>,
+    pub unconstrained_ports: Vec<PortBit>,
+    pub clocks: Vec<ClockNet>,
+    pub unconstrained_clocks: Vec<String>,
+    pub unmatched_constraints: Vec<Unmatched>,
+}
+
+// \name のエスケープ識別子（空白まで）をそのままの名前にする
+fn unescape(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut escaped = false;
+    for c in text.chars() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            c if escaped && c.is_whitespace() => {
+                escaped = false;
+                out.push(' ');
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+fn net_name(expr: &str) -> Option<String> {
+    let e: String = expr.split_whitespace().collect();
+    // 定数や連結はクロックにならない
+    if e.is_empty() || e.starts_with('{') || e.contains('\'') || e == "GND" || e == "VCC" {
+        return None;
+    }
+    Some(e)
+}
+
+// "CELL [#(...)] NAME ( .PIN(net), ... )"
+fn parse_instance(stmt: &str) -> Option<Instance> {
+    let cell: String = stmt
+        .chars()
+        .take_while(|c| c.is_ascii_alphanumeric() || *c == '_' || *c == '$')
+        .collect();
+    let mut rest = stmt[cell.len()..].trim_start();
+    if rest.starts_with('#') {
+        let open = rest.find('(')?;
+        rest = rest[hdl::matching(rest, open, '(', ')')? + 1..].trim_start();
+    }
+    let open = rest.find('(')?;
+    let name: String = rest[..open].split_whitespace().collect();
+    let close = hdl::matching(rest, open, '(', ')')?;
+    if cell.is_empty() || name.is_empty() {
+        return None;
+    }
+    let pins = hdl::split_top(&rest[open + 1..close], ',')
+        .into_iter()
+        .filter_map(|conn| {
+            let conn = conn.trim().strip_prefix('.')?;
+            let open = conn.find('(')?;
+            let close = hdl::matching(conn, open, '(', ')')?;
+            Some((
+                conn[..open].trim().to_string(),
+                net_name(&conn[open + 1..close])?,
+            ))
+        })
+        .collect();
+    Some(Instance { cell, name, pins })
+}
+
+const DECLARATIONS: [&str; 13] = [
+    "input",
+    "output",
+    "inout",
+    "wire",
+    "reg",
+    "tri",
+    "supply0",
+    "supply1",
+    "parameter",
+    "localparam",
+    "defparam",
+    "genvar",
+    "timescale",
+];
+
+pub fn parse_netlist(text: &str, top_module: Option<&str>) -> anyhow::Result<Netlist> {
+    let text = unescape(&hdl::strip_verilog_comments(text));
+    let modules = hdl::parse_verilog(&text, std::path::Path::new(""));
+    let top = hdl::pick_top(&modules, top_module)?;
+
+    let mut netlist = Netlist {
+        module: top.name.clone(),
+        ports: top.ports.clone(),
+        instances: Vec::new(),
+        assigns: BTreeMap::new(),
+        nets: BTreeSet::new(),
+    };
+    for stmt in hdl::split_top(&top.body, ';') {
+        let stmt = stmt.trim();
+        let keyword: String = stmt
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric() || *c == '_')
+            .collect();
+        if keyword == "wire" {
+            for item in hdl::split_top(&stmt[keyword.len()..], ',') {
+                let item = item.trim();
+                // "[3:0] a" の範囲は飛ばす
+                let item = match item.strip_prefix('[') {
+                    Some(r) => r.split_once(']').map_or("", |(_, n)| n),
+                    None => item,
+                };
+                if let Some(n) = net_name(item) {
+                    netlist.nets.insert(n);
+                }
+            }
+        } else if keyword == "assign" {
+            if let Some((lhs, rhs)) = stmt[keyword.len()..].split_once('=')
+                && let (Some(l), Some(r)) = (net_name(lhs), net_name(rhs))
+            {
+                netlist.nets.insert(l.clone());
+                netlist.nets.insert(r.clone());
+                netlist.assigns.insert(l, r);
+            }
+        } else if !keyword.is_empty()
+            && !DECLARATIONS.contains(&keyword.as_str())
+            && let Some(inst) = parse_instance(stmt)
+        {
+            netlist
+                .nets
+                .extend(inst.pins.iter().map(|(_, n)| n.clone()));
+            netlist.instances.push(inst);
+        }
+    }
+    Ok(netlist)
+}
+
+// [7:0] → 0..=7 のビットに展開する
+pub fn port_bits(ports: &[hdl::Port]) -> Vec<PortBit> {
+    let mut out = Vec::new();
+    for p in ports {
+        let range = p
+            .range
+            .as_deref()
+            .and_then(|r| r.strip_prefix('[')?.strip_suffix(']')?.split_once(':'))
+            .and_then(|(a, b)| {
+                Some((a.trim().parse::<i64>().ok()?, b.trim().parse::<i64>().ok()?))
+            });
+        match range {
+            Some((a, b)) => out.extend((a.min(b)..=a.max(b)).map(|i| PortBit {
+                port: format!("{}[{i}]", p.name),
+                direction: p.direction.clone(),
+            })),
+            None => out.push(PortBit {
+                port: p.name.clone(),
+                direction: p.direction.clone(),
+            }),
+        }
+    }
+    out
+}
+
+fn is_port(netlist: &Netlist, net: &str) -> bool {
+    netlist
+        .ports
+        .iter()
+        .any(|p| p.name == net || p.name == hdl::base_name(net))
+}
+
+// クロックネットを元までたどる: (元, 種類, 途中のネット)
+fn trace(netlist: &Netlist, net: &str) -> (String, String, Vec<String>) {
+    let mut chain = vec![net.to_string()];
+    let mut current = net.to_string();
+    // ループしないよう、たどる回数を制限する
+    for _ in 0..32 {
+        if is_port(netlist, &current) {
+            return (current, "port".into(), chain);
+        }
+        let driver = netlist.instances.iter().find_map(|inst| {
+            inst.pins
+                .iter()
+                .find(|(pin, n)| {
+                    *n == current
+                        && (BUFFERS
+                            .iter()
+                            .any(|(cell, _, out)| *cell == inst.cell && pin == out)
+                            || (GENERATORS.contains(&inst.cell.as_str())
+                                && (pin.starts_with("CLKOUT")
+                                    || pin.starts_with("OSCOUT")
+                                    || pin == "OUT")))
+                })
+                .map(|(pin, _)| (inst, pin))
+        });
+        let next = match driver {
+            Some((inst, pin)) if GENERATORS.contains(&inst.cell.as_str()) => {
+                return (format!("{}/{pin}", inst.name), "pin".into(), chain);
+            }
+            Some((inst, _)) => {
+                let input = BUFFERS
+                    .iter()
+                    .find(|(cell, _, _)| *cell == inst.cell)
+                    .map(|(_, input, _)| *input)
+                    .unwrap_or("I");
+                inst.pins
+                    .iter()
+                    .find(|(pin, _)| pin == input)
+                    .map(|(_, n)| n.clone())
+            }
+            None => netlist.assigns.get(&current).cloned(),
+        };
+        match next {
+            Some(n) if !chain.contains(&n) => {
+                chain.push(n.clone());
+                current = n;
+            }
+            _ => break,
+        }
+    }
+    (current, "net".into(), chain)
+}
+
+pub fn clock_nets(netlist: &Netlist) -> Vec<ClockNet> {
+    let mut by_root: BTreeMap<String, ClockNet> = BTreeMap::new();
+    for inst in &netlist.instances {
+        if BUFFERS.iter().any(|(cell, _, _)| *cell == inst.cell) {
+            continue;
+        }
+        for (pin, net) in &inst.pins {
+            if !CLOCK_PINS.contains(&pin.as_str()) {
+                continue;
+            }
+            let (root, kind, chain) = trace(netlist, net);
+            let entry = by_root.entry(root.clone()).or_insert_with(|| ClockNet {
+                root,
+                kind,
+                nets: Vec::new(),
+                sinks: 0,
+                constrained_by: Vec::new(),
+            });
+            entry.sinks += 1;
+            // 元に近い順
+            for n in chain.into_iter().rev() {
+                if !entry.nets.contains(&n) {
+                    entry.nets.push(n);
+                }
+            }
+        }
+    }
+    by_root.into_values().collect()
+}
+
+fn pin_names(netlist: &Netlist) -> Vec<String> {
+    netlist
+        .instances
+        .iter()
+        .flat_map(|i| i.pins.iter().map(move |(p, _)| format!("{}/{p}", i.name)))
+        .collect()
+}
+
+pub fn check(
+    netlist: &Netlist,
+    cst: Option<&cst::CstFile>,
+    sdc: Option<&sdc::SdcFile>,
+) -> Coverage {
+    let port_bits = port_bits(&netlist.ports);
+
+    // IO_LOC のないポート
+    let assignments = cst.map(cst::assignments).unwrap_or_default();
+    let placed = |bit: &PortBit| {
+        assignments.iter().any(|a| {
+            !a.pins.is_empty() && (a.port == bit.port || a.port == hdl::base_name(&bit.port))
+        })
+    };
+    let unconstrained_ports = port_bits.iter().filter(|b| !placed(b)).cloned().collect();
+
+    // クロック
+    let sdc_clocks = sdc.map(sdc::clocks).unwrap_or_default();
+    let mut clocks = clock_nets(netlist);
+    for c in &mut clocks {
+        let names: Vec<&String> = std::iter::once(&c.root).chain(&c.nets).collect();
+        c.constrained_by = sdc_clocks
+            .iter()
+            .filter(|k| {
+                k.targets
+                    .iter()
+                    .any(|t| names.iter().any(|n| hdl::glob_match(t, n)))
+            })
+            .map(|k| k.name.clone())
+            .collect();
+    }
+    let unconstrained_clocks = clocks
+        .iter()
+        .filter(|c| c.constrained_by.is_empty())
+        .map(|c| c.root.clone())
+        .collect();
+
+    // 何にも当たらない参照
+    let mut unmatched_constraints = Vec::new();
+    if let Some(file) = cst {
+        for s in &file.statements {
+            let Some(target) = s.target.as_deref() else {
+                continue;
+            };
+            let hit = match s.keyword.as_str() {
+                "IO_LOC" | "IO_PORT" => port_bits
+                    .iter()
+                    .any(|b| b.port == target || hdl::base_name(&b.port) == target),
+                "INS_LOC" => netlist
+                    .instances
+                    .iter()
+                    .any(|i| hdl::glob_match(target, &i.name)),
+                "CLOCK_LOC" => netlist.nets.iter().any(|n| hdl::glob_match(target, n)),
+                _ => true,
+            };
+            if !hit {
+                unmatched_constraints.push(Unmatched {
+                    file: "cst".into(),
+                    line: s.line,
+                    kind: s.keyword.clone(),
+                    reference: target.to_string(),
+                    text: s.text.clone(),
+                });
+            }
+        }
+    }
+    if let Some(file) = sdc {
+        let pins = pin_names(netlist);
+        for c in &file.commands {
+            for (query, pattern) in c.queries() {
+                let hit = match query {
+                    "get_ports" => {
+                        hdl::port_exists(&netlist.ports, pattern)
+                            && (!pattern.contains('[')
+                                || pattern.contains('*')
+                                || hdl::port_bit_exists(&netlist.ports, pattern))
+                    }
+                    "get_nets" => netlist.nets.iter().any(|n| hdl::glob_match(pattern, n)),
+                    "get_pins" => pins.iter().any(|n| hdl::glob_match(pattern, n)),
+                    "get_cells" => netlist
+                        .instances
+                        .iter()
+                        .any(|i| hdl::glob_match(pattern, &i.name)),
+                    // get_clocks は sdc::validate が確認する
+                    _ => true,
+                };
+                if !hit {
+                    unmatched_constraints.push(Unmatched {
+                        file: "sdc".into(),
+                        line: c.line,
+                        kind: query.to_string(),
+                        reference: pattern.to_string(),
+                        text: c.text.clone(),
+                    });
+                }
+            }
+        }
+    }
+
+    Coverage {
+        port_bits,
+        unconstrained_ports,
+        clocks,
+        unconstrained_clocks,
+        unmatched_constraints,
+    }
+}
@@ -0,0 +1,421 @@
+// 物理制約（.cst）の解析と編集
+//
+//   IO_LOC "led[0]" 10;
+//   IO_PORT "led[0]" IO_TYPE=LVCMOS33 PULL_MODE=UP DRIVE=8;
+//   IO_LOC "lvds_p" 32,33;        // 差動ペアは P,N の 2 ピン
+//
+// ; で区切られた文ごとにファイル中の範囲を持つ。IO_LOC / IO_PORT をポートごとの
+// ピン割り当てにまとめ、編集はその文の置き換え・削除・末尾への追加で行う。
+
+use std::collections::BTreeMap;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{hdl, pinout, sdc::Issue};
+
+// Gowin の物理制約で文の先頭に来るもの（IO_LOC / IO_PORT 以外はそのまま残す）
+const KEYWORDS: &[&str] = &[
+    "IO_LOC",
+    "IO_PORT",
+    "INS_LOC",
+    "GRP_LOC",
+    "GROUP",
+    "CLOCK_LOC",
+    "VREF_DRIVER",
+];
+
+// IO_PORT の属性
+pub const ATTRIBUTES: &[&str] = &[
+    "IO_TYPE",
+    "PULL_MODE",
+    "DRIVE",
+    "BANK_VCCIO",
+    "OPEN_DRAIN",
+    "HYSTERESIS",
+    "CLAMP",
+    "PCI_CLAMP",
+    "DIFFRESISTOR",
+    "SINGLERESISTOR",
+    "VREF",
+    "SLEW_RATE",
+];
+
+const PULL_MODES: &[&str] = &["UP", "DOWN", "KEEPER", "NONE"];
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Statement {
+    pub index: usize,
+    // 1 始まり
+    pub line: usize,
+    pub keyword: String,
+    // "..." で囲まれた対象（ポート名など）
+    pub target: Option<String>,
+    pub args: Vec<String>,
+    pub text: String,
+    #[serde(skip)]
+    pub span: (usize, usize),
+}
+
+impl Statement {
+    // IO_LOC のピン（"32,33" / "32, 33" は 2 ピン、exclusive は除く）
+    fn pins(&self) -> Vec<String> {
+        self.args
+            .iter()
+            .flat_map(|a| a.split(','))
+            .map(str::trim)
+            .filter(|p| !p.is_empty() && !p.eq_ignore_ascii_case("exclusive"))
+            .map(str::to_string)
+            .collect()
+    }
+
+    // IO_PORT の (属性, 値)。= のない項目は値 None
+    pub fn attributes(&self) -> Vec<(String, Option<String>)> {
+        self.args
+            .iter()
+            .map(|a| match a.split_once('=') {
+                Some((k, v)) => (k.trim().to_ascii_uppercase(), Some(v.trim().to_string())),
+                None => (a.trim().to_ascii_uppercase(), None),
+            })
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PinAssignment {
+    pub port: String,
+    pub pins: Vec<String>,
+    pub io_type: Option<String>,
+    pub pull_mode: Option<String>,
+    pub drive: Option<String>,
+    // 上の 3 つ以外の IO_PORT 属性
+    pub attributes: BTreeMap<String, String>,
+    pub loc_line: Option<usize>,
+    pub port_line: Option<usize>,
+}
+
+#[derive(Debug, Clone)]
+pub struct CstFile {
+    pub statements: Vec<Statement>,
+    pub errors: Vec<Issue>,
+}
+
+fn line_of(src: &str, pos: usize) -> usize {
+    src[..pos].matches('\n').count() + 1
+}
+
+pub fn parse(src: &str) -> CstFile {
+    let mut statements: Vec<Statement> = Vec::new();
+    let mut errors = Vec::new();
+    let mut words: Vec<(String, bool)> = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut chars = src.char_indices().peekable();
+
+    let mut finish = |words: &mut Vec<(String, bool)>, start: usize, end: usize| {
+        let mut iter = std::mem::take(words).into_iter();
+        let Some((keyword, _)) = iter.next() else {
+            return;
+        };
+        let mut rest: Vec<(String, bool)> = iter.collect();
+        let target = match rest.first() {
+            Some((_, true)) => Some(rest.remove(0).0),
+            _ => None,
+        };
+        // "IO_TYPE = LVCMOS33" のように = の前後に空白があっても 1 項目にする
+        let mut args: Vec<String> = Vec::new();
+        for (w, _) in rest {
+            match args.last_mut() {
+                Some(last) if last.ends_with('=') || w.starts_with('=') => last.push_str(&w),
+                _ => args.push(w),
+            }
+        }
+        let text = src[start..end].trim_end().to_string();
+        statements.push(Statement {
+            index: statements.len(),
+            line: line_of(src, start),
+            keyword: keyword.to_ascii_uppercase(),
+            target,
+            args,
+            span: (start, start + text.len()),
+            text,
+        });
+    };
+
+    while let Some((pos, c)) = chars.next() {
+        match c {
+            '/' if chars.peek().is_some_and(|(_, n)| *n == '/') => {
+                while chars.next_if(|(_, n)| *n != '\n').is_some() {}
+            }
+            ';' => {
+                if let Some(s) = start.take() {
+                    finish(&mut words, s, pos + 1);
+                }
+            }
+            '"' => {
+                start.get_or_insert(pos);
+                let mut word = String::new();
+                let mut closed = false;
+                for (_, n) in chars.by_ref() {
+                    if n == '"' {
+                        closed = true;
+                        break;
+                    }
+                    word.push(n);
+                }
+                if !closed {
+                    errors.push(Issue {
+                        level: "error".into(),
+                        line: Some(line_of(src, pos)),
+                        message: "\" が閉じていません".into(),
+                    });
+                    return CstFile { statements, errors };
+                }
+                words.push((word, true));
+            }
+            c if c.is_whitespace() => {}
+            _ => {
+                start.get_or_insert(pos);
+                let mut word = c.to_string();
+                while let Some((_, n)) =
+                    chars.next_if(|(_, n)| !n.is_whitespace() && !matches!(n, ';' | '"'))
+                {
+                    word.push(n);
+                }
+                words.push((word, false));
+            }
+        }
+    }
+    if let Some(s) = start {
+        errors.push(Issue {
+            level: "error".into(),
+            line: Some(line_of(src, s)),
+            message: "最後の文が ; で終わっていません".into(),
+        });
+        finish(&mut words, s, src.len());
+    }
+    CstFile { statements, errors }
+}
+
+// ポートごとの割り当て（ファイルに最初に現れた順）
+pub fn assignments(file: &CstFile) -> Vec<PinAssignment> {
+    let mut out: Vec<PinAssignment> = Vec::new();
+    for s in &file.statements {
+        let Some(port) = s.target.as_ref() else {
+            continue;
+        };
+        if s.keyword != "IO_LOC" && s.keyword != "IO_PORT" {
+            continue;
+        }
+        let a = match out.iter().position(|a| &a.port == port) {
+            Some(i) => &mut out[i],
+            None => {
+                out.push(PinAssignment {
+                    port: port.clone(),
+                    pins: Vec::new(),
+                    io_type: None,
+                    pull_mode: None,
+                    drive: None,
+                    attributes: BTreeMap::new(),
+                    loc_line: None,
+                    port_line: None,
+                });
+                out.last_mut().expect("pushed")
+            }
+        };
+        if s.keyword == "IO_LOC" {
+            a.pins = s.pins();
+            a.loc_line.get_or_insert(s.line);
+            continue;
+        }
+        a.port_line.get_or_insert(s.line);
+        for (k, v) in s.attributes() {
+            let Some(v) = v else {
+                continue;
+            };
+            match k.as_str() {
+                "IO_TYPE" => a.io_type = Some(v),
+                "PULL_MODE" => a.pull_mode = Some(v),
+                "DRIVE" => a.drive = Some(v),
+                _ => {
+                    a.attributes.insert(k, v);
+                }
+            }
+        }
+    }
+    out
+}
+
+// pinout: (ピン配置データベース, パッケージ列)。あればピンの存在・VCCIO・差動ペアも見る
+pub fn validate(
+    file: &CstFile,
+    ports: Option<&[hdl::Port]>,
+    pinout: Option<(&pinout::PinoutDb, &str)>,
+) -> Vec<Issue> {
+    let mut issues = file.errors.clone();
+    let issue = |level: &str, line: usize, message: String| Issue {
+        level: level.into(),
+        line: Some(line),
+        message,
+    };
+
+    let mut located: BTreeMap<&str, usize> = BTreeMap::new();
+    let mut pin_owner: BTreeMap<String, (&str, usize)> = BTreeMap::new();
+    for s in &file.statements {
+        if !KEYWORDS.contains(&s.keyword.as_str()) {
+            issues.push(issue(
+                "warning",
+                s.line,
+                format!("{} は解釈できない文です", s.keyword),
+            ));
+            continue;
+        }
+        if s.keyword != "IO_LOC" && s.keyword != "IO_PORT" {
+            continue;
+        }
+        let Some(port) = s.target.as_deref() else {
+            issues.push(issue(
+                "error",
+                s.line,
+                format!("{} にポート名（\"...\"）がありません", s.keyword),
+            ));
+            continue;
+        };
+        if let Some(ports) = ports
+            && !hdl::port_bit_exists(ports, port)
+        {
+            issues.push(issue(
+                "error",
+                s.line,
+                format!("ポート {port} はトップモジュールにありません"),
+            ));
+        }
+
+        if s.keyword == "IO_LOC" {
+            let pins = s.pins();
+            if pins.is_empty() {
+                issues.push(issue("error", s.line, format!("{port} のピンがありません")));
+            }
+            if let Some(first) = located.insert(port, s.line) {
+                issues.push(issue(
+                    "error",
+                    s.line,
+                    format!("{port} の IO_LOC は {first} 行目にもあります"),
+                ));
+            }
+            for pin in pins {
+                match pin_owner.get(&pin.to_ascii_uppercase()) {
+                    Some((other, line)) if *other != port => issues.push(issue(
+                        "error",
+                        s.line,
+                        format!("ピン {pin} は {other}（{line} 行目）にも割り当てられています"),
+                    )),
+                    _ => {
+                        pin_owner.insert(pin.to_ascii_uppercase(), (port, s.line));
+                    }
+                }
+            }
+            continue;
+        }
+
+        for (k, v) in s.attributes() {
+            let Some(v) = v.filter(|v| !v.is_empty()) else {
+                issues.push(issue(
+                    "error",
+                    s.line,
+                    format!("{port} の {k} に値がありません（{k}=<値>）"),
+                ));
+                continue;
+            };
+            if !ATTRIBUTES.contains(&k.as_str()) {
+                issues.push(issue(
+                    "error",
+                    s.line,
+                    format!("{port}: {k} は IO_PORT の属性ではありません"),
+                ));
+            } else if k == "PULL_MODE" && !PULL_MODES.contains(&v.to_ascii_uppercase().as_str()) {
+                issues.push(issue(
+                    "error",
+                    s.line,
+                    format!(
+                        "{port}: PULL_MODE={v} は不正です（{}）",
+                        PULL_MODES.join(" / ")
+                    ),
+                ));
+            } else if k == "DRIVE" && !v.eq_ignore_ascii_case("OFF") && v.parse::<f64>().is_err() {
+                issues.push(issue(
+                    "error",
+                    s.line,
+                    format!("{port}: DRIVE={v} は不正です（mA の数値か OFF）"),
+                ));
+            }
+        }
+    }
+
+    for a in assignments(file) {
+        if a.loc_line.is_none()
+            && let Some(line) = a.port_line
+        {
+            issues.push(issue(
+                "warning",
+                line,
+                format!(
+                    "{} に IO_LOC がありません（ピンは配置ツールが選びます）",
+                    a.port
+                ),
+            ));
+        }
+    }
+    if let Some((db, package)) = pinout {
+        issues.extend(pinout::validate(db, package, file, ports));
+    }
+    issues
+}
+
+// 割り当て 1 つ分の文
+pub fn loc_statement(port: &str, pins: &[String]) -> String {
+    format!("IO_LOC \"{port}\" {};", pins.join(","))
+}
+
+pub fn port_statement(port: &str, attributes: &[(String, String)]) -> String {
+    let attrs: Vec<String> = attributes.iter().map(|(k, v)| format!("{k}={v}")).collect();
+    format!("IO_PORT \"{port}\" {};", attrs.join(" "))
+}
+
+// 文の置き換え（None は行ごと削除）をまとめて適用する
+pub fn apply(src: &str, edits: &[(&Statement, Option<String>)]) -> String {
+    let mut edits: Vec<&(&Statement, Option<String>)> = edits.iter().collect();
+    edits.sort_by_key(|(s, _)| std::cmp::Reverse(s.span.0));
+    let mut out = src.to_string();
+    for (s, with) in edits {
+        let (start, mut end) = s.span;
+        match with {
+            Some(text) => out.replace_range(start..end, text),
+            None => {
+                // 行にこの文しかなければ行ごと消す
+                let line_start = out[..start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+                let rest = &out[end..];
+                let line_end = rest.find('\n').map(|i| end + i + 1).unwrap_or(out.len());
+                let alone = out[line_start..start].trim().is_empty()
+                    && out[end..line_end].trim().is_empty();
+                let start = if alone { line_start } else { start };
+                if alone {
+                    end = line_end;
+                }
+                out.replace_range(start..end, "");
+            }
+        }
+    }
+    out
+}
+
+pub fn append(src: &str, lines: &[String]) -> String {
+    let mut out = src.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    for l in lines {
+        out.push_str(l);
+        out.push('\n');
+    }
+    out
+}
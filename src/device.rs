@@ -0,0 +1,453 @@
+// Gowin デバイス名・IDCODE の扱い
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+pub struct IdcodeEntry {
+    pub idcode: u32,
+    pub family: &'static str,
+    // 同じダイを共有するデバイス（ベース名: "GW1NR-9" 形式）
+    pub devices: &'static [&'static str],
+}
+
+// 上位4bit（バージョン）はマスクして比較する
+const IDCODE_VERSION_MASK: u32 = 0x0FFF_FFFF;
+// Gowin の JEDEC メーカーID（bit 11:0）
+const GOWIN_MANUFACTURER_BITS: u32 = 0x81B;
+
+pub const IDCODES: &[IdcodeEntry] = &[
+    IdcodeEntry {
+        idcode: 0x0900_281B,
+        family: "GW1N",
+        devices: &["GW1N-1"],
+    },
+    IdcodeEntry {
+        idcode: 0x0100_681B,
+        family: "GW1NZ",
+        devices: &["GW1NZ-1"],
+    },
+    IdcodeEntry {
+        idcode: 0x0120_681B,
+        family: "GW1N",
+        devices: &["GW1N-2", "GW1NR-2", "GW1N-1P5"],
+    },
+    IdcodeEntry {
+        idcode: 0x0100_381B,
+        family: "GW1N",
+        devices: &["GW1N-4", "GW1NR-4"],
+    },
+    IdcodeEntry {
+        idcode: 0x0300_081B,
+        family: "GW1NS",
+        devices: &["GW1NS-2"],
+    },
+    IdcodeEntry {
+        idcode: 0x0300_181B,
+        family: "GW1NSR",
+        devices: &["GW1NSR-2"],
+    },
+    IdcodeEntry {
+        idcode: 0x0100_981B,
+        family: "GW1NS",
+        devices: &["GW1NS-4", "GW1NSR-4"],
+    },
+    IdcodeEntry {
+        idcode: 0x0100_481B,
+        family: "GW1N",
+        devices: &["GW1N-9", "GW1NR-9"],
+    },
+    IdcodeEntry {
+        idcode: 0x0100_581B,
+        family: "GW1N",
+        devices: &["GW1N-9", "GW1NR-9"],
+    },
+    IdcodeEntry {
+        idcode: 0x0000_081B,
+        family: "GW2A",
+        devices: &["GW2A-18", "GW2AR-18"],
+    },
+    IdcodeEntry {
+        idcode: 0x0000_281B,
+        family: "GW2A",
+        devices: &["GW2A-55", "GW2AN-55"],
+    },
+    IdcodeEntry {
+        idcode: 0x0001_281B,
+        family: "GW5A",
+        devices: &["GW5A-25", "GW5AR-25"],
+    },
+    IdcodeEntry {
+        idcode: 0x0001_481B,
+        family: "GW5AT",
+        devices: &["GW5AT-60", "GW5A-60"],
+    },
+    IdcodeEntry {
+        idcode: 0x0001_081B,
+        family: "GW5AST",
+        devices: &["GW5AST-138", "GW5AT-138"],
+    },
+];
+
+pub fn lookup_idcode(idcode: u32) -> Option<&'static IdcodeEntry> {
+    IDCODES
+        .iter()
+        .find(|e| e.idcode & IDCODE_VERSION_MASK == idcode & IDCODE_VERSION_MASK)
+}
+
+pub fn same_die(a: u32, b: u32) -> bool {
+    a & IDCODE_VERSION_MASK == b & IDCODE_VERSION_MASK
+}
+
+pub fn format_idcode(idcode: u32) -> String {
+    format!("0x{idcode:08X}")
+}
+
+pub fn parse_idcode(s: &str) -> Option<u32> {
+    let t = s.trim();
+    let hex = t
+        .strip_prefix("0x")
+        .or_else(|| t.strip_prefix("0X"))
+        .unwrap_or(t);
+    u32::from_str_radix(hex, 16).ok()
+}
+
+// テキスト中の "0x........"（8 桁）を出現順に拾う
+fn hex_words(text: &str) -> Vec<u32> {
+    let mut found = Vec::new();
+    let bytes = text.as_bytes();
+    let mut i = 0;
+    while i + 10 <= bytes.len() {
+        if bytes[i] == b'0' && (bytes[i + 1] == b'x' || bytes[i + 1] == b'X') {
+            let digits = &bytes[i + 2..i + 10];
+            let boundary = bytes.get(i + 10).is_none_or(|c| !c.is_ascii_hexdigit());
+            if boundary
+                && digits.iter().all(|c| c.is_ascii_hexdigit())
+                && let Ok(v) = u32::from_str_radix(&String::from_utf8_lossy(digits), 16)
+            {
+                found.push(v);
+                i += 10;
+                continue;
+            }
+        }
+        i += 1;
+    }
+    found
+}
+
+pub fn is_gowin(idcode: u32) -> bool {
+    idcode & 0xFFF == GOWIN_MANUFACTURER_BITS
+}
+
+// テキスト中の IDCODE のうち Gowin のメーカーIDを持つものを出現順に拾う
+pub fn find_idcodes(text: &str) -> Vec<u32> {
+    hex_words(text)
+        .into_iter()
+        .filter(|v| is_gowin(*v))
+        .collect()
+}
+
+// "GW5A-25A" / "GW1NR-LV9QN88PC6/I5" → "GW5A-25" / "GW1NR-9"
+pub fn device_base(name: &str) -> Option<String> {
+    let upper = name.trim().to_uppercase();
+    let (family, rest) = upper.split_once('-')?;
+    if !family.starts_with("GW") {
+        return None;
+    }
+    let rest = ["LV", "UV", "EV", "UX", "LX"]
+        .iter()
+        .find_map(|p| rest.strip_prefix(p))
+        .unwrap_or(rest);
+    let density: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == 'P')
+        .collect();
+    let density = density.trim_end_matches('P');
+    if density.is_empty() {
+        return None;
+    }
+    Some(format!("{family}-{density}"))
+}
+
+// 品番のパッケージ: "GW1NR-LV9QN88PC6/I5" → "QN88P"、"GW5A-LV25MG121NC1/I0" → "MG121N"
+pub fn package(part_number: &str) -> Option<String> {
+    let upper = part_number.trim().to_uppercase();
+    let (_, rest) = upper.split_once('-')?;
+    let rest = ["LV", "UV", "EV", "UX", "LX"]
+        .iter()
+        .find_map(|p| rest.strip_prefix(p))
+        .unwrap_or(rest);
+    // 集積度（"9"、"1P5"）の後ろ。"18PG256" の P はパッケージ側
+    let density = rest
+        .chars()
+        .take_while(|c| c.is_ascii_digit() || *c == 'P')
+        .collect::<String>();
+    let rest = &rest[density.trim_end_matches('P').len()..];
+    let chars: Vec<char> = rest.chars().collect();
+    let mut end = chars.iter().take_while(|c| c.is_ascii_uppercase()).count();
+    let letters = end;
+    end += chars[end..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    if letters == 0 || end == letters {
+        return None;
+    }
+    // 末尾の英字（"P"、"N"）。C6 / I5 のような速度グレードの手前まで
+    while end < chars.len()
+        && chars[end].is_ascii_uppercase()
+        && !(matches!(chars[end], 'C' | 'I')
+            && chars.get(end + 1).is_some_and(|c| c.is_ascii_digit()))
+    {
+        end += 1;
+    }
+    Some(chars[..end].iter().collect())
+}
+
+pub fn idcode_matches_device(idcode: u32, device: &str) -> Option<bool> {
+    let entry = lookup_idcode(idcode)?;
+    let base = device_base(device)?;
+    Some(entry.devices.iter().any(|d| *d == base))
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ChainDevice {
+    pub position: u32,
+    pub idcode: String,
+    // Gowin 以外（CPU の DAP など）は false。family / part は None
+    pub gowin: bool,
+    pub family: Option<String>,
+    pub part: Option<String>,
+}
+
+// JTAG チェーンのスキャン結果。IDCODE の出現順をチェーン上の位置（0 始まり）とみなす。
+// 位置がずれないよう他社のデバイスも残す。"IDCODE" などの見出しがある行はメーカーを
+// 問わず JTAG の IDCODE（bit 0 が 1）を拾い、見出しのない行は Gowin のものだけ拾う。
+// stdout と stderr に同じ行が出ることがあるので、まったく同じ行は 1 回だけ数える
+pub fn parse_chain(text: &str) -> Vec<ChainDevice> {
+    let mut seen = std::collections::HashSet::new();
+    let mut idcodes = Vec::new();
+    for line in text.lines() {
+        let l = line.trim();
+        if l.is_empty() || !seen.insert(l) {
+            continue;
+        }
+        let compact: String = l
+            .chars()
+            .filter(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        let labeled = ["idcode", "deviceid", "devicecode"]
+            .iter()
+            .any(|k| compact.contains(k));
+        idcodes.extend(
+            hex_words(l)
+                .into_iter()
+                .filter(|v| is_gowin(*v) || (labeled && v & 1 == 1)),
+        );
+    }
+    idcodes
+        .into_iter()
+        .enumerate()
+        .map(|(i, idcode)| {
+            let entry = lookup_idcode(idcode);
+            ChainDevice {
+                position: i as u32,
+                idcode: format_idcode(idcode),
+                gowin: is_gowin(idcode),
+                family: entry.map(|e| e.family.to_string()),
+                part: entry.map(|e| e.devices.join("/")),
+            }
+        })
+        .collect()
+}
+
+// デバイスの目録（programmer_cli の --device に渡す名前）。リソースはデータシートの値
+pub struct Part {
+    pub name: &'static str,
+    pub family: &'static str,
+    pub luts: u32,
+    pub bsram_kbits: u32,
+    pub plls: u32,
+}
+
+const fn part(
+    name: &'static str,
+    family: &'static str,
+    luts: u32,
+    bsram_kbits: u32,
+    plls: u32,
+) -> Part {
+    Part {
+        name,
+        family,
+        luts,
+        bsram_kbits,
+        plls,
+    }
+}
+
+pub const PARTS: &[Part] = &[
+    part("GW1N-1", "GW1N", 1152, 72, 1),
+    part("GW1N-1P5", "GW1N", 1584, 72, 1),
+    part("GW1N-1P5C", "GW1N", 1584, 72, 1),
+    part("GW1N-2", "GW1N", 2304, 72, 1),
+    part("GW1N-2B", "GW1N", 2304, 72, 1),
+    part("GW1N-4", "GW1N", 4608, 180, 2),
+    part("GW1N-4B", "GW1N", 4608, 180, 2),
+    part("GW1N-4D", "GW1N", 4608, 180, 2),
+    part("GW1N-9", "GW1N", 8640, 468, 2),
+    part("GW1N-9C", "GW1N", 8640, 468, 2),
+    part("GW1NR-4", "GW1NR", 4608, 180, 2),
+    part("GW1NR-4B", "GW1NR", 4608, 180, 2),
+    part("GW1NR-4C", "GW1NR", 4608, 180, 2),
+    part("GW1NR-9", "GW1NR", 8640, 468, 2),
+    part("GW1NR-9C", "GW1NR", 8640, 468, 2),
+    part("GW1NZ-1", "GW1NZ", 1152, 72, 1),
+    part("GW1NZ-1C", "GW1NZ", 1152, 72, 1),
+    part("GW1NS-4", "GW1NS", 4608, 180, 2),
+    part("GW1NS-4C", "GW1NS", 4608, 180, 2),
+    part("GW1NSR-4", "GW1NSR", 4608, 180, 2),
+    part("GW1NSR-4C", "GW1NSR", 4608, 180, 2),
+    part("GW1NSER-4C", "GW1NSER", 4608, 180, 2),
+    part("GW2A-18", "GW2A", 20736, 828, 4),
+    part("GW2A-18C", "GW2A", 20736, 828, 4),
+    part("GW2AR-18", "GW2AR", 20736, 828, 4),
+    part("GW2AR-18C", "GW2AR", 20736, 828, 4),
+    part("GW2A-55", "GW2A", 54720, 2520, 6),
+    part("GW2A-55C", "GW2A", 54720, 2520, 6),
+    part("GW2AN-55C", "GW2AN", 54720, 2520, 6),
+    part("GW5A-25A", "GW5A", 23040, 1008, 6),
+    part("GW5A-138B", "GW5A", 138240, 6120, 12),
+    part("GW5AT-60B", "GW5AT", 59904, 2124, 8),
+    part("GW5AT-138B", "GW5AT", 138240, 6120, 12),
+    part("GW5AST-138B", "GW5AST", 138240, 6120, 12),
+    part("GW5AST-138C", "GW5AST", 138240, 6120, 12),
+];
+
+// programmer_cli の --frequency で選べる JTAG クロック
+pub const FREQUENCIES: &[&str] = &[
+    "2.5MHz", "5MHz", "6MHz", "10MHz", "12MHz", "15MHz", "20MHz", "30MHz",
+];
+
+// 系列ごとの JTAG クロックの上限（kHz）。長い名前から順に前方一致で引く。
+// 低電力の GW1NZ と、GW1N / GW2A 系は GW5A 系ほど TCK を上げられない
+const FAMILY_MAX_KHZ: &[(&str, u32)] = &[
+    ("GW1NZ", 10_000),
+    ("GW1NSR", 15_000),
+    ("GW1NS", 15_000),
+    ("GW1NR", 15_000),
+    ("GW1N", 15_000),
+    ("GW2AR", 20_000),
+    ("GW2AN", 20_000),
+    ("GW2A", 20_000),
+    ("GW5", 30_000),
+];
+
+// "2.5MHz" → 2500
+fn frequency_khz(frequency: &str) -> Option<u32> {
+    let mhz: f64 = frequency.strip_suffix("MHz")?.parse().ok()?;
+    Some((mhz * 1000.0).round() as u32)
+}
+
+// 空白を除き、大文字小文字を目録の表記にそろえる（"15 mhz" → "15MHz"）。目録になければ None
+pub fn normalize_frequency(frequency: &str) -> Option<&'static str> {
+    let compact: String = frequency.split_whitespace().collect();
+    FREQUENCIES
+        .iter()
+        .find(|f| f.eq_ignore_ascii_case(&compact))
+        .copied()
+}
+
+// device で選べる JTAG クロック。系列が分からない名前（extra_devices など）はすべて
+pub fn frequencies_for(device: &str) -> Vec<&'static str> {
+    let upper = device.trim().to_ascii_uppercase();
+    let family = find_part(device).map(|p| p.family).unwrap_or(&upper);
+    let max = FAMILY_MAX_KHZ
+        .iter()
+        .find(|(prefix, _)| family.starts_with(prefix))
+        .map(|(_, khz)| *khz);
+    FREQUENCIES
+        .iter()
+        .filter(|f| max.is_none_or(|max| frequency_khz(f).is_some_and(|khz| khz <= max)))
+        .copied()
+        .collect()
+}
+
+pub fn find_part(name: &str) -> Option<&'static Part> {
+    PARTS
+        .iter()
+        .find(|p| p.name.eq_ignore_ascii_case(name.trim()))
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().collect();
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for i in 1..=a.len() {
+        let mut prev = row[0];
+        row[0] = i;
+        for j in 1..=b.len() {
+            let cur = row[j];
+            row[j] = (row[j] + 1)
+                .min(row[j - 1] + 1)
+                .min(prev + usize::from(a[i - 1] != b[j - 1]));
+            prev = cur;
+        }
+    }
+    row[b.len()]
+}
+
+// 近い候補（"did you mean"）。同じ系列（device_base）のものと、編集距離の近いもの
+pub fn suggest<'a>(input: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<String> {
+    let upper = input.trim().to_uppercase();
+    let base = device_base(&upper);
+    let mut scored: Vec<(bool, usize, &str)> = candidates
+        .into_iter()
+        .filter_map(|c| {
+            let d = edit_distance(&upper, &c.to_uppercase());
+            let same_base = base.is_some() && device_base(c) == base;
+            (same_base || d <= 2.max(upper.len() / 4)).then_some((!same_base, d, c))
+        })
+        .collect();
+    scored.sort();
+    scored
+        .into_iter()
+        .take(3)
+        .map(|(_, _, c)| c.to_string())
+        .collect()
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PartInfo {
+    pub name: String,
+    pub family: String,
+    // "GW1NR-9"（IDCODE・ピン配置データベースの単位）
+    pub base: Option<String>,
+    pub luts: u32,
+    pub bsram_kbits: u32,
+    pub plls: u32,
+    pub idcodes: Vec<String>,
+    // このデバイスで選べる JTAG クロック
+    pub frequencies: Vec<String>,
+}
+
+pub fn part_info(part: &Part) -> PartInfo {
+    let base = device_base(part.name);
+    PartInfo {
+        name: part.name.to_string(),
+        family: part.family.to_string(),
+        idcodes: IDCODES
+            .iter()
+            .filter(|e| base.as_deref().is_some_and(|b| e.devices.contains(&b)))
+            .map(|e| format_idcode(e.idcode))
+            .collect(),
+        frequencies: frequencies_for(part.name)
+            .into_iter()
+            .map(str::to_string)
+            .collect(),
+        base,
+        luts: part.luts,
+        bsram_kbits: part.bsram_kbits,
+        plls: part.plls,
+    }
+}
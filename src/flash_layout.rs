@@ -0,0 +1,250 @@
+// マルチブート（ゴールデン/アップデート）向けフラッシュレイアウトの組み立て
+//
+// レイアウトは TOML（拡張子 .yaml / .yml なら同じ構造の YAML）:
+//
+//   flash_size = "4M"
+//   alignment = "64K"
+//   device = "GW5A-25A"
+//
+//   [[partitions]]
+//   name = "golden"
+//   file = "golden/impl/pnr/golden.fs"
+//   offset = 0x000000
+//   size = "1M"
+//
+//   # YAML の場合
+//   flash_size: 4M
+//   partitions:
+//     - { name: golden, file: golden/impl/pnr/golden.fs, offset: 0x000000, size: 1M }
+
+use std::path::Path;
+
+use anyhow::{Context, Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{bitstream, image};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(untagged)]
+pub enum SizeValue {
+    Int(u64),
+    Str(String),
+}
+
+impl SizeValue {
+    fn bytes(&self) -> Result<u64> {
+        match self {
+            Self::Int(n) => Ok(*n),
+            Self::Str(s) => image::parse_size(s),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct FlashLayout {
+    pub flash_size: SizeValue,
+    pub alignment: Option<SizeValue>,
+    pub fill: Option<u8>,
+    pub device: Option<String>,
+    pub pad_to_flash: Option<bool>,
+    pub target: Option<String>,
+    pub partitions: Vec<PartitionSpec>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct PartitionSpec {
+    pub name: String,
+    pub file: String,
+    pub offset: SizeValue,
+    pub size: Option<SizeValue>,
+    // bitstream (.fs) / raw。未指定なら拡張子で判断
+    pub kind: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PartitionReport {
+    pub name: String,
+    pub kind: String,
+    pub file: String,
+    pub offset: u64,
+    pub size: Option<u64>,
+    pub used: u64,
+    pub end: u64,
+    pub crc32: String,
+    pub idcode: Option<String>,
+}
+
+pub struct Assembled {
+    pub flash_size: u64,
+    pub image: Vec<u8>,
+    pub partitions: Vec<PartitionReport>,
+    pub issues: Vec<String>,
+}
+
+pub fn load(path: &Path) -> Result<FlashLayout> {
+    let text =
+        std::fs::read_to_string(path).with_context(|| format!("read({})", path.display()))?;
+    let yaml = path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("yaml") || e.eq_ignore_ascii_case("yml"));
+    let parsed = if yaml {
+        serde_yaml_ng::from_str(&text).map_err(|e| e.to_string())
+    } else {
+        toml::from_str(&text).map_err(|e| e.to_string())
+    };
+    parsed.map_err(|e| anyhow!("レイアウト {} を解釈できません: {e}", path.display()))
+}
+
+// base_dir はレイアウト内の相対パスの基準
+pub fn assemble(layout: &FlashLayout, base_dir: &Path) -> Result<Assembled> {
+    let flash_size = layout.flash_size.bytes()?;
+    if flash_size > image::MAX_FLASH_SIZE {
+        return Err(anyhow!(
+            "flash_size 0x{flash_size:X} が大きすぎます（上限 0x{:X}）",
+            image::MAX_FLASH_SIZE
+        ));
+    }
+    let alignment = layout
+        .alignment
+        .as_ref()
+        .map(SizeValue::bytes)
+        .transpose()?
+        .unwrap_or(1)
+        .max(1);
+    let fill = layout.fill.unwrap_or(0xFF);
+
+    let mut issues = Vec::new();
+    let mut reports = Vec::new();
+    let mut contents: Vec<Vec<u8>> = Vec::new();
+
+    for p in &layout.partitions {
+        let path = crate::resolve_under(base_dir, &p.file);
+        let offset = p.offset.bytes()?;
+        let size = p.size.as_ref().map(SizeValue::bytes).transpose()?;
+        let kind = match p.kind.as_deref() {
+            Some(k) => k.to_lowercase(),
+            None if path
+                .extension()
+                .is_some_and(|e| e.eq_ignore_ascii_case("fs")) =>
+            {
+                "bitstream".into()
+            }
+            None => "raw".into(),
+        };
+
+        let (data, idcode) = match kind.as_str() {
+            "bitstream" => {
+                let text = std::fs::read_to_string(&path)
+                    .with_context(|| format!("read({})", path.display()))?;
+                let bs = bitstream::parse_fs(&text)
+                    .with_context(|| format!("パーティション {} の .fs", p.name))?;
+                if let Some(device) = layout.device.as_deref() {
+                    for reason in bitstream::device_mismatches(&bs, device) {
+                        issues.push(format!("{}: {reason}", p.name));
+                    }
+                }
+                (bs.data, bs.info.idcode)
+            }
+            "raw" => (
+                std::fs::read(&path).with_context(|| format!("read({})", path.display()))?,
+                None,
+            ),
+            other => {
+                return Err(anyhow!(
+                    "パーティション {} の kind は bitstream / raw のいずれかです（指定: {other}）",
+                    p.name
+                ));
+            }
+        };
+
+        let used = data.len() as u64;
+        if offset % alignment != 0 {
+            issues.push(format!(
+                "{}: offset 0x{offset:X} が alignment 0x{alignment:X} に揃っていません",
+                p.name
+            ));
+        }
+        if let Some(size) = size {
+            if used > size {
+                issues.push(format!(
+                    "{}: 内容 {used} bytes がパーティションサイズ {size} bytes を超えています",
+                    p.name
+                ));
+            }
+            if size % alignment != 0 {
+                issues.push(format!(
+                    "{}: size 0x{size:X} が alignment 0x{alignment:X} の倍数ではありません",
+                    p.name
+                ));
+            }
+        }
+        let end = offset
+            .checked_add(size.unwrap_or(used).max(used))
+            .ok_or_else(|| {
+                anyhow!(
+                    "パーティション {} の offset 0x{offset:X} が大きすぎます",
+                    p.name
+                )
+            })?;
+        if end > flash_size {
+            issues.push(format!(
+                "{}: 0x{offset:X}..0x{end:X} が flash_size 0x{flash_size:X} を超えています",
+                p.name
+            ));
+        }
+
+        reports.push(PartitionReport {
+            name: p.name.clone(),
+            kind,
+            file: path.display().to_string(),
+            offset,
+            size,
+            used,
+            end,
+            crc32: format!("{:08x}", bitstream::crc32(&data)),
+            idcode,
+        });
+        contents.push(data);
+    }
+
+    for (i, a) in reports.iter().enumerate() {
+        for b in reports.iter().skip(i + 1) {
+            if a.name == b.name {
+                issues.push(format!("パーティション名 {} が重複しています", a.name));
+            }
+            if a.offset < b.end && b.offset < a.end {
+                issues.push(format!(
+                    "{} (0x{:X}..0x{:X}) と {} (0x{:X}..0x{:X}) が重なっています",
+                    a.name, a.offset, a.end, b.name, b.offset, b.end
+                ));
+            }
+        }
+    }
+
+    let image_len = if layout.pad_to_flash.unwrap_or(false) {
+        flash_size
+    } else {
+        reports
+            .iter()
+            // end は checked_add 済みなので offset + used はあふれない
+            .map(|r| r.offset + r.used)
+            .max()
+            .unwrap_or(0)
+            .min(flash_size)
+    };
+
+    let mut image = vec![fill; image_len as usize];
+    if issues.is_empty() {
+        for (r, data) in reports.iter().zip(&contents) {
+            image[r.offset as usize..(r.offset + r.used) as usize].copy_from_slice(data);
+        }
+    }
+
+    Ok(Assembled {
+        flash_size,
+        image,
+        partitions: reports,
+        issues,
+    })
+}
@@ -0,0 +1,679 @@
+// プロジェクトの git 情報
+//
+// - commit / branch: .git を直接読む。HEAD → refs/heads/<branch>（loose ref、なければ packed-refs）
+// - dirty: git コマンドは使わず（すべての実行で呼ぶので、遅い・止まった git を待たない）、
+//   .git/index を HEAD のツリーと作業ツリーの両方と比べる。
+//   - HEAD とは .git/objects（loose と pack）から読んだツリーの blob の SHA-1 とモードで比べる
+//     （ステージ済みの変更）。オブジェクトが読めなければこの比較は省く
+//   - 作業ツリーとはサイズと mtime が index と同じなら変更なし、違えば blob の SHA-1 を計算して比べる
+// - 未追跡のファイルは、プロジェクトのソース（config の sources / .gprj の一覧）に入っているものだけ数える
+// - diff_hash: 変更のあったファイル（パスと現在の内容）の sha256
+//
+// 対象は project_root 以下（このサーバが書く .gowin-mcp/ は除く）。
+
+use std::{
+    collections::{BTreeMap, HashSet},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256};
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct GitInfo {
+    pub commit: Option<String>,
+    // detached HEAD なら None
+    pub branch: Option<String>,
+    pub dirty: Option<bool>,
+    pub diff_hash: Option<String>,
+    pub changed_files: Vec<String>,
+}
+
+struct Repo {
+    // 作業ツリーの最上位
+    top: PathBuf,
+    git_dir: PathBuf,
+}
+
+struct IndexEntry {
+    path: String,
+    mtime_sec: u32,
+    mtime_nsec: u32,
+    mode: u32,
+    size: u32,
+    sha1: [u8; 20],
+    stage: u16,
+    skip_worktree: bool,
+}
+
+// start から親へ辿って .git を探す（worktree / submodule の "gitdir: ..." ファイルにも対応）
+fn find_repo(start: &Path) -> Option<Repo> {
+    let mut current = Some(start);
+    while let Some(dir) = current {
+        let dot_git = dir.join(".git");
+        if dot_git.is_dir() {
+            return Some(Repo {
+                top: dir.to_path_buf(),
+                git_dir: dot_git,
+            });
+        }
+        if dot_git.is_file() {
+            let text = std::fs::read_to_string(&dot_git).ok()?;
+            let target = text.trim().strip_prefix("gitdir:")?.trim();
+            return Some(Repo {
+                top: dir.to_path_buf(),
+                git_dir: crate::resolve_under(dir, target),
+            });
+        }
+        current = dir.parent();
+    }
+    None
+}
+
+// worktree の .git/worktrees/<name> なら、refs は commondir 側にある
+fn common_dir(git_dir: &Path) -> PathBuf {
+    match std::fs::read_to_string(git_dir.join("commondir")) {
+        Ok(text) => crate::resolve_under(git_dir, text.trim()),
+        Err(_) => git_dir.to_path_buf(),
+    }
+}
+
+fn resolve_ref(git_dir: &Path, name: &str) -> Option<String> {
+    for dir in [git_dir.to_path_buf(), common_dir(git_dir)] {
+        if let Ok(text) = std::fs::read_to_string(dir.join(name)) {
+            return Some(text.trim().to_string());
+        }
+        if let Ok(packed) = std::fs::read_to_string(dir.join("packed-refs")) {
+            for line in packed.lines() {
+                if let Some((hash, r)) = line.split_once(' ')
+                    && r.trim() == name
+                {
+                    return Some(hash.to_string());
+                }
+            }
+        }
+    }
+    None
+}
+
+// (commit, branch)
+fn head(git_dir: &Path) -> (Option<String>, Option<String>) {
+    let Ok(text) = std::fs::read_to_string(git_dir.join("HEAD")) else {
+        return (None, None);
+    };
+    let text = text.trim();
+    match text.strip_prefix("ref:") {
+        Some(name) => {
+            let name = name.trim();
+            (
+                resolve_ref(git_dir, name),
+                Some(name.strip_prefix("refs/heads/").unwrap_or(name).to_string()),
+            )
+        }
+        None => (Some(text.to_string()), None),
+    }
+}
+
+fn be32(bytes: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(bytes.get(at..at + 4)?.try_into().ok()?))
+}
+
+fn be16(bytes: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(bytes.get(at..at + 2)?.try_into().ok()?))
+}
+
+// index v4 のパス圧縮で使う可変長整数
+fn varint(bytes: &[u8], pos: &mut usize) -> Option<usize> {
+    let mut b = *bytes.get(*pos)?;
+    *pos += 1;
+    let mut value = (b & 0x7f) as usize;
+    while b & 0x80 != 0 {
+        b = *bytes.get(*pos)?;
+        *pos += 1;
+        value = ((value + 1) << 7) | (b & 0x7f) as usize;
+    }
+    Some(value)
+}
+
+// .git/index（DIRC、v2〜v4）のエントリ
+fn parse_index(bytes: &[u8]) -> Option<Vec<IndexEntry>> {
+    if bytes.get(..4)? != b"DIRC" {
+        return None;
+    }
+    let version = be32(bytes, 4)?;
+    if !(2..=4).contains(&version) {
+        return None;
+    }
+    let count = be32(bytes, 8)? as usize;
+    let mut entries = Vec::with_capacity(count);
+    let mut pos = 12;
+    let mut previous: Vec<u8> = Vec::new();
+    for _ in 0..count {
+        let start = pos;
+        let sha1: [u8; 20] = bytes.get(start + 40..start + 60)?.try_into().ok()?;
+        let flags = be16(bytes, start + 60)?;
+        pos = start + 62;
+        let mut skip_worktree = false;
+        if version >= 3 && flags & 0x4000 != 0 {
+            skip_worktree = be16(bytes, pos)? & 0x4000 != 0;
+            pos += 2;
+        }
+        let path = if version == 4 {
+            let strip = varint(bytes, &mut pos)?;
+            let end = pos + bytes.get(pos..)?.iter().position(|&b| b == 0)?;
+            let mut path = previous[..previous.len().checked_sub(strip)?].to_vec();
+            path.extend_from_slice(&bytes[pos..end]);
+            pos = end + 1;
+            path
+        } else {
+            let end = pos + bytes.get(pos..)?.iter().position(|&b| b == 0)?;
+            let path = bytes[pos..end].to_vec();
+            // エントリ全体が 8 バイト境界になるよう NUL で埋められている
+            pos = start + (end - start + 8) / 8 * 8;
+            path
+        };
+        entries.push(IndexEntry {
+            mtime_sec: be32(bytes, start + 8)?,
+            mtime_nsec: be32(bytes, start + 12)?,
+            mode: be32(bytes, start + 24)?,
+            size: be32(bytes, start + 36)?,
+            sha1,
+            stage: (flags >> 12) & 0x3,
+            skip_worktree,
+            path: String::from_utf8_lossy(&path).to_string(),
+        });
+        previous = path;
+    }
+    Some(entries)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+// git の blob オブジェクトとしての SHA-1（"blob <len>\0" + 内容）
+fn blob_sha1(content: &[u8]) -> [u8; 20] {
+    let mut hasher = Sha1::new();
+    hasher.update(format!("blob {}\0", content.len()).as_bytes());
+    hasher.update(content);
+    hasher.finalize().into()
+}
+
+fn worktree_content(path: &Path, mode: u32) -> Option<Vec<u8>> {
+    if mode & 0o170000 == 0o120000 {
+        let target = std::fs::read_link(path).ok()?;
+        Some(target.to_string_lossy().as_bytes().to_vec())
+    } else {
+        std::fs::read(path).ok()
+    }
+}
+
+// 作業ツリーの内容が index と違えば現在の blob の SHA-1（削除なら None）を返す。
+// index 自体と同じ時刻以降に更新されたエントリは stat を信用せず内容を比べる（racy git）
+fn changed(top: &Path, entry: &IndexEntry, index_mtime: (u32, u32)) -> Option<Option<[u8; 20]>> {
+    let path = top.join(&entry.path);
+    let Ok(meta) = std::fs::symlink_metadata(&path) else {
+        return Some(None);
+    };
+    let mtime = meta
+        .modified()
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok());
+    let same_stat = mtime.is_some_and(|m| {
+        m.as_secs() as u32 == entry.mtime_sec && m.subsec_nanos() == entry.mtime_nsec
+    }) && meta.len() as u32 == entry.size
+        && (entry.mtime_sec, entry.mtime_nsec) < index_mtime;
+    if same_stat {
+        return None;
+    }
+    let content = worktree_content(&path, entry.mode)?;
+    let sha1 = blob_sha1(&content);
+    (sha1 != entry.sha1).then_some(Some(sha1))
+}
+
+// project_root 基準のソースのパス（未追跡ファイルの判定用）
+fn source_paths(project_root: &Path) -> Vec<String> {
+    crate::stale::project_sources(project_root)
+        .map(|(_, files)| {
+            files
+                .iter()
+                .filter_map(|p| p.strip_prefix(project_root).ok())
+                .map(|p| p.to_string_lossy().replace('\\', "/"))
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+// 現在の内容の blob SHA-1（削除済みなら "deleted"）
+fn worktree_state(path: &Path) -> String {
+    let Ok(meta) = std::fs::symlink_metadata(path) else {
+        return "deleted".into();
+    };
+    if meta.is_dir() {
+        // サブモジュールの変更
+        return "directory".into();
+    }
+    let mode = if meta.file_type().is_symlink() {
+        0o120000
+    } else {
+        0o100644
+    };
+    worktree_content(path, mode)
+        .map(|c| hex(&blob_sha1(&c)))
+        .unwrap_or_else(|| "unreadable".into())
+}
+
+// ステージ済みの変更を見るため、HEAD のツリーを .git/objects（loose と pack）から読む
+struct Objects {
+    dirs: Vec<PathBuf>,
+    packs: Vec<Pack>,
+}
+
+struct Pack {
+    // .idx（v2）の中身
+    idx: Vec<u8>,
+    count: usize,
+    path: PathBuf,
+}
+
+const OBJ_COMMIT: u8 = 1;
+const OBJ_TREE: u8 = 2;
+const OBJ_OFS_DELTA: u8 = 6;
+const OBJ_REF_DELTA: u8 = 7;
+// delta の連鎖が壊れていても止まるように
+const MAX_DELTA_DEPTH: usize = 512;
+
+impl Objects {
+    fn open(git_dir: &Path) -> Self {
+        let mut dirs = vec![common_dir(git_dir).join("objects")];
+        if let Ok(text) = std::fs::read_to_string(dirs[0].join("info").join("alternates")) {
+            let base = dirs[0].clone();
+            dirs.extend(
+                text.lines()
+                    .map(str::trim)
+                    .filter(|l| !l.is_empty() && !l.starts_with('#'))
+                    .map(|l| crate::resolve_under(&base, l)),
+            );
+        }
+        let mut packs = Vec::new();
+        for dir in &dirs {
+            let Ok(read) = std::fs::read_dir(dir.join("pack")) else {
+                continue;
+            };
+            for path in read.filter_map(|e| e.ok()).map(|e| e.path()) {
+                if path.extension().is_some_and(|e| e == "idx")
+                    && let Ok(idx) = std::fs::read(&path)
+                    && idx.get(..8) == Some(&[0xff, b't', b'O', b'c', 0, 0, 0, 2][..])
+                    && let Some(count) = be32(&idx, 8 + 255 * 4)
+                {
+                    packs.push(Pack {
+                        idx,
+                        count: count as usize,
+                        path: path.with_extension("pack"),
+                    });
+                }
+            }
+        }
+        Self { dirs, packs }
+    }
+
+    // (種類, 中身)
+    fn read(&self, sha1: &[u8; 20], depth: usize) -> Option<(u8, Vec<u8>)> {
+        if depth > MAX_DELTA_DEPTH {
+            return None;
+        }
+        let name = hex(sha1);
+        for dir in &self.dirs {
+            if let Ok(bytes) = std::fs::read(dir.join(&name[..2]).join(&name[2..])) {
+                return parse_loose(&bytes);
+            }
+        }
+        self.packs.iter().find_map(|pack| {
+            let offset = pack.find(sha1)?;
+            self.read_packed(pack, offset, depth)
+        })
+    }
+
+    fn read_packed(&self, pack: &Pack, offset: u64, depth: usize) -> Option<(u8, Vec<u8>)> {
+        if depth > MAX_DELTA_DEPTH {
+            return None;
+        }
+        let head = read_at(&pack.path, offset, 64)?;
+        let mut pos = 0;
+        let mut c = *head.first()?;
+        let kind = (c >> 4) & 0x7;
+        let mut size = (c & 0x0f) as usize;
+        let mut shift = 4;
+        while c & 0x80 != 0 {
+            pos += 1;
+            c = *head.get(pos)?;
+            size |= ((c & 0x7f) as usize).checked_shl(shift)?;
+            shift += 7;
+        }
+        pos += 1;
+        let base = match kind {
+            OBJ_OFS_DELTA => {
+                let mut c = *head.get(pos)?;
+                pos += 1;
+                let mut back = (c & 0x7f) as u64;
+                while c & 0x80 != 0 {
+                    c = *head.get(pos)?;
+                    pos += 1;
+                    back = ((back + 1) << 7) | (c & 0x7f) as u64;
+                }
+                Some(self.read_packed(pack, offset.checked_sub(back)?, depth + 1)?)
+            }
+            OBJ_REF_DELTA => {
+                let sha1: [u8; 20] = head.get(pos..pos + 20)?.try_into().ok()?;
+                pos += 20;
+                Some(self.read(&sha1, depth + 1)?)
+            }
+            _ => None,
+        };
+        // 圧縮後が元より大きくなるのは zlib のヘッダと無圧縮ブロックの分だけ
+        let compressed = read_at(&pack.path, offset + pos as u64, size + size / 16_000 + 64)?;
+        let data =
+            miniz_oxide::inflate::decompress_to_vec_zlib_with_limit(&compressed, size).ok()?;
+        match base {
+            Some((base_kind, base_data)) => Some((base_kind, apply_delta(&base_data, &data)?)),
+            None => Some((kind, data)),
+        }
+    }
+}
+
+impl Pack {
+    // .idx の SHA-1 表を二分探索して pack 内のオフセットを返す
+    fn find(&self, sha1: &[u8; 20]) -> Option<u64> {
+        let fanout = |i: usize| be32(&self.idx, 8 + i * 4).map(|n| n as usize);
+        let first = sha1[0] as usize;
+        let mut lo = if first == 0 { 0 } else { fanout(first - 1)? };
+        let mut hi = fanout(first)?;
+        let names = 8 + 256 * 4;
+        while lo < hi {
+            let mid = (lo + hi) / 2;
+            let at = names + mid * 20;
+            match self.idx.get(at..at + 20)?.cmp(&sha1[..]) {
+                std::cmp::Ordering::Less => lo = mid + 1,
+                std::cmp::Ordering::Greater => hi = mid,
+                std::cmp::Ordering::Equal => {
+                    let offsets = names + self.count * 24;
+                    let small = be32(&self.idx, offsets + mid * 4)?;
+                    if small & 0x8000_0000 == 0 {
+                        return Some(small as u64);
+                    }
+                    let at = offsets + self.count * 4 + (small & 0x7fff_ffff) as usize * 8;
+                    return Some(u64::from_be_bytes(
+                        self.idx.get(at..at + 8)?.try_into().ok()?,
+                    ));
+                }
+            }
+        }
+        None
+    }
+}
+
+fn read_at(path: &Path, offset: u64, len: usize) -> Option<Vec<u8>> {
+    use std::io::{Read, Seek, SeekFrom};
+    let mut file = std::fs::File::open(path).ok()?;
+    file.seek(SeekFrom::Start(offset)).ok()?;
+    let mut buf = Vec::with_capacity(len);
+    file.take(len as u64).read_to_end(&mut buf).ok()?;
+    Some(buf)
+}
+
+// "<種類> <長さ>\0<中身>" を zlib で圧縮したもの
+fn parse_loose(bytes: &[u8]) -> Option<(u8, Vec<u8>)> {
+    let data = miniz_oxide::inflate::decompress_to_vec_zlib(bytes).ok()?;
+    let nul = data.iter().position(|&b| b == 0)?;
+    let kind = match data[..nul].split(|&b| b == b' ').next()? {
+        b"commit" => OBJ_COMMIT,
+        b"tree" => OBJ_TREE,
+        b"blob" => 3,
+        b"tag" => 4,
+        _ => return None,
+    };
+    Some((kind, data[nul + 1..].to_vec()))
+}
+
+// pack の delta（元と結果の長さのあと、コピーと挿入の命令が並ぶ）を base に当てる
+fn apply_delta(base: &[u8], delta: &[u8]) -> Option<Vec<u8>> {
+    let size = |pos: &mut usize| -> Option<usize> {
+        let mut value = 0usize;
+        let mut shift = 0;
+        loop {
+            let b = *delta.get(*pos)?;
+            *pos += 1;
+            value |= ((b & 0x7f) as usize).checked_shl(shift)?;
+            shift += 7;
+            if b & 0x80 == 0 {
+                return Some(value);
+            }
+        }
+    };
+    let mut pos = 0;
+    if size(&mut pos)? != base.len() {
+        return None;
+    }
+    let target_len = size(&mut pos)?;
+    let mut out = Vec::with_capacity(target_len);
+    while pos < delta.len() {
+        let op = delta[pos];
+        pos += 1;
+        if op & 0x80 != 0 {
+            let mut field = |bits: std::ops::Range<u32>| -> Option<usize> {
+                let mut value = 0usize;
+                for bit in bits.clone() {
+                    if op & (1 << bit) != 0 {
+                        value |= (*delta.get(pos)? as usize) << ((bit - bits.start) * 8);
+                        pos += 1;
+                    }
+                }
+                Some(value)
+            };
+            let start = field(0..4)?;
+            let len = match field(4..7)? {
+                0 => 0x10000,
+                n => n,
+            };
+            out.extend_from_slice(base.get(start..start.checked_add(len)?)?);
+        } else if op != 0 {
+            out.extend_from_slice(delta.get(pos..pos + op as usize)?);
+            pos += op as usize;
+        } else {
+            return None;
+        }
+    }
+    (out.len() == target_len).then_some(out)
+}
+
+fn parse_sha1(text: &str) -> Option<[u8; 20]> {
+    let text = text.trim();
+    if text.len() != 40 {
+        return None;
+    }
+    let mut out = [0u8; 20];
+    for (i, b) in out.iter_mut().enumerate() {
+        *b = u8::from_str_radix(text.get(i * 2..i * 2 + 2)?, 16).ok()?;
+    }
+    Some(out)
+}
+
+// ツリーの (モード, 名前, SHA-1) の並び
+fn tree_entries(data: &[u8]) -> Option<Vec<(u32, String, [u8; 20])>> {
+    let mut entries = Vec::new();
+    let mut pos = 0;
+    while pos < data.len() {
+        let space = pos + data[pos..].iter().position(|&b| b == b' ')?;
+        let nul = space + data[space..].iter().position(|&b| b == 0)?;
+        let mode = u32::from_str_radix(std::str::from_utf8(&data[pos..space]).ok()?, 8).ok()?;
+        let name = String::from_utf8_lossy(&data[space + 1..nul]).to_string();
+        let sha1: [u8; 20] = data.get(nul + 1..nul + 21)?.try_into().ok()?;
+        entries.push((mode, name, sha1));
+        pos = nul + 21;
+    }
+    Some(entries)
+}
+
+// HEAD のツリーのうち prefix 以下のファイル（リポジトリ基準のパス → (モード, SHA-1)）。
+// まだコミットが無ければ空。オブジェクトが読めなければ None
+fn head_files(
+    objects: &Objects,
+    commit: Option<&str>,
+    prefix: &str,
+) -> Option<BTreeMap<String, (u32, [u8; 20])>> {
+    let mut files = BTreeMap::new();
+    let Some(commit) = commit else {
+        return Some(files);
+    };
+    let (kind, body) = objects.read(&parse_sha1(commit)?, 0)?;
+    if kind != OBJ_COMMIT {
+        return None;
+    }
+    let tree = String::from_utf8_lossy(&body)
+        .lines()
+        .next()?
+        .strip_prefix("tree ")
+        .and_then(parse_sha1)?;
+
+    let mut tree = Some(tree);
+    for part in prefix.split('/').filter(|p| !p.is_empty()) {
+        let Some(sha1) = tree else { break };
+        let (_, data) = objects.read(&sha1, 0)?;
+        tree = tree_entries(&data)?
+            .into_iter()
+            .find(|(mode, name, _)| *mode == 0o40000 && name == part)
+            .map(|(_, _, sha1)| sha1);
+    }
+    let mut pending: Vec<(String, [u8; 20])> =
+        tree.map(|t| (prefix.to_string(), t)).into_iter().collect();
+    while let Some((base, sha1)) = pending.pop() {
+        let (kind, data) = objects.read(&sha1, 0)?;
+        if kind != OBJ_TREE {
+            return None;
+        }
+        for (mode, name, sha1) in tree_entries(&data)? {
+            if mode == 0o40000 {
+                pending.push((format!("{base}{name}/"), sha1));
+            } else {
+                files.insert(format!("{base}{name}"), (mode, sha1));
+            }
+        }
+    }
+    Some(files)
+}
+
+// .git/index と作業ツリーの比較から (project_root 基準のパス, 状態)。index が読めなければ None
+fn index_changes(
+    repo: &Repo,
+    commit: Option<&str>,
+    prefix: &str,
+    sources: &[String],
+) -> Option<Vec<(String, String)>> {
+    let index_path = repo.git_dir.join("index");
+    let entries = std::fs::read(&index_path)
+        .ok()
+        .and_then(|b| parse_index(&b))?;
+    let index_mtime = std::fs::metadata(&index_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+        .map(|d| (d.as_secs() as u32, d.subsec_nanos()))
+        .unwrap_or((0, 0));
+    // HEAD のツリーが読めなければステージ済みの変更は見ない（作業ツリーとの差分だけ）
+    let head = head_files(&Objects::open(&repo.git_dir), commit, prefix);
+    let mut changes: Vec<(String, String)> = Vec::new();
+    for entry in &entries {
+        let Some(rel) = entry.path.strip_prefix(prefix) else {
+            continue;
+        };
+        if rel.starts_with(".gowin-mcp/") {
+            continue;
+        }
+        // 衝突中（stage 1〜3）は同じパスが複数並ぶ
+        if entry.stage != 0 {
+            if !changes.iter().any(|(f, _)| f == rel) {
+                changes.push((rel.to_string(), "conflict".into()));
+            }
+            continue;
+        }
+        // ステージ済み（index が HEAD と違う）
+        if let Some(head) = &head
+            && head.get(&entry.path) != Some(&(entry.mode, entry.sha1))
+        {
+            changes.push((rel.to_string(), worktree_state(&repo.top.join(&entry.path))));
+            continue;
+        }
+        // サブモジュール（gitlink）と sparse checkout の対象外は作業ツリーを見ない
+        if entry.mode == 0o160000 || entry.skip_worktree {
+            continue;
+        }
+        if let Some(now) = changed(&repo.top, entry, index_mtime) {
+            let state = now.map(|h| hex(&h)).unwrap_or_else(|| "deleted".into());
+            changes.push((rel.to_string(), state));
+        }
+    }
+    // HEAD にあって index に無いものは削除をステージ済み
+    if let Some(head) = &head {
+        let listed: HashSet<&str> = entries.iter().map(|e| e.path.as_str()).collect();
+        for path in head.keys().filter(|p| !listed.contains(p.as_str())) {
+            let Some(rel) = path.strip_prefix(prefix) else {
+                continue;
+            };
+            if !rel.starts_with(".gowin-mcp/") {
+                changes.push((rel.to_string(), worktree_state(&repo.top.join(path))));
+            }
+        }
+    }
+    // index に無いソースは未追跡
+    for rel in sources {
+        let full = format!("{prefix}{rel}");
+        if !entries.iter().any(|e| e.path == full) {
+            let path = repo.top.join(&full);
+            if path.is_file() {
+                changes.push((rel.clone(), format!("untracked {}", worktree_state(&path))));
+            }
+        }
+    }
+    Some(changes)
+}
+
+pub fn info(project_root: &Path) -> GitInfo {
+    let Some(repo) = find_repo(project_root) else {
+        return GitInfo::default();
+    };
+    let (commit, branch) = head(&repo.git_dir);
+
+    let prefix = project_root
+        .strip_prefix(&repo.top)
+        .map(|p| {
+            let p = p.to_string_lossy().replace('\\', "/");
+            if p.is_empty() { p } else { format!("{p}/") }
+        })
+        .unwrap_or_default();
+    let sources = source_paths(project_root);
+    let Some(mut changes) = index_changes(&repo, commit.as_deref(), &prefix, &sources) else {
+        return GitInfo {
+            commit,
+            branch,
+            ..GitInfo::default()
+        };
+    };
+    changes.sort();
+    changes.dedup_by(|a, b| a.0 == b.0);
+
+    let mut hasher = Sha256::new();
+    for (rel, state) in &changes {
+        hasher.update(format!("{rel}\0{state}\n").as_bytes());
+    }
+    let dirty = !changes.is_empty();
+    GitInfo {
+        commit,
+        branch,
+        dirty: Some(dirty),
+        diff_hash: dirty.then(|| hex(&hasher.finalize())),
+        changed_files: changes.into_iter().map(|(rel, _)| rel).collect(),
+    }
+}
@@ -0,0 +1,178 @@
+// Gowin プロジェクトファイル（.gprj）の読み取り
+//
+//   <Project>
+//       <Device name="GW5A-25A" pn="GW5A-LV25MG121NC1/I0">gw5a25a-002</Device>
+//       <FileList>
+//           <File path="src/top.v" type="file.verilog" enable="1"/>
+//       </FileList>
+//   </Project>
+//
+// XML パーサは使わず、<File .../> の属性だけを拾う。書き込みは </FileList> の手前への追加だけ。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Context, Result, anyhow};
+
+#[derive(Debug, Clone)]
+pub struct GprjFile {
+    pub path: String,
+    pub enabled: bool,
+}
+
+pub fn parse_files(text: &str) -> Vec<GprjFile> {
+    let mut found = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("<File ") {
+        let after = &rest[start + 6..];
+        let end = after.find('>').unwrap_or(after.len());
+        let tag = &after[..end];
+        if let Some(path) = attr(tag, "path") {
+            found.push(GprjFile {
+                path,
+                enabled: attr(tag, "enable").is_none_or(|v| v != "0"),
+            });
+        }
+        rest = &after[end..];
+    }
+    found
+}
+
+fn attr(tag: &str, name: &str) -> Option<String> {
+    let key = format!("{name}=\"");
+    let mut from = 0;
+    while let Some(pos) = tag[from..].find(&key) {
+        let start = from + pos;
+        from = start + key.len();
+        // "enable" が "xenable" などの途中に一致しないように
+        if tag[..start]
+            .chars()
+            .next_back()
+            .is_some_and(|c| !c.is_whitespace())
+        {
+            continue;
+        }
+        let value = &tag[from..];
+        let end = value.find('"')?;
+        return Some(
+            value[..end]
+                .replace("&amp;", "&")
+                .replace("&quot;", "\"")
+                .replace("&lt;", "<")
+                .replace("&gt;", ">"),
+        );
+    }
+    None
+}
+
+// project_root 直下の .gprj（複数あれば名前順で最初）
+pub fn find(project_root: &Path) -> Option<PathBuf> {
+    let mut found: Vec<PathBuf> = std::fs::read_dir(project_root)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && p.extension().is_some_and(|e| e == "gprj"))
+        .collect();
+    found.sort();
+    found.into_iter().next()
+}
+
+// 有効なファイルの絶対パス（.gprj のあるディレクトリ基準）
+pub fn source_files(gprj: &Path) -> Result<Vec<PathBuf>> {
+    let text =
+        std::fs::read_to_string(gprj).with_context(|| format!("read({})", gprj.display()))?;
+    let base = gprj.parent().unwrap_or(Path::new("."));
+    Ok(parse_files(&text)
+        .into_iter()
+        .filter(|f| f.enabled)
+        .map(|f| crate::resolve_under(base, &f.path))
+        .collect())
+}
+
+// <Device name=".." pn="..">（名前, 品番）
+pub fn device(text: &str) -> Option<(String, Option<String>)> {
+    let start = text.find("<Device ")?;
+    let after = &text[start + 8..];
+    let tag = &after[..after.find('>').unwrap_or(after.len())];
+    Some((attr(tag, "name")?, attr(tag, "pn")))
+}
+
+// 制約ファイル（.sdc / .cst）: .gprj の有効なファイル、なければ project_root/src の下
+pub fn find_constraint(project_root: &Path, extension: &str) -> Option<PathBuf> {
+    let matches = |p: &Path| {
+        p.extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case(extension))
+    };
+    if let Some(gprj) = find(project_root)
+        && let Ok(files) = source_files(&gprj)
+        && let Some(p) = files.into_iter().find(|p| matches(p))
+    {
+        return Some(p);
+    }
+    let mut found: Vec<PathBuf> = std::fs::read_dir(project_root.join("src"))
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file() && matches(p))
+        .collect();
+    found.sort();
+    found.into_iter().next()
+}
+
+// 拡張子 → <File type="..">
+pub fn file_type(path: &str) -> Option<&'static str> {
+    let ext = Path::new(path).extension()?.to_str()?.to_ascii_lowercase();
+    match ext.as_str() {
+        "v" | "sv" => Some("file.verilog"),
+        "vhd" | "vhdl" => Some("file.vhdl"),
+        "cst" => Some("file.cst"),
+        "sdc" => Some("file.sdc"),
+        _ => None,
+    }
+}
+
+fn escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+// .gprj のディレクトリからの相対パス（/ 区切り）を FileList に加える。(新しいテキスト, 追加したパス)
+pub fn add_files(text: &str, paths: &[String]) -> Result<(String, Vec<String>)> {
+    let existing = parse_files(text);
+    let added: Vec<String> = paths
+        .iter()
+        .filter(|p| !existing.iter().any(|f| f.path == **p))
+        .cloned()
+        .collect();
+    if added.is_empty() {
+        return Ok((text.to_string(), added));
+    }
+    let mut lines = String::new();
+    for p in &added {
+        let kind = file_type(p).ok_or_else(|| anyhow!("{p} の種類（type）が分かりません"))?;
+        lines.push_str(&format!(
+            "        <File path=\"{}\" type=\"{kind}\" enable=\"1\"/>\n",
+            escape(p)
+        ));
+    }
+    let out = if let Some(pos) = text.find("</FileList>") {
+        // 閉じタグだけの行ならその行頭に、同じ行に他の要素があれば改行して入れる
+        let line_start = text[..pos].rfind('\n').map_or(0, |i| i + 1);
+        if text[line_start..pos].trim().is_empty() {
+            format!("{}{lines}{}", &text[..line_start], &text[line_start..])
+        } else {
+            format!("{}\n{lines}{}", &text[..pos], &text[pos..])
+        }
+    } else if let Some(pos) = text.find("</Project>") {
+        format!(
+            "{}    <FileList>\n{lines}    </FileList>\n{}",
+            &text[..pos],
+            &text[pos..]
+        )
+    } else {
+        return Err(anyhow!("</Project> がないため .gprj に追加できません"));
+    };
+    Ok((out, added))
+}
@@ -0,0 +1,499 @@
+// HDL ソースからトップモジュールのポートを拾う（制約ファイルの検証用）
+//
+// Verilog / SystemVerilog の module（ANSI / 非 ANSI のポート宣言）と
+// VHDL の entity の port 節だけを見る簡易パーサ。
+// ソースは stale と同じ（config.toml の sources → .gprj）で、どちらもなければ src/ 以下。
+// トップは top_module 指定 → impl/project_process_config.json の Top_Module →
+// 他のモジュールからインスタンス化されていない唯一のモジュール、の順で決める。
+
+use std::path::{Path, PathBuf};
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::stale;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Port {
+    pub name: String,
+    // input / output / inout
+    pub direction: String,
+    // "[7:0]" / "(7 downto 0)" のような範囲（スカラーなら None）
+    pub range: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Module {
+    pub name: String,
+    pub ports: Vec<Port>,
+    pub body: String,
+    pub file: PathBuf,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TopLevel {
+    pub module: String,
+    pub file: String,
+    pub ports: Vec<Port>,
+}
+
+fn is_ident(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '_' || c == '$'
+}
+
+// // と /* */ のコメント、(* *) の属性を空白にする
+pub fn strip_verilog_comments(text: &str) -> String {
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        if let Some(r) = rest.strip_prefix("//") {
+            let end = r.find('\n').unwrap_or(r.len());
+            rest = &r[end..];
+        } else if let Some(r) = rest.strip_prefix("/*") {
+            let end = r.find("*/").map(|i| i + 2).unwrap_or(r.len());
+            rest = &r[end..];
+            out.push(' ');
+        } else if let Some(r) = rest.strip_prefix("(*")
+            && !r.starts_with(')')
+        {
+            let end = r.find("*)").map(|i| i + 2).unwrap_or(r.len());
+            rest = &r[end..];
+            out.push(' ');
+        } else {
+            let c = rest.chars().next().unwrap_or(' ');
+            out.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+    }
+    out
+}
+
+// text[from..] で、単語として現れる word の位置
+fn find_word(text: &str, word: &str, from: usize) -> Option<usize> {
+    let mut at = from;
+    while let Some(pos) = text[at..].find(word) {
+        let start = at + pos;
+        let end = start + word.len();
+        let before = text[..start]
+            .chars()
+            .next_back()
+            .is_none_or(|c| !is_ident(c));
+        let after = text[end..].chars().next().is_none_or(|c| !is_ident(c));
+        if before && after {
+            return Some(start);
+        }
+        at = end;
+    }
+    None
+}
+
+// text[open] が開き括弧のとき、対応する閉じ括弧の位置
+pub fn matching(text: &str, open: usize, left: char, right: char) -> Option<usize> {
+    let mut depth = 0usize;
+    for (i, c) in text[open..].char_indices() {
+        if c == left {
+            depth += 1;
+        } else if c == right {
+            depth -= 1;
+            if depth == 0 {
+                return Some(open + i);
+            }
+        }
+    }
+    None
+}
+
+// 深さ 0 の sep で区切る
+pub fn split_top(text: &str, sep: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '(' | '[' | '{' => depth += 1,
+            ')' | ']' | '}' => depth -= 1,
+            c if c == sep && depth == 0 => {
+                parts.push(&text[start..i]);
+                start = i + c.len_utf8();
+            }
+            _ => {}
+        }
+    }
+    parts.push(&text[start..]);
+    parts
+}
+
+const DIRECTIONS: [&str; 3] = ["input", "output", "inout"];
+const NET_TYPES: [&str; 10] = [
+    "wire", "reg", "logic", "signed", "unsigned", "var", "tri", "bit", "integer", "wand",
+];
+
+// "input wire [7:0] a" → (Some(input), Some([7:0]), a)。方向や範囲がなければ None
+fn parse_port_decl(item: &str) -> Option<(Option<String>, Option<String>, String)> {
+    let item = item.split('=').next().unwrap_or("").trim();
+    let mut direction = None;
+    let mut range = None;
+    let mut name = None;
+    let mut rest = item;
+    while !rest.is_empty() {
+        rest = rest.trim_start();
+        if rest.starts_with('[') {
+            let end = matching(rest, 0, '[', ']')?;
+            // 名前の後ろの [..] はアンパック配列なので範囲にしない
+            if name.is_none() {
+                range = Some(rest[..=end].split_whitespace().collect::<String>());
+            }
+            rest = &rest[end + 1..];
+            continue;
+        }
+        let end = rest
+            .char_indices()
+            .find(|(_, c)| !is_ident(*c))
+            .map(|(i, _)| i)
+            .unwrap_or(rest.len());
+        if end == 0 {
+            rest = &rest[rest.chars().next()?.len_utf8()..];
+            continue;
+        }
+        let word = &rest[..end];
+        if DIRECTIONS.contains(&word) {
+            direction = Some(word.to_string());
+        } else if !NET_TYPES.contains(&word) {
+            name = Some(word.to_string());
+        }
+        rest = &rest[end..];
+    }
+    Some((direction, range, name?))
+}
+
+pub fn parse_verilog(text: &str, file: &Path) -> Vec<Module> {
+    let text = strip_verilog_comments(text);
+    let mut modules = Vec::new();
+    let mut at = 0;
+    while let Some(start) = ["module", "macromodule"]
+        .iter()
+        .filter_map(|k| find_word(&text, k, at))
+        .min()
+    {
+        let keyword_end = start
+            + text[start..]
+                .find(|c: char| !is_ident(c))
+                .unwrap_or(text.len() - start);
+        let Some(end) = find_word(&text, "endmodule", keyword_end) else {
+            break;
+        };
+        let header_and_body = &text[keyword_end..end];
+        at = end + "endmodule".len();
+
+        let trimmed = header_and_body.trim_start();
+        let name: String = trimmed.chars().take_while(|c| is_ident(*c)).collect();
+        if name.is_empty() {
+            continue;
+        }
+        let mut rest_start = keyword_end + (header_and_body.len() - trimmed.len()) + name.len();
+        // #( パラメータ )
+        let after_name = text[rest_start..end].trim_start();
+        if after_name.starts_with('#') {
+            let open = rest_start + text[rest_start..end].find('(').unwrap_or(0);
+            rest_start = matching(&text, open, '(', ')')
+                .map(|i| i + 1)
+                .unwrap_or(open);
+        }
+        let header_end = rest_start + text[rest_start..end].find(';').unwrap_or(0);
+        let header = &text[rest_start..header_end];
+        let body = &text[header_end..end];
+
+        let mut ports: Vec<Port> = Vec::new();
+        if let Some(open) = header.find('(')
+            && let Some(close) = matching(header, open, '(', ')')
+        {
+            let list = &header[open + 1..close];
+            let ansi = DIRECTIONS.iter().any(|d| find_word(list, d, 0).is_some());
+            let mut direction = String::new();
+            let mut range: Option<String> = None;
+            for item in split_top(list, ',') {
+                let Some((dir, r, port)) = parse_port_decl(item) else {
+                    continue;
+                };
+                if ansi {
+                    // 方向を省略した項目は直前の宣言を引き継ぐ
+                    if let Some(d) = dir {
+                        direction = d;
+                        range = r;
+                    } else if r.is_some() {
+                        range = r;
+                    }
+                    ports.push(Port {
+                        name: port,
+                        direction: direction.clone(),
+                        range: range.clone(),
+                    });
+                } else {
+                    ports.push(Port {
+                        name: port,
+                        direction: String::new(),
+                        range: None,
+                    });
+                }
+            }
+        }
+        // 非 ANSI: 本体の input/output/inout 宣言で方向と範囲を埋める
+        if ports.iter().any(|p| p.direction.is_empty()) {
+            for stmt in body.split(';') {
+                let s = stmt.trim();
+                if !DIRECTIONS.iter().any(|d| find_word(s, d, 0) == Some(0)) {
+                    continue;
+                }
+                let mut direction = String::new();
+                let mut range = None;
+                for item in split_top(s, ',') {
+                    let Some((dir, r, name)) = parse_port_decl(item) else {
+                        continue;
+                    };
+                    if let Some(d) = dir {
+                        direction = d;
+                        range = r;
+                    }
+                    if let Some(p) = ports.iter_mut().find(|p| p.name == name) {
+                        p.direction = direction.clone();
+                        p.range = range.clone();
+                    }
+                }
+            }
+        }
+
+        modules.push(Module {
+            name,
+            ports,
+            body: body.to_string(),
+            file: file.to_path_buf(),
+        });
+    }
+    modules
+}
+
+pub fn parse_vhdl(text: &str, file: &Path) -> Vec<Module> {
+    let text: String = text
+        .lines()
+        .map(|l| l.split("--").next().unwrap_or(""))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let lower = text.to_ascii_lowercase();
+    let mut modules = Vec::new();
+    let mut at = 0;
+    while let Some(start) = find_word(&lower, "entity", at) {
+        let after = start + "entity".len();
+        at = after;
+        let name: String = text[after..]
+            .trim_start()
+            .chars()
+            .take_while(|c| is_ident(*c))
+            .collect();
+        // "entity work.foo" のようなインスタンス化は飛ばす
+        let head = lower[after..].trim_start();
+        if name.is_empty() || !head[name.len()..].trim_start().starts_with("is") {
+            continue;
+        }
+        let end = find_word(&lower, "end", after).unwrap_or(lower.len());
+        let mut ports = Vec::new();
+        if let Some(port) = find_word(&lower[..end], "port", after)
+            && let Some(open) = lower[port..end].find('(').map(|i| port + i)
+            && let Some(close) = matching(&text, open, '(', ')')
+        {
+            for decl in split_top(&text[open + 1..close], ';') {
+                let Some((names, spec)) = decl.split_once(':') else {
+                    continue;
+                };
+                let spec = spec.trim();
+                let mode = spec
+                    .split_whitespace()
+                    .next()
+                    .unwrap_or("")
+                    .to_ascii_lowercase();
+                let direction = match mode.as_str() {
+                    "in" => "input",
+                    "out" | "buffer" => "output",
+                    "inout" => "inout",
+                    _ => continue,
+                };
+                let range = spec.find('(').map(|i| spec[i..].trim().to_string());
+                for n in names.split(',') {
+                    let n = n.trim();
+                    if !n.is_empty() {
+                        ports.push(Port {
+                            name: n.to_string(),
+                            direction: direction.into(),
+                            range: range.clone(),
+                        });
+                    }
+                }
+            }
+        }
+        let body_end = find_word(&lower, "entity", end).unwrap_or(lower.len());
+        modules.push(Module {
+            name,
+            ports,
+            body: text[end..body_end].to_string(),
+            file: file.to_path_buf(),
+        });
+    }
+    modules
+}
+
+pub fn is_hdl(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| ["v", "sv", "vhd", "vhdl"].contains(&e.to_ascii_lowercase().as_str()))
+}
+
+pub fn project_modules(project_root: &Path) -> Result<Vec<Module>> {
+    let (kind, mut files) = stale::project_sources(project_root)?;
+    // 設定も .gprj もなければ src/ 以下
+    if kind == "none" {
+        stale::walk(&project_root.join("src"), &mut files);
+    }
+    let mut modules = Vec::new();
+    for f in files.iter().filter(|f| is_hdl(f)) {
+        let Ok(bytes) = std::fs::read(f) else {
+            continue;
+        };
+        let text = String::from_utf8_lossy(&bytes);
+        let vhdl = f
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("vhd") || e.eq_ignore_ascii_case("vhdl"));
+        modules.extend(if vhdl {
+            parse_vhdl(&text, f)
+        } else {
+            parse_verilog(&text, f)
+        });
+    }
+    Ok(modules)
+}
+
+// impl/project_process_config.json のどこかにある "Top_Module"
+fn configured_top(project_root: &Path) -> Option<String> {
+    fn walk(v: &serde_json::Value) -> Option<String> {
+        match v {
+            serde_json::Value::Object(map) => map.iter().find_map(|(k, v)| match v {
+                serde_json::Value::String(s) if k == "Top_Module" && !s.trim().is_empty() => {
+                    Some(s.trim().to_string())
+                }
+                other => walk(other),
+            }),
+            _ => None,
+        }
+    }
+    let bytes = std::fs::read(
+        project_root
+            .join("impl")
+            .join("project_process_config.json"),
+    )
+    .ok()?;
+    walk(&serde_json::from_slice(&bytes).ok()?)
+}
+
+pub fn top_level(project_root: &Path, top_module: Option<&str>) -> Result<TopLevel> {
+    let modules = project_modules(project_root)?;
+    if modules.is_empty() {
+        return Err(anyhow!(
+            "HDL ソースが見つかりません（.gowin-mcp/config.toml の sources か .gprj のファイル一覧を確認してください）"
+        ));
+    }
+    let wanted = top_module
+        .map(str::to_string)
+        .or_else(|| configured_top(project_root));
+    let top = pick_top(&modules, wanted.as_deref())?;
+    Ok(TopLevel {
+        module: top.name.clone(),
+        file: top.file.display().to_string(),
+        ports: top.ports.clone(),
+    })
+}
+
+// wanted があればその名前、なければ他のモジュールからインスタンス化されていない唯一のモジュール
+pub fn pick_top<'a>(modules: &'a [Module], wanted: Option<&str>) -> Result<&'a Module> {
+    if let Some(name) = wanted {
+        return modules
+            .iter()
+            .find(|m| m.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| anyhow!("トップモジュール {name} が見つかりません"));
+    }
+    let roots: Vec<&Module> = modules
+        .iter()
+        .filter(|m| {
+            !modules
+                .iter()
+                .any(|other| other.name != m.name && find_word(&other.body, &m.name, 0).is_some())
+        })
+        .collect();
+    match roots.as_slice() {
+        [only] => Ok(*only),
+        _ => Err(anyhow!(
+            "トップモジュールを特定できません（候補: {}）。top_module を指定してください",
+            roots
+                .iter()
+                .map(|m| m.name.as_str())
+                .collect::<Vec<_>>()
+                .join(", ")
+        )),
+    }
+}
+
+// "data[3]" / "data[*]" → "data"
+pub fn base_name(reference: &str) -> &str {
+    reference.split('[').next().unwrap_or(reference).trim()
+}
+
+// * と ? だけのグロブ
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let p: Vec<char> = pattern.chars().collect();
+    let n: Vec<char> = name.chars().collect();
+    let (mut pi, mut ni) = (0, 0);
+    let mut star: Option<(usize, usize)> = None;
+    while ni < n.len() {
+        if pi < p.len() && (p[pi] == '?' || p[pi] == n[ni]) {
+            pi += 1;
+            ni += 1;
+        } else if pi < p.len() && p[pi] == '*' {
+            star = Some((pi, ni));
+            pi += 1;
+        } else if let Some((sp, sn)) = star {
+            pi = sp + 1;
+            ni = sn + 1;
+            star = Some((sp, sn + 1));
+        } else {
+            return false;
+        }
+    }
+    p[pi..].iter().all(|c| *c == '*')
+}
+
+// ポート参照（ワイルドカード・ビット指定可）がトップのポートに当たるか
+pub fn port_exists(ports: &[Port], reference: &str) -> bool {
+    let base = base_name(reference);
+    ports.iter().any(|p| glob_match(base, &p.name))
+}
+
+// "led[3]" のようなビット指定がポートの範囲（[7:0] など）に収まるか。ワイルドカードは使えない
+pub fn port_bit_exists(ports: &[Port], reference: &str) -> bool {
+    let base = base_name(reference);
+    let Some(port) = ports.iter().find(|p| p.name == base) else {
+        return false;
+    };
+    let bit = reference
+        .split_once('[')
+        .and_then(|(_, r)| r.strip_suffix(']'))
+        .and_then(|b| b.trim().parse::<i64>().ok());
+    let range = port
+        .range
+        .as_deref()
+        .and_then(|r| r.strip_prefix('[')?.strip_suffix(']')?.split_once(':'))
+        .and_then(|(a, b)| Some((a.trim().parse::<i64>().ok()?, b.trim().parse::<i64>().ok()?)));
+    match (bit, range) {
+        (Some(bit), Some((a, b))) => (a.min(b)..=a.max(b)).contains(&bit),
+        // スカラーのポートにビット指定はできない
+        (Some(_), None) => port.range.is_some(),
+        _ => true,
+    }
+}
@@ -0,0 +1,203 @@
+// ビルド履歴（<project_root>/.gowin-mcp/history/builds.jsonl、1 行 1 ビルド）
+//
+// gw_sh を実際に実行したビルドごとに、診断の件数、タイミング、リソース使用率、
+// 所要時間を追記する。gowin.build_trends は指標ごとの時系列にして返す。
+
+use std::{
+    collections::BTreeMap,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::report;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HistoryEntry {
+    pub run_id: String,
+    pub finished_at_ms: u64,
+    pub tool: String,
+    pub tcl_file: String,
+    pub success: bool,
+    pub duration_ms: u128,
+    pub errors: usize,
+    pub warnings: usize,
+    // 警告コード → 件数（コードのないものは "-"）
+    #[serde(default)]
+    pub warning_codes: BTreeMap<String, usize>,
+    // .fs を更新しなかったビルドでは None / 空
+    pub timing: Option<report::TimingSummary>,
+    pub wns_ns: Option<f64>,
+    pub utilization: Vec<report::ResourceUsage>,
+    pub artifact_id: Option<String>,
+    pub git_commit: Option<String>,
+    pub git_dirty: Option<bool>,
+    // 実行した Tcl と impl/project_process_config.json の写し（失敗したビルドでも残す）
+    #[serde(default)]
+    pub tcl_text: Option<String>,
+    #[serde(default)]
+    pub options: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrendPoint {
+    pub run_id: String,
+    pub finished_at_ms: u64,
+    pub value: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrendStep {
+    pub from_run_id: String,
+    pub to_run_id: String,
+    pub delta: f64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TrendSeries {
+    pub metric: String,
+    pub points: Vec<TrendPoint>,
+    pub first: f64,
+    pub last: f64,
+    pub min: f64,
+    pub max: f64,
+    // 連続する 2 ビルド間で最も増えたところ（使用率の急増などを探す用）
+    pub largest_increase: Option<TrendStep>,
+}
+
+pub fn history_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(".gowin-mcp")
+        .join("history")
+        .join("builds.jsonl")
+}
+
+// Fmax サマリから求めたクロックごとの周期スラック（ns）の最小値
+pub fn worst_slack_ns(timing: &report::TimingSummary) -> Option<f64> {
+    timing
+        .clocks
+        .iter()
+        .filter_map(|c| match (c.constraint_mhz, c.fmax_mhz) {
+            (Some(want), Some(got)) if want > 0.0 && got > 0.0 => {
+                Some(((1000.0 / want - 1000.0 / got) * 1000.0).round() / 1000.0)
+            }
+            _ => None,
+        })
+        .reduce(f64::min)
+}
+
+pub fn warning_codes(diagnostics: &[report::Diagnostic]) -> BTreeMap<String, usize> {
+    let mut codes = BTreeMap::new();
+    for d in diagnostics.iter().filter(|d| d.level == "WARN") {
+        *codes
+            .entry(d.code.clone().unwrap_or_else(|| "-".into()))
+            .or_default() += 1;
+    }
+    codes
+}
+
+pub fn append(project_root: &Path, entry: &HistoryEntry) -> Result<()> {
+    let path = history_path(project_root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("mkdir -p {}", dir.display()))?;
+    }
+    let mut line = serde_json::to_vec(entry)?;
+    line.push(b'\n');
+    std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .and_then(|mut f| f.write_all(&line))
+        .with_context(|| format!("append({})", path.display()))
+}
+
+// 壊れた行（書き込み途中など）は飛ばす
+pub fn load(project_root: &Path) -> Vec<HistoryEntry> {
+    std::fs::read_to_string(history_path(project_root))
+        .unwrap_or_default()
+        .lines()
+        .filter_map(|l| serde_json::from_str(l).ok())
+        .collect()
+}
+
+pub fn find(project_root: &Path, run_id: &str) -> Option<HistoryEntry> {
+    load(project_root).into_iter().find(|e| e.run_id == run_id)
+}
+
+// 指標名 → 値
+//   duration_ms / errors / warnings / timing_met（1/0）/ wns_ns / setup_tns / hold_tns
+//   fmax_mhz:<clock> / util_percent:<resource> / util_used:<resource>
+pub fn metrics(entry: &HistoryEntry) -> BTreeMap<String, f64> {
+    let mut m = BTreeMap::new();
+    m.insert("duration_ms".into(), entry.duration_ms as f64);
+    m.insert("errors".into(), entry.errors as f64);
+    m.insert("warnings".into(), entry.warnings as f64);
+    if let Some(t) = entry.timing.as_ref() {
+        if let Some(met) = t.met {
+            m.insert("timing_met".into(), if met { 1.0 } else { 0.0 });
+        }
+        if let Some(v) = t.setup_tns {
+            m.insert("setup_tns".into(), v);
+        }
+        if let Some(v) = t.hold_tns {
+            m.insert("hold_tns".into(), v);
+        }
+        for c in &t.clocks {
+            if let Some(f) = c.fmax_mhz {
+                m.insert(format!("fmax_mhz:{}", c.clock), f);
+            }
+        }
+    }
+    if let Some(v) = entry.wns_ns {
+        m.insert("wns_ns".into(), v);
+    }
+    for u in &entry.utilization {
+        m.insert(format!("util_percent:{}", u.resource), u.percent);
+        m.insert(format!("util_used:{}", u.resource), u.used as f64);
+    }
+    m
+}
+
+// metric_filter は前方一致（"util_percent" なら全リソース）
+pub fn trends(entries: &[HistoryEntry], metric_filter: &[String]) -> Vec<TrendSeries> {
+    let mut points: BTreeMap<String, Vec<TrendPoint>> = BTreeMap::new();
+    for e in entries {
+        for (metric, value) in metrics(e) {
+            if !metric_filter.is_empty() && !metric_filter.iter().any(|f| metric.starts_with(f)) {
+                continue;
+            }
+            points.entry(metric).or_default().push(TrendPoint {
+                run_id: e.run_id.clone(),
+                finished_at_ms: e.finished_at_ms,
+                value,
+            });
+        }
+    }
+    points
+        .into_iter()
+        .map(|(metric, points)| {
+            let values: Vec<f64> = points.iter().map(|p| p.value).collect();
+            let largest_increase = points
+                .windows(2)
+                .map(|w| TrendStep {
+                    from_run_id: w[0].run_id.clone(),
+                    to_run_id: w[1].run_id.clone(),
+                    delta: w[1].value - w[0].value,
+                })
+                .filter(|s| s.delta > 0.0)
+                .max_by(|a, b| a.delta.total_cmp(&b.delta));
+            TrendSeries {
+                metric,
+                first: values[0],
+                last: values[values.len() - 1],
+                min: values.iter().copied().fold(f64::INFINITY, f64::min),
+                max: values.iter().copied().fold(f64::NEG_INFINITY, f64::max),
+                largest_increase,
+                points,
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,171 @@
+// フラッシュ向けバイナリイメージ（.bin / Intel HEX / MCS）の生成
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Bin,
+    Hex,
+    Mcs,
+}
+
+impl ImageFormat {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().trim_start_matches('.').to_lowercase().as_str() {
+            "bin" => Some(Self::Bin),
+            "hex" | "ihex" => Some(Self::Hex),
+            "mcs" => Some(Self::Mcs),
+            _ => None,
+        }
+    }
+
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Bin => "bin",
+            Self::Hex => "hex",
+            Self::Mcs => "mcs",
+        }
+    }
+}
+
+// "4194304" / "0x400000" / "4M" / "4MiB" / "64K" → バイト数
+pub fn parse_size(s: &str) -> Result<u64> {
+    let t = s.trim();
+    if let Some(hex) = t.strip_prefix("0x").or_else(|| t.strip_prefix("0X")) {
+        let hex = hex.replace('_', "");
+        if !hex.is_empty() && hex.bytes().all(|c| c.is_ascii_hexdigit()) {
+            return u64::from_str_radix(&hex, 16)
+                .map_err(|_| anyhow!("サイズ/アドレスが大きすぎます: {s}"));
+        }
+        return Err(anyhow!("サイズ/アドレスを解釈できません: {s}"));
+    }
+    let upper = t.to_uppercase();
+    let num_end = upper
+        .find(|c: char| !c.is_ascii_digit() && c != '_')
+        .unwrap_or(upper.len());
+    let (num, unit) = upper.split_at(num_end);
+    let mul = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        "G" | "GB" | "GIB" => 1024 * 1024 * 1024,
+        _ => return Err(anyhow!("サイズ/アドレスを解釈できません: {s}")),
+    };
+    let n: u64 = num
+        .replace('_', "")
+        .parse()
+        .map_err(|_| anyhow!("サイズ/アドレスを解釈できません: {s}"))?;
+    n.checked_mul(mul)
+        .ok_or_else(|| anyhow!("サイズ/アドレスが大きすぎます: {s}"))
+}
+
+// SPI NOR フラッシュとして扱う最大サイズ（1Gbit）。flash_size 未指定時の上限
+pub const MAX_FLASH_SIZE: u64 = 128 * 1024 * 1024;
+
+// offset に data を置き、pad_to まで fill で埋めたフラッシュ先頭からのイメージ。
+// 末尾が flash_size を超えるものは作らない
+pub fn place(
+    data: &[u8],
+    offset: u64,
+    pad_to: Option<u64>,
+    fill: u8,
+    flash_size: u64,
+) -> Result<Vec<u8>> {
+    let end = offset
+        .checked_add(data.len() as u64)
+        .ok_or_else(|| anyhow!("offset 0x{offset:X} が大きすぎます"))?;
+    let total = match pad_to {
+        Some(size) if size < end => {
+            return Err(anyhow!(
+                "イメージ (offset 0x{offset:X} + {} bytes = 0x{end:X}) が pad_to 0x{size:X} に収まりません",
+                data.len()
+            ));
+        }
+        Some(size) => size,
+        None => end,
+    };
+    if total > flash_size {
+        return Err(anyhow!(
+            "イメージの末尾 0x{total:X} がフラッシュサイズ 0x{flash_size:X} を超えています"
+        ));
+    }
+    let mut image = vec![fill; total as usize];
+    image[offset as usize..end as usize].copy_from_slice(data);
+    Ok(image)
+}
+
+pub fn encode(format: ImageFormat, image: &[u8], start: u64) -> Vec<u8> {
+    match format {
+        ImageFormat::Bin => image.to_vec(),
+        ImageFormat::Hex => intel_hex(&image[start as usize..], start as u32, "\n").into_bytes(),
+        ImageFormat::Mcs => intel_hex(&image[start as usize..], start as u32, "\r\n").into_bytes(),
+    }
+}
+
+fn intel_hex(data: &[u8], base: u32, eol: &str) -> String {
+    let mut out = String::new();
+    let mut upper: Option<u32> = None;
+    let mut addr = base;
+    let mut rest = data;
+    while !rest.is_empty() {
+        // 64KiB 境界をまたがないように 16 バイトずつ
+        let room = 0x1_0000 - (addr & 0xFFFF) as usize;
+        let n = rest.len().min(16).min(room);
+        if upper != Some(addr >> 16) {
+            let hi = (addr >> 16) as u16;
+            out.push_str(&record(0, 0x04, &hi.to_be_bytes()));
+            out.push_str(eol);
+            upper = Some(addr >> 16);
+        }
+        out.push_str(&record((addr & 0xFFFF) as u16, 0x00, &rest[..n]));
+        out.push_str(eol);
+        addr = addr.wrapping_add(n as u32);
+        rest = &rest[n..];
+    }
+    out.push_str(&record(0, 0x01, &[]));
+    out.push_str(eol);
+    out
+}
+
+fn record(addr: u16, kind: u8, data: &[u8]) -> String {
+    let mut bytes = vec![data.len() as u8];
+    bytes.extend_from_slice(&addr.to_be_bytes());
+    bytes.push(kind);
+    bytes.extend_from_slice(data);
+    let sum = bytes.iter().fold(0u8, |acc, b| acc.wrapping_add(*b));
+    bytes.push(sum.wrapping_neg());
+    let mut s = String::from(":");
+    for b in bytes {
+        s.push_str(&format!("{b:02X}"));
+    }
+    s
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Comparison {
+    pub compared_bytes: u64,
+    pub matches: bool,
+    // 最初に食い違ったフラッシュ上のアドレス（base からの絶対値）
+    pub first_diff_address: Option<u64>,
+    pub expected: Option<String>,
+    pub actual: Option<String>,
+}
+
+// expected 全体が actual の先頭と一致するか。actual が短い場合はその末尾で食い違い扱い
+pub fn compare(expected: &[u8], actual: &[u8], base: u64) -> Comparison {
+    let diff = expected
+        .iter()
+        .zip(actual)
+        .position(|(e, a)| e != a)
+        .or_else(|| (actual.len() < expected.len()).then_some(actual.len()));
+    let byte = |b: Option<&u8>| b.map(|v| format!("0x{v:02X}"));
+    Comparison {
+        compared_bytes: expected.len().min(actual.len()) as u64,
+        matches: diff.is_none(),
+        first_diff_address: diff.map(|i| base + i as u64),
+        expected: diff.and_then(|i| byte(expected.get(i))),
+        actual: diff.and_then(|i| byte(actual.get(i))),
+    }
+}
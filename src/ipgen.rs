@@ -0,0 +1,183 @@
+// IP Core Generator（GowinModGen）のバッチ実行用 .mod ファイル
+//
+//   -series GW1NR
+//   -device GW1NR-9C
+//   -device_version C
+//   -package QFN88P
+//   -part_number GW1NR-LV9QN88PC6/I5
+//
+//   -mod_name Gowin_rPLL
+//   -file_name gowin_rpll
+//   -path /abs/project/src/gowin_rpll/
+//   -type PLL
+//   -file_type vlg
+//   -idiv_sel 2
+//   ...
+//
+// GowinModGen -do <file>.mod で <path> に <file_name>.v（.vhd）と .ipc などができる。
+// デバイスの行は .gprj から、IP ごとのオプションは parameters をそのまま並べる。
+// 空白を含む -path は "..." で囲む。
+
+use std::{
+    collections::BTreeMap,
+    path::{Component, Path, PathBuf},
+};
+
+use anyhow::{Result, anyhow};
+
+use crate::device;
+
+// よく使う IP の種類（GowinModGen の -type）。これ以外も渡せる
+pub const KNOWN_TYPES: &[&str] = &[
+    "PLL",
+    "CLKDIV",
+    "OSC",
+    "FIFO",
+    "FIFO_HS",
+    "SP",
+    "SPX9",
+    "SDPB",
+    "SDPX9B",
+    "DPB",
+    "DPX9B",
+    "ROM",
+    "ROMX9",
+    "SSRAM",
+    "DSP",
+    "MULT",
+    "MULTALU",
+    "MULTADDALU",
+];
+
+pub struct ModSpec<'a> {
+    pub device: &'a str,
+    pub part_number: Option<&'a str>,
+    pub module_name: &'a str,
+    pub file_name: &'a str,
+    pub output_dir: &'a Path,
+    pub ip_type: &'a str,
+    // vlg / vhdl
+    pub file_type: &'a str,
+    // オプション名（- なし）→ 値
+    pub parameters: &'a BTreeMap<String, String>,
+}
+
+// "GW1NR-9C" → "C"、"GW5A-25A" → "A"（末尾が数字なら None）
+fn device_version(device: &str) -> Option<String> {
+    let last = device.trim().chars().last()?;
+    last.is_ascii_alphabetic()
+        .then(|| last.to_ascii_uppercase().to_string())
+}
+
+// 品番のパッケージ記号 → IDE のパッケージ名（"QN88P" → "QFN88P"）
+fn package_name(code: &str) -> String {
+    const PREFIXES: [(&str, &str); 7] = [
+        ("QN", "QFN"),
+        ("LQ", "LQFP"),
+        ("EQ", "ELQFP"),
+        ("MG", "MBGA"),
+        ("PG", "PBGA"),
+        ("UG", "UBGA"),
+        ("CS", "WLCSP"),
+    ];
+    PREFIXES
+        .iter()
+        .find_map(|(short, long)| code.strip_prefix(short).map(|rest| format!("{long}{rest}")))
+        .unwrap_or_else(|| code.to_string())
+}
+
+// "." と ".." を畳んだパス（ファイルシステムは見ない）。出力先が .gprj の下かを比べるのに使う
+pub fn normalize(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for c in path.components() {
+        match c {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other),
+        }
+    }
+    out
+}
+
+// Verilog / VHDL の識別子として使えるか
+pub fn is_identifier(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars
+        .next()
+        .is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+pub fn mod_text(spec: &ModSpec) -> Result<String> {
+    let series = device::find_part(spec.device)
+        .map(|p| p.family.to_string())
+        .or_else(|| {
+            device::device_base(spec.device).and_then(|b| b.split('-').next().map(str::to_string))
+        })
+        .ok_or_else(|| anyhow!("デバイス {} の系列が分かりません", spec.device))?;
+
+    let mut lines = vec![
+        format!("-series {series}"),
+        format!("-device {}", spec.device),
+    ];
+    if let Some(v) = device_version(spec.device) {
+        lines.push(format!("-device_version {v}"));
+    }
+    if let Some(pn) = spec.part_number {
+        if let Some(pkg) = device::package(pn) {
+            lines.push(format!("-package {}", package_name(&pkg)));
+        }
+        lines.push(format!("-part_number {pn}"));
+    }
+    lines.push(String::new());
+
+    // 出力先は絶対パスで、末尾に / が要る
+    let mut dir = spec.output_dir.display().to_string();
+    if !dir.ends_with('/') {
+        dir.push('/');
+    }
+    if dir.contains(['"', '\n', '\r']) {
+        return Err(anyhow!("output_dir {dir:?} は .mod の -path にできません"));
+    }
+    if dir.contains(char::is_whitespace) {
+        dir = format!("\"{dir}\"");
+    }
+    lines.extend([
+        format!("-mod_name {}", spec.module_name),
+        format!("-file_name {}", spec.file_name),
+        format!("-path {dir}"),
+        format!("-type {}", spec.ip_type),
+        format!("-file_type {}", spec.file_type),
+    ]);
+    const RESERVED: [&str; 10] = [
+        "series",
+        "device",
+        "device_version",
+        "package",
+        "part_number",
+        "mod_name",
+        "file_name",
+        "path",
+        "type",
+        "file_type",
+    ];
+    for (key, value) in spec.parameters {
+        let key = key.trim_start_matches('-');
+        if RESERVED.contains(&key) {
+            return Err(anyhow!(
+                "parameters の {key} は指定できません（ip_type / module_name / file_name / output_dir / language と .gprj のデバイスから決めます）"
+            ));
+        }
+        if key.is_empty() || key.contains(char::is_whitespace) || value.contains('\n') {
+            return Err(anyhow!(
+                "parameters の {key:?} = {value:?} は .mod の 1 行にできません"
+            ));
+        }
+        lines.push(format!("-{key} {value}"));
+    }
+    Ok(lines.join("\n") + "\n")
+}
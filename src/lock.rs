@@ -0,0 +1,240 @@
+// ケーブルごとのアドバイザリロック
+//
+// 共有ディレクトリ（GOWIN_MCP_LOCK_DIR、既定は <tmp>/gw-synth-flash-mcp-locks）に
+// <ケーブルキー>.lock を create_new で作れたら取得。中身は保持者の情報（JSON）。
+// 保持者が異常終了した場合に備え、expires_at_ms を過ぎたロックは奪ってよい。
+// 奪うときは <ケーブルキー>.lock.steal を作れた 1 つだけが、保持者が変わっていないことを
+// 確かめてから消す（待っている同士で互いの新しいロックを消さないように）。
+//
+// ケーブルの列挙（list_cables）は ENUMERATE_KEY のロックの中で行い、ケーブルのロックも
+// その中で取る。列挙の前に busy() で使用中のケーブルを確かめる。
+
+use std::{
+    io::Write,
+    path::{Path, PathBuf},
+    time::{Duration, UNIX_EPOCH},
+};
+
+use anyhow::{Context, Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{cable::CableInfo, now_ms};
+
+pub const LOCK_DIR_ENV: &str = "GOWIN_MCP_LOCK_DIR";
+
+// 作成直後で中身が書かれていないロックを、壊れたものとみなすまでの時間
+const UNREADABLE_GRACE_MS: u64 = 10_000;
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+pub const ENUMERATE_KEY: &str = "enumerate";
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct LockHolder {
+    pub run_id: String,
+    pub tool: String,
+    pub pid: u32,
+    pub project_root: String,
+    pub cable_key: String,
+    // 使っているケーブル（列挙のロックでは None）
+    #[serde(default)]
+    pub cable: Option<String>,
+    #[serde(default)]
+    pub serial: Option<String>,
+    pub acquired_at_ms: u64,
+    pub expires_at_ms: u64,
+}
+
+// ロックを取る実行
+pub struct Owner<'a> {
+    pub run_id: &'a str,
+    pub tool: &'a str,
+    pub project_root: &'a Path,
+    pub cable: Option<&'a str>,
+    pub serial: Option<&'a str>,
+}
+
+pub struct CableLock {
+    path: PathBuf,
+    run_id: String,
+    pub key: String,
+    pub waited_ms: u64,
+}
+
+impl Drop for CableLock {
+    fn drop(&mut self) {
+        // 期限切れで奪われていたら消さない
+        if read_holder(&self.path).is_some_and(|h| h.run_id == self.run_id) {
+            let _ = std::fs::remove_file(&self.path);
+        }
+    }
+}
+
+pub fn lock_dir() -> PathBuf {
+    match std::env::var(LOCK_DIR_ENV) {
+        Ok(p) if !p.trim().is_empty() => PathBuf::from(p),
+        _ => std::env::temp_dir().join("gw-synth-flash-mcp-locks"),
+    }
+}
+
+// シリアルが分かればそれを、なければケーブル名（+ location）をキーにする。
+// 名前で指定した場合も列挙で CableInfo にしてから使う（同じケーブルが同じキーになるように）
+pub fn cable_key(cable: Option<&str>, info: Option<&CableInfo>) -> String {
+    let raw = match (info, cable) {
+        (
+            Some(CableInfo {
+                serial: Some(serial),
+                ..
+            }),
+            _,
+        ) => return serial_key(serial),
+        (Some(i), _) => match i.location.as_deref() {
+            Some(loc) => format!("{}@{loc}", i.name),
+            None => i.name.clone(),
+        },
+        (None, Some(name)) => name.to_string(),
+        (None, None) => "default".to_string(),
+    };
+    crate::safe_file_stem(&raw)
+}
+
+pub fn serial_key(serial: &str) -> String {
+    crate::safe_file_stem(&format!("sn-{serial}"))
+}
+
+fn read_holder(path: &Path) -> Option<LockHolder> {
+    let text = std::fs::read_to_string(path).ok()?;
+    serde_json::from_str(&text).ok()
+}
+
+fn modified_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+fn is_stale(path: &Path, holder: Option<&LockHolder>, now: u64) -> bool {
+    match holder {
+        Some(h) => h.expires_at_ms < now,
+        None => modified_ms(path).is_some_and(|m| m + UNREADABLE_GRACE_MS < now),
+    }
+}
+
+// 期限切れのロックを消す。<key>.lock.steal を作れたときだけ、見たときと同じ保持者のまま
+// 期限切れであることを確かめてから消す。作れなければ false（呼び出し側で待って再試行）
+fn remove_stale(path: &Path, seen: Option<&LockHolder>) -> bool {
+    let guard = path.with_extension("lock.steal");
+    if std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(&guard)
+        .is_err()
+    {
+        // 奪う途中で異常終了した実行の残りは消す
+        if modified_ms(&guard).is_some_and(|m| m + UNREADABLE_GRACE_MS < now_ms()) {
+            let _ = std::fs::remove_file(&guard);
+        }
+        return false;
+    }
+    let current = read_holder(path);
+    let unchanged = match (current.as_ref(), seen) {
+        (Some(c), Some(s)) => c.run_id == s.run_id && c.acquired_at_ms == s.acquired_at_ms,
+        (None, None) => true,
+        _ => false,
+    };
+    if unchanged && is_stale(path, current.as_ref(), now_ms()) {
+        let _ = std::fs::remove_file(path);
+    }
+    let _ = std::fs::remove_file(&guard);
+    true
+}
+
+// 有効なケーブルのロックのうち matches に当たるもの（列挙のロックは除く）
+pub fn busy(matches: impl Fn(&LockHolder) -> bool) -> Option<LockHolder> {
+    let now = now_ms();
+    std::fs::read_dir(lock_dir())
+        .ok()?
+        .filter_map(|e| e.ok().map(|e| e.path()))
+        .filter(|p| p.extension().is_some_and(|e| e == "lock"))
+        .filter_map(|p| read_holder(&p))
+        .find(|h| h.cable_key != ENUMERATE_KEY && h.expires_at_ms >= now && matches(h))
+}
+
+pub fn describe(holder: Option<&LockHolder>) -> String {
+    match holder {
+        Some(h) => format!(
+            "{}（run_id: {}, pid: {}, project_root: {}）",
+            h.tool, h.run_id, h.pid, h.project_root
+        ),
+        None => "別の実行".to_string(),
+    }
+}
+
+// hold_sec はロックの有効期限（ツールのタイムアウト + 余裕）。
+// 使用中なら wait_sec まで待ち、それでも空かなければ保持者の run_id を含むエラー
+pub async fn acquire(
+    key: &str,
+    owner: &Owner<'_>,
+    hold_sec: u64,
+    wait_sec: u64,
+) -> Result<CableLock> {
+    let dir = lock_dir();
+    crate::ensure_dir(&dir).await?;
+    let path = dir.join(format!("{key}.lock"));
+    let started = now_ms();
+
+    loop {
+        let now = now_ms();
+        let holder = LockHolder {
+            run_id: owner.run_id.to_string(),
+            tool: owner.tool.to_string(),
+            pid: std::process::id(),
+            project_root: owner.project_root.display().to_string(),
+            cable_key: key.to_string(),
+            cable: owner.cable.map(str::to_string),
+            serial: owner.serial.map(str::to_string),
+            acquired_at_ms: now,
+            expires_at_ms: now + hold_sec * 1000,
+        };
+
+        match std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&path)
+        {
+            Ok(mut file) => {
+                file.write_all(&serde_json::to_vec_pretty(&holder)?)
+                    .with_context(|| format!("write({})", path.display()))?;
+                return Ok(CableLock {
+                    path,
+                    run_id: owner.run_id.to_string(),
+                    key: key.to_string(),
+                    waited_ms: now - started,
+                });
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {}
+            Err(e) => {
+                return Err(e).with_context(|| format!("create({})", path.display()));
+            }
+        }
+
+        let current = read_holder(&path);
+        if is_stale(&path, current.as_ref(), now) {
+            if !remove_stale(&path, current.as_ref()) {
+                tokio::time::sleep(POLL_INTERVAL).await;
+            }
+            continue;
+        }
+
+        if now - started >= wait_sec * 1000 {
+            let who = describe(current.as_ref());
+            return Err(anyhow!(
+                "ケーブル {key} は {who} が使用中です。終了を待つには lock_wait_sec を指定してください（ロック: {}）",
+                path.display()
+            ));
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
@@ -1,5 +1,32 @@
+mod artifacts;
+mod bitstream;
+mod board;
+mod build_cache;
+mod cable;
+mod capability;
+mod compare;
+mod config;
+mod coverage;
+mod cst;
+mod device;
+mod flash_layout;
+mod git;
+mod gprj;
+mod hdl;
+mod history;
+mod image;
+mod ipgen;
+mod lock;
+mod pinout;
+mod pll;
+mod programmer;
+mod report;
+mod sdc;
+mod stale;
+mod status;
+
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     path::{Path, PathBuf},
     process::Stdio,
     time::{Duration, SystemTime, UNIX_EPOCH},
@@ -48,12 +75,22 @@ async fn ensure_dir(dir: &Path) -> Result<()> {
         .with_context(|| format!("create_dir_all({})", dir.display()))
 }
 
-fn stamp() -> String {
-    let ms = SystemTime::now()
+fn now_ms() -> u64 {
+    SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_else(|_| Duration::from_secs(0))
-        .as_millis();
-    ms.to_string()
+        .as_millis() as u64
+}
+
+fn stamp() -> String {
+    now_ms().to_string()
+}
+
+// ツール呼び出し 1 回ごとの ID（ロックの保持者表示などに使う）
+fn new_run_id() -> String {
+    static SEQ: std::sync::atomic::AtomicU32 = std::sync::atomic::AtomicU32::new(0);
+    let seq = SEQ.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+    format!("{}-{}-{seq}", stamp(), std::process::id())
 }
 
 fn safe_file_stem(s: &str) -> String {
@@ -126,6 +163,13 @@ async fn write_run_logs(
     let log_dir = project_root.join(".gowin-mcp").join("logs");
     ensure_dir(&log_dir).await?;
 
+    // どのソースから作ったものか辿れるよう、すべての実行に git の状態を残す
+    let mut meta = meta.clone();
+    if let Some(obj) = meta.as_object_mut() {
+        obj.entry("git")
+            .or_insert_with(|| serde_json::json!(git::info(project_root)));
+    }
+
     let base = format!("{}_{}", stamp(), safe_file_stem(tool_name));
     let log_file = log_dir.join(format!("{base}.log"));
     let meta_file = log_dir.join(format!("{base}.json"));
@@ -133,7 +177,7 @@ async fn write_run_logs(
     tokio::fs::write(&log_file, log_text)
         .await
         .with_context(|| format!("write({})", log_file.display()))?;
-    tokio::fs::write(&meta_file, serde_json::to_vec_pretty(meta)?)
+    tokio::fs::write(&meta_file, serde_json::to_vec_pretty(&meta)?)
         .await
         .with_context(|| format!("write({})", meta_file.display()))?;
 
@@ -293,6 +337,167 @@ impl GowinMcp {
         }
     }
 
+    // cable（名前）/ cable_serial / cable_index からケーブルを決める。
+    // どれも無ければ list_cables の先頭
+    // 列挙の前に、使うケーブルが他の実行で使用中でないか確かめる。列挙からケーブルの
+    // ロック取得までは列挙用のロックの中で行う（確認してからロックを取るまでに割り込まれない）。
+    // ロックのキーは列挙で分かった CableInfo から作るので、名前・シリアル・番号のどれで
+    // 指定しても同じケーブルは同じキーになる
+    async fn select_cable(
+        &self,
+        project_root: &Path,
+        gowin_ide_app_path: &str,
+        timeout_sec: u64,
+        run_id: &str,
+        request: CableRequest<'_>,
+    ) -> Result<(CableSelection, lock::CableLock), McpError> {
+        let CableRequest {
+            tool,
+            cable,
+            cable_serial,
+            cable_index,
+            hold_sec,
+            lock_wait_sec,
+        } = request;
+        let explicit = cable_serial.is_some() || cable_index.is_some();
+        if explicit && cable.is_some() {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "cable と cable_serial / cable_index は同時に指定できません",
+                None,
+            ));
+        }
+
+        let wait_sec = lock_wait_sec.unwrap_or(0);
+        let started = std::time::Instant::now();
+        let lock_error =
+            |e: anyhow::Error| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None);
+        // 番号指定や自動選択は、列挙するまでどのケーブルか分からないので、どれかが使用中なら待つ
+        let targets = |h: &lock::LockHolder| match (cable_serial, cable.as_deref()) {
+            (Some(serial), _) => {
+                h.serial
+                    .as_deref()
+                    .is_some_and(|s| s.eq_ignore_ascii_case(serial))
+                    || h.cable_key == lock::serial_key(serial)
+            }
+            (None, Some(name)) => {
+                h.cable
+                    .as_deref()
+                    .is_some_and(|c| c.eq_ignore_ascii_case(name))
+                    || h.cable_key == lock::cable_key(Some(name), None)
+            }
+            (None, None) => true,
+        };
+        let owner = lock::Owner {
+            run_id,
+            tool,
+            project_root,
+            cable: None,
+            serial: None,
+        };
+        let enumerate_sec = timeout_sec.min(20) + 30;
+        let enumerating = loop {
+            let guard = lock::acquire(
+                lock::ENUMERATE_KEY,
+                &owner,
+                enumerate_sec,
+                enumerate_sec + wait_sec,
+            )
+            .await
+            .map_err(lock_error)?;
+            let Some(holder) = lock::busy(targets) else {
+                break guard;
+            };
+            drop(guard);
+            if started.elapsed().as_secs() >= wait_sec {
+                return Err(McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    format!(
+                        "ケーブル {} は {} が使用中です。終了を待つには lock_wait_sec を指定してください",
+                        holder.cable_key,
+                        lock::describe(Some(&holder))
+                    ),
+                    None,
+                ));
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        };
+
+        let list = match self
+            .list_cables(Parameters(ListCablesRequest {
+                project_root: Some(project_root.display().to_string()),
+                gowin_ide_app_path: Some(gowin_ide_app_path.to_string()),
+                timeout_sec: Some(timeout_sec.min(20)),
+                refresh_capabilities: None,
+            }))
+            .await
+        {
+            Ok(list) => Some(list.0),
+            // 名前で指定されていれば、列挙できなくてもその名前で書き込む
+            Err(_) if cable.is_some() => None,
+            Err(e) => return Err(e),
+        };
+        let cables = list
+            .as_ref()
+            .map(|l| l.cables.as_slice())
+            .unwrap_or_default();
+
+        let chosen = if let Some(serial) = cable_serial {
+            cables
+                .iter()
+                .find(|c| {
+                    c.serial
+                        .as_deref()
+                        .is_some_and(|s| s.eq_ignore_ascii_case(serial))
+                })
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| cable_not_found(&format!("serial={serial}"), cables))?
+        } else if let Some(index) = cable_index {
+            cables
+                .iter()
+                .find(|c| c.index == index)
+                .cloned()
+                .map(Some)
+                .ok_or_else(|| cable_not_found(&format!("index={index}"), cables))?
+        } else if let Some(name) = cable.as_deref() {
+            cables
+                .iter()
+                .find(|c| c.name.eq_ignore_ascii_case(name))
+                .cloned()
+        } else {
+            cables.first().cloned()
+        };
+
+        let selection = CableSelection {
+            cable: cable.or_else(|| chosen.as_ref().map(|c| c.name.clone())),
+            chip_idcode: chosen.as_ref().and_then(|c| c.idcode.clone()).or_else(|| {
+                list.as_ref()
+                    .and_then(|l| l.detected_idcodes.first().cloned())
+            }),
+            info: chosen,
+            list_cables_attempts: list.map(|l| l.attempts),
+            explicit,
+        };
+
+        let key = lock::cable_key(selection.cable.as_deref(), selection.info.as_ref());
+        let owner = lock::Owner {
+            cable: selection.cable.as_deref(),
+            serial: selection.info.as_ref().and_then(|i| i.serial.as_deref()),
+            ..owner
+        };
+        let cable_lock = lock::acquire(
+            &key,
+            &owner,
+            hold_sec,
+            wait_sec.saturating_sub(started.elapsed().as_secs()),
+        )
+        .await
+        .map_err(lock_error)?;
+        drop(enumerating);
+        Ok((selection, cable_lock))
+    }
+
     #[tool(
         name = "gowin.run_tcl",
         description = "gw_sh で Tcl を実行（任意Tcl可）。ログ保存・タイムアウト対応"
@@ -321,6 +526,11 @@ impl GowinMcp {
             ));
         }
 
+        // 別のボード向けのプロジェクトをビルドしないよう、.gprj の品番と照合する
+        if let Some(b) = req.board.as_deref() {
+            check_board_part(&project_root, b).await?;
+        }
+
         let tcl_file_path = if let Some(tcl_path) = req.tcl_path.as_deref() {
             resolve_under(&project_root, tcl_path)
         } else {
@@ -342,6 +552,27 @@ impl GowinMcp {
             p
         };
 
+        // 入力（ソース・Tcl・環境変数・IDE）が前回の成功ビルドと同じなら実行を省略する
+        let cache_key = if req.use_cache.unwrap_or(false) {
+            Some(
+                build_cache::compute_key(
+                    &project_root,
+                    &tcl_file_path,
+                    req.env.as_ref(),
+                    gowin_ide_app_path,
+                )
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?,
+            )
+        } else {
+            None
+        };
+        let cached = cache_key
+            .as_deref()
+            .and_then(|k| build_cache::lookup(&project_root, k));
+        let cache_hit = cached.is_some();
+        let run_id = new_run_id();
+        let mut artifact_id = cached.as_ref().and_then(|c| c.artifact_id.clone());
+
         let mut env = gw_sh_env(&ide_base);
         if let Some(extra) = req.env {
             for (k, v) in extra {
@@ -349,15 +580,93 @@ impl GowinMcp {
             }
         }
 
-        let exec = exec_with_timeout(
-            &gw_sh,
-            &[tcl_file_path.display().to_string()],
-            Some(&ide_bin_dir),
-            Some(&env),
-            timeout_sec,
-        )
-        .await
-        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        // mtime の分解能が秒のファイルシステムもあるので秒に切り捨てて比べる
+        let started_at_ms = now_ms() / 1000 * 1000;
+        let exec = match cached {
+            Some(entry) => ExecMeta {
+                exit_code: 0,
+                timed_out: false,
+                duration_ms: 0,
+                stdout: entry.stdout,
+                stderr: entry.stderr,
+            },
+            None => exec_with_timeout(
+                &gw_sh,
+                &[tcl_file_path.display().to_string()],
+                Some(&ide_bin_dir),
+                Some(&env),
+                timeout_sec,
+            )
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?,
+        };
+
+        let git = git::info(&project_root);
+        if !cache_hit {
+            let finished_at_ms = now_ms();
+            let succeeded = exec.exit_code == 0 && !exec.timed_out;
+            let fresh_fs = report::find_latest_fs(&project_root)
+                .filter(|fs| stale::mtime_ms(fs).is_some_and(|m| m >= started_at_ms));
+
+            // .fs を更新したビルドが成功したら、その時点のソースを記録する
+            // （古いビットストリームの検出とビルドキャッシュ用）
+            if succeeded && fresh_fs.is_some() {
+                let _ = stale::record_build(&project_root, "gowin.run_tcl", finished_at_ms);
+                // 成果物を .gowin-mcp/artifacts/<run_id>/ に残す（impl/pnr は次のビルドで上書きされる）
+                artifact_id = artifacts::store(
+                    &project_root,
+                    &run_id,
+                    "gowin.run_tcl",
+                    &tcl_file_path,
+                    finished_at_ms,
+                )
+                .await
+                .ok()
+                .map(|m| m.artifact_id);
+                if let Some(key) = cache_key.as_deref() {
+                    let _ = build_cache::store(
+                        &project_root,
+                        key,
+                        finished_at_ms,
+                        exec.duration_ms,
+                        &exec.stdout,
+                        &exec.stderr,
+                        artifact_id.as_deref(),
+                    );
+                }
+            }
+
+            // 失敗したビルドも含めて履歴に残す（レポートは今回更新されたものだけ）
+            let diagnostics =
+                report::parse_diagnostics(&format!("{}\n{}", exec.stdout, exec.stderr));
+            let (timing, utilization) = fresh_fs
+                .as_deref()
+                .and_then(Path::parent)
+                .map(report::load_reports)
+                .unwrap_or_default();
+            let _ = history::append(
+                &project_root,
+                &history::HistoryEntry {
+                    run_id: run_id.clone(),
+                    finished_at_ms,
+                    tool: "gowin.run_tcl".into(),
+                    tcl_file: tcl_file_path.display().to_string(),
+                    success: succeeded,
+                    duration_ms: exec.duration_ms,
+                    errors: diagnostics.iter().filter(|d| d.level == "ERROR").count(),
+                    warnings: diagnostics.iter().filter(|d| d.level == "WARN").count(),
+                    warning_codes: history::warning_codes(&diagnostics),
+                    wns_ns: timing.as_ref().and_then(history::worst_slack_ns),
+                    timing,
+                    utilization,
+                    artifact_id: artifact_id.clone(),
+                    git_commit: git.commit.clone(),
+                    git_dirty: git.dirty,
+                    tcl_text: std::fs::read_to_string(&tcl_file_path).ok(),
+                    options: artifacts::build_options(&project_root),
+                },
+            );
+        }
 
         let expected_checks = if let Some(expected) = req.expected_files {
             let mut checks = Vec::new();
@@ -376,6 +685,7 @@ impl GowinMcp {
 
         let meta_json = serde_json::json!({
             "tool": "gowin.run_tcl",
+            "run_id": run_id,
             "project_root": project_root.display().to_string(),
             "gowin_ide_app_path": gowin_ide_app_path,
             "gw_sh": gw_sh.display().to_string(),
@@ -385,15 +695,20 @@ impl GowinMcp {
             "timed_out": exec.timed_out,
             "duration_ms": exec.duration_ms,
             "expected_checks": expected_checks,
+            "cache_hit": cache_hit,
+            "cache_key": cache_key,
+            "artifact_id": artifact_id,
+            "git": git,
         });
 
         let log_text = format!(
-            "command: {} {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+            "command: {} {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\ncache_hit: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
             gw_sh.display(),
             vec![tcl_file_path.display().to_string()],
             exec.exit_code,
             exec.timed_out,
             exec.duration_ms,
+            cache_hit,
             exec.stdout,
             exec.stderr,
         );
@@ -404,6 +719,7 @@ impl GowinMcp {
                 .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
 
         Ok(Json(RunTclResponse {
+            run_id,
             project_root: project_root.display().to_string(),
             tcl_file_path: tcl_file_path.display().to_string(),
             gowin_ide_app_path: gowin_ide_app_path.to_string(),
@@ -413,6 +729,9 @@ impl GowinMcp {
             stdout: exec.stdout,
             stderr: exec.stderr,
             expected_checks,
+            cache_hit,
+            cache_key,
+            artifact_id,
             log_file: log_file.display().to_string(),
             meta_file: meta_file.display().to_string(),
         }))
@@ -446,32 +765,22 @@ impl GowinMcp {
 
         let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
 
-        if tokio::fs::metadata(&programmer_cli).await.is_err() {
-            return Err(McpError::new(
-                ErrorCode::INVALID_PARAMS,
-                format!(
-                    "programmer_cli が見つかりません: {}。gowin_ide_app_path を確認してください（現在: {}）",
-                    programmer_cli.display(),
-                    gowin_ide_app_path
-                ),
-                None,
-            ));
-        }
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
 
-        let candidates: Vec<Vec<String>> = vec![
-            vec!["--list-cables".into()],
-            vec!["--list_cables".into()],
-            vec!["--cableList".into()],
-            vec!["--listCable".into()],
-            vec!["--cables".into()],
-            vec!["-l".into()],
-            vec!["--list".into()],
-            vec!["--scan".into()],
-            vec!["--enumerate".into()],
-        ];
+        // --help の解析結果（キャッシュ）から列挙フラグを決める。
+        // 解析できなかった場合だけ従来どおり候補を総当たりする
+        let (caps, caps_cached) = capability::load_or_probe(
+            &programmer_cli,
+            timeout_sec,
+            req.refresh_capabilities.unwrap_or(false),
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        let candidates = caps.list_cables_candidates();
 
         let mut attempts = Vec::new();
-        let mut cables: Vec<String> = Vec::new();
+        let mut cables: Vec<cable::CableInfo> = Vec::new();
+        let mut detected: Vec<u32> = Vec::new();
 
         for argv in candidates {
             let exec = exec_with_timeout(&programmer_cli, &argv, None, None, timeout_sec)
@@ -485,7 +794,12 @@ impl GowinMcp {
                 });
 
             let text = format!("{}\n{}", exec.stdout, exec.stderr);
-            let parsed = parse_cable_names(&text);
+            let parsed = cable::parse_cables(&text);
+            for idcode in device::find_idcodes(&text) {
+                if !detected.contains(&idcode) {
+                    detected.push(idcode);
+                }
+            }
             attempts.push(Attempt {
                 args: argv,
                 exit_code: exec.exit_code,
@@ -497,7 +811,7 @@ impl GowinMcp {
             }
         }
 
-        if cables.is_empty() {
+        if cables.is_empty() && !caps.known() {
             let argv = vec!["--help".into()];
             let exec = exec_with_timeout(&programmer_cli, &argv, None, None, timeout_sec)
                 .await
@@ -509,13 +823,16 @@ impl GowinMcp {
                     stderr: "".into(),
                 });
             let text = format!("{}\n{}", exec.stdout, exec.stderr);
-            cables = parse_cable_names(&text);
+            cables = cable::parse_cables(&text);
             attempts.push(Attempt {
                 args: vec!["--help".into()],
                 exit_code: exec.exit_code,
             });
         }
 
+        let detected_idcodes: Vec<String> =
+            detected.into_iter().map(device::format_idcode).collect();
+
         let meta_json = serde_json::json!({
             "tool": "gowin.list_cables",
             "project_root": project_root.display().to_string(),
@@ -523,6 +840,9 @@ impl GowinMcp {
             "programmer_cli": programmer_cli.display().to_string(),
             "attempts": attempts,
             "cables": cables,
+            "detected_idcodes": detected_idcodes,
+            "capabilities_cached": caps_cached,
+            "list_cables_flag": caps.list_cables_flag,
         });
 
         let log_text = format!(
@@ -533,7 +853,11 @@ impl GowinMcp {
                 .map(|a| format!("{:?} => {}", a.args, a.exit_code))
                 .collect::<Vec<_>>()
                 .join("\n"),
-            cables.join("\n"),
+            cables
+                .iter()
+                .map(describe_cable)
+                .collect::<Vec<_>>()
+                .join("\n"),
         );
 
         let (log_file, meta_file) =
@@ -546,6 +870,9 @@ impl GowinMcp {
             gowin_ide_app_path: gowin_ide_app_path.to_string(),
             cables,
             attempts,
+            detected_idcodes,
+            capabilities: caps,
+            capabilities_cached: caps_cached,
             log_file: log_file.display().to_string(),
             meta_file: meta_file.display().to_string(),
         }))
@@ -570,15 +897,88 @@ impl GowinMcp {
 
         let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
 
-        let fs_file_path = req
-            .fs_file_path
-            .as_deref()
-            .unwrap_or("fpgaOscillator/impl/pnr/fpgaOscillator.fs");
-        let fs_abs = resolve_under(&project_root, fs_file_path);
+        // artifact_id なら .gowin-mcp/artifacts/<id>/ に保存した過去のビルドを書き込む
+        let artifact = match req.artifact_id.as_deref() {
+            Some(_) if req.fs_file_path.is_some() => {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "artifact_id と fs_file_path は同時に指定できません",
+                    None,
+                ));
+            }
+            Some(id) => Some(
+                artifacts::load(&project_root, id)
+                    .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?,
+            ),
+            None => None,
+        };
+        let fs_abs = match artifact.as_ref() {
+            Some((_, fs)) => fs.clone(),
+            None => resolve_under(
+                &project_root,
+                req.fs_file_path
+                    .as_deref()
+                    .unwrap_or("fpgaOscillator/impl/pnr/fpgaOscillator.fs"),
+            ),
+        };
 
-        let device = req.device.unwrap_or_else(|| "GW5A-25A".into());
-        let frequency = req.frequency.unwrap_or_else(|| "15MHz".into());
-        let retries = req.retries.unwrap_or(2);
+        // board は device / frequency / cable / flash の既定値
+        let board = req
+            .board
+            .as_deref()
+            .map(|b| board::find(&project_root, b))
+            .transpose()
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+        let device = req
+            .device
+            .or_else(|| artifact.as_ref().and_then(|(m, _)| m.device.clone()))
+            .or_else(|| board.as_ref().map(|b| b.device.clone()))
+            .unwrap_or_else(|| "GW5A-25A".into());
+        let frequency = req
+            .frequency
+            .or_else(|| board.as_ref().and_then(|b| b.frequency.clone()))
+            .unwrap_or_else(|| "15MHz".into());
+        let frequency = check_device_params(&project_root, &device, &frequency)?;
+        // シリアル/インデックスで選ぶときは、ボードのケーブル名で絞り込まない
+        let cable = req.cable.or_else(|| {
+            board
+                .as_ref()
+                .filter(|_| req.cable_serial.is_none() && req.cable_index.is_none())
+                .and_then(|b| b.cable.clone())
+        });
+        let retries = req.retries.unwrap_or(0);
+        let force = req.force.unwrap_or(false);
+        let verify = req.verify.unwrap_or(false);
+        let target = match req.target.as_deref() {
+            None => programmer::Target::Sram,
+            // ボードのフラッシュ（embflash / exflash）
+            Some("flash") => board
+                .as_ref()
+                .and_then(|b| b.flash.as_deref())
+                .and_then(programmer::Target::parse)
+                .ok_or_else(|| {
+                    McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        "target: \"flash\" には flash を設定した board が必要です。embflash / exflash を直接指定することもできます",
+                        None,
+                    )
+                })?,
+            Some(t) => programmer::Target::parse(t).ok_or_else(|| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("target は sram / embflash / exflash のいずれかを指定してください（指定: {t}）"),
+                    None,
+                )
+            })?,
+        };
+        let spi_address = req
+            .spi_address
+            .as_deref()
+            .map(image::parse_size)
+            .transpose()
+            .map_err(|e| {
+                McpError::new(ErrorCode::INVALID_PARAMS, format!("spi_address: {e}"), None)
+            })?;
         let timeout_sec = req.timeout_sec.unwrap_or(120);
         if timeout_sec == 0 {
             return Err(McpError::new(
@@ -599,48 +999,234 @@ impl GowinMcp {
             ));
         }
 
-        let mut selected_cable = req.cable;
-        let mut list_cables_attempts: Option<Vec<Attempt>> = None;
+        // 書き込み前に .fs を解析し、device と食い違っていれば止める（force で続行）
+        let mut validation_warnings: Vec<String> = Vec::new();
+        let bitstream = if fs_abs
+            .extension()
+            .is_some_and(|e| e.eq_ignore_ascii_case("fs"))
+        {
+            let text = tokio::fs::read_to_string(&fs_abs)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            match bitstream::parse_fs(&text) {
+                Ok(bs) => Some(bs),
+                Err(e) => {
+                    validation_warnings.push(format!("{}: {e}", fs_abs.display()));
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        if let Some(bs) = bitstream.as_ref() {
+            validation_warnings.extend(bitstream::device_mismatches(bs, &device));
+        }
+        if !validation_warnings.is_empty() && !force {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "ビットストリームの検証に失敗しました: {}。意図したものであれば force: true を指定してください",
+                    validation_warnings.join(" / ")
+                ),
+                None,
+            ));
+        }
 
-        if selected_cable.is_none() {
-            // 内部的に list_cables を呼んで先頭を選ぶ
-            let list = self
-                .list_cables(Parameters(ListCablesRequest {
-                    project_root: Some(project_root.display().to_string()),
-                    gowin_ide_app_path: Some(gowin_ide_app_path.to_string()),
-                    timeout_sec: Some(timeout_sec.min(20)),
-                }))
-                .await?
-                .0;
-            list_cables_attempts = Some(list.attempts.clone());
-            selected_cable = list.cables.first().cloned();
+        // ソースが .fs より新しい / 最後のビルドから変わっていれば警告（block なら停止）
+        let stale_policy = req.stale_policy.as_deref().unwrap_or("warn");
+        let staleness = match stale_policy {
+            "off" => None,
+            // 過去のビルドをあえて書くので、現在のソースとは比べない
+            "warn" | "block" if artifact.is_some() => None,
+            // 判定できないときは block なら止め、warn なら警告に残す
+            "warn" | "block" => match stale::check(&project_root, &fs_abs) {
+                Ok(report) => Some(report),
+                Err(e) if stale_policy == "block" && !force => {
+                    return Err(McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        format!(
+                            "ソースとの新旧を確認できません: {e:#}。意図したものであれば force: true を指定してください"
+                        ),
+                        None,
+                    ));
+                }
+                Err(e) => {
+                    validation_warnings.push(format!("ソースとの新旧を確認できません: {e:#}"));
+                    None
+                }
+            },
+            other => {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "stale_policy は warn / block / off のいずれかを指定してください（指定: {other}）"
+                    ),
+                    None,
+                ));
+            }
+        };
+        if let Some(report) = staleness.as_ref().filter(|r| r.stale) {
+            let mut files: Vec<&str> = Vec::new();
+            for f in report
+                .newer_than_fs
+                .iter()
+                .chain(&report.changed_since_build)
+            {
+                if !files.contains(&f.as_str()) {
+                    files.push(f);
+                }
+            }
+            let reason = format!(
+                ".fs がソースより古い可能性があります（変更: {}）",
+                files.join(", ")
+            );
+            if stale_policy == "block" && !force {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "{reason}。ビルドし直すか、意図したものであれば force: true を指定してください"
+                    ),
+                    None,
+                ));
+            }
+            validation_warnings.push(reason);
+        }
+
+        // シリアル/インデックスで特定のボードを狙う場合は、別のボードに書かないよう
+        // --cable 省略での試行はしない
+        let run_id = new_run_id();
+        let (selection, cable_lock) = self
+            .select_cable(
+                &project_root,
+                gowin_ide_app_path,
+                timeout_sec,
+                &run_id,
+                CableRequest {
+                    tool: "gowin.program_fs",
+                    cable,
+                    cable_serial: req.cable_serial.as_deref(),
+                    cable_index: req.cable_index,
+                    hold_sec: timeout_sec * (4 + u64::from(retries)) + 60,
+                    lock_wait_sec: req.lock_wait_sec,
+                },
+            )
+            .await?;
+        let explicit_selection = selection.explicit;
+        let mut selected_cable = selection.cable.clone();
+        let selected_cable_info = selection.info.clone();
+        let list_cables_attempts = selection.list_cables_attempts.clone();
+
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
+        let (caps, _) = capability::load_or_probe(&programmer_cli, timeout_sec.min(20), false)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        cable_index_flag(&caps, &selection)?;
+
+        // 多デバイスチェーンでは位置を指定し、その位置のデバイスを照合する
+        let mut chain_devices: Option<Vec<device::ChainDevice>> = None;
+        let mut chip = selection.chip_idcode.clone();
+        let mut chain_args: Vec<String> = Vec::new();
+        if let Some(position) = req.chain_position {
+            let flag = chain_flag(&caps)?;
+            let (_, _, chain) = scan_chain_devices(
+                &programmer_cli,
+                &caps,
+                &selection,
+                &device,
+                &frequency,
+                timeout_sec.min(20),
+            )
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            let at = chain.iter().find(|d| d.position == position).ok_or_else(|| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "chain_position={position} のデバイスがありません（チェーン上のデバイス数: {}）",
+                        chain.len()
+                    ),
+                    None,
+                )
+            })?;
+            if !at.gowin {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "chain_position={position} のデバイス（IDCODE {}）は Gowin の FPGA ではありません",
+                        at.idcode
+                    ),
+                    None,
+                ));
+            }
+            chip = Some(at.idcode.clone());
+            chain_args = vec![flag.into(), position.to_string()];
+            chain_devices = Some(chain);
+        }
+
+        // 検出されたチップとビットストリームの IDCODE を突き合わせる
+        if let Some(bs_idcode) = bitstream.as_ref().and_then(|bs| bs.idcode)
+            && let Some(chip) = chip
+            && !device::parse_idcode(&chip).is_some_and(|c| device::same_die(c, bs_idcode))
+        {
+            let reason = format!(
+                ".fs の IDCODE {} が検出されたチップ {chip} と一致しません",
+                device::format_idcode(bs_idcode)
+            );
+            if !force {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "ビットストリームの検証に失敗しました: {reason}。意図したものであれば force: true を指定してください"
+                    ),
+                    None,
+                ));
+            }
+            validation_warnings.push(reason);
         }
 
-        let base_args: Vec<String> = vec![
-            "-r".into(),
-            retries.to_string(),
+        let operation = target.operation(verify);
+
+        let index = caps.operation_index(operation).ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                missing_operation(operation).to_string(),
+                None,
+            )
+        })?;
+        let mut base_args: Vec<String> = vec![caps.run_flag().into(), index.to_string()];
+        base_args.extend([
             "--device".into(),
             device.clone(),
             "--fsFile".into(),
             fs_abs.display().to_string(),
             "--frequency".into(),
             frequency.clone(),
-        ];
+        ]);
+        base_args.extend(chain_args.iter().cloned());
+        if target == programmer::Target::ExtFlash {
+            let flag = caps
+                .pick_flag(&["--spiaddr", "--spi-addr", "--spiAddr"])
+                .unwrap_or("--spiaddr");
+            base_args.push(flag.into());
+            base_args.push(format!("0x{:06X}", spi_address.unwrap_or(0)));
+        }
 
         let mut variants: Vec<(String, Vec<String>)> = Vec::new();
-        if let Some(cable) = selected_cable.clone() {
+        if selected_cable.is_some() {
             let mut argv = Vec::new();
             argv.extend(base_args.iter().take(4).cloned());
-            argv.push("--cable".into());
-            argv.push(cable);
+            argv.extend(cable_args(&caps, &selection)?);
             argv.extend(base_args.iter().skip(4).cloned());
             variants.push(("with_cable".into(), argv));
         }
-        variants.push(("without_cable".into(), base_args.clone()));
+        if !explicit_selection {
+            variants.push(("without_cable".into(), base_args.clone()));
+        }
 
         let mut tried: Vec<VariantTried> = Vec::new();
         let mut last_exec: Option<ExecMeta> = None;
         let mut last_label: Option<String> = None;
+        let mut last_argv: Vec<String> = Vec::new();
         let mut cable_from_output: Option<String> = None;
 
         for (label, argv) in variants {
@@ -654,13 +1240,14 @@ impl GowinMcp {
             });
 
             if label == "without_cable" && exec.exit_code != 0 {
-                let parsed = parse_cable_names(&format!("{}\n{}", exec.stdout, exec.stderr));
-                if !parsed.is_empty() {
-                    cable_from_output = Some(parsed[0].clone());
+                let parsed = cable::parse_cables(&format!("{}\n{}", exec.stdout, exec.stderr));
+                if let Some(first) = parsed.into_iter().next() {
+                    cable_from_output = Some(first.name);
                 }
             }
 
             last_label = Some(label.clone());
+            last_argv = argv;
             last_exec = Some(exec);
 
             if last_exec.as_ref().map(|e| e.exit_code).unwrap_or(1) == 0 {
@@ -688,6 +1275,27 @@ impl GowinMcp {
 
             selected_cable = Some(cable);
             last_label = Some("retry_cable_from_output".into());
+            last_argv = argv;
+            last_exec = Some(exec);
+        }
+
+        // 書き込みの失敗（タイムアウト以外）は同じ引数で retries 回までやり直す
+        for attempt in 1..=retries {
+            if last_exec
+                .as_ref()
+                .is_none_or(|e| e.exit_code == 0 || e.timed_out)
+            {
+                break;
+            }
+            let exec = exec_with_timeout(&programmer_cli, &last_argv, None, None, timeout_sec)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            let label = format!("retry_{attempt}");
+            tried.push(VariantTried {
+                label: label.clone(),
+                exit_code: exec.exit_code,
+            });
+            last_label = Some(label);
             last_exec = Some(exec);
         }
 
@@ -699,28 +1307,73 @@ impl GowinMcp {
             )
         })?;
 
+        // 書き込み後にステータスレジスタで DONE / エラービットを確認する
+        let mut post_check = None;
+        let mut post_check_error = None;
+        if req.post_check.unwrap_or(false) && exec.exit_code == 0 && !exec.timed_out {
+            let selection = CableSelection {
+                cable: selected_cable.clone(),
+                info: selected_cable_info.clone(),
+                chip_idcode: None,
+                list_cables_attempts: None,
+                explicit: explicit_selection,
+            };
+            match read_device_status(
+                &programmer_cli,
+                &caps,
+                &selection,
+                &device,
+                &frequency,
+                &chain_args,
+                timeout_sec.min(20),
+            )
+            .await
+            {
+                Ok((_, status)) => post_check = Some(status),
+                Err(e) => post_check_error = Some(e.to_string()),
+            }
+        }
+
         let meta_json = serde_json::json!({
             "tool": "gowin.program_fs",
+            "run_id": run_id,
+            "cable_lock": cable_lock.key,
+            "lock_waited_ms": cable_lock.waited_ms,
             "project_root": project_root.display().to_string(),
             "gowin_ide_app_path": gowin_ide_app_path,
             "programmer_cli": programmer_cli.display().to_string(),
             "fs_file": fs_abs.display().to_string(),
+            "artifact_id": req.artifact_id,
+            "board": board.as_ref().map(|b| &b.name),
+            "target": target.name(),
+            "operation": operation.label(),
+            "spi_address": spi_address,
             "device": device,
             "frequency": frequency,
             "retries": retries,
             "selected_cable": selected_cable,
+            "selected_cable_info": selected_cable_info,
+            "chain_position": req.chain_position,
+            "chain_devices": chain_devices,
+            "bitstream": bitstream.as_ref().map(|bs| &bs.info),
+            "staleness": staleness,
+            "force": force,
+            "validation_warnings": validation_warnings,
             "list_cables_attempts": list_cables_attempts,
             "variants_tried": tried,
             "final_variant": last_label,
+            "post_check": post_check,
+            "post_check_error": post_check_error,
             "exit_code": exec.exit_code,
             "timed_out": exec.timed_out,
             "duration_ms": exec.duration_ms,
         });
 
         let log_text = format!(
-            "programmer_cli: {}\nfs: {}\ndevice: {}\nfrequency: {}\nretries: {}\nselected_cable: {:?}\n\nvariants_tried: {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+            "programmer_cli: {}\nfs: {}\ntarget: {}\ndevice: {}\nfrequency: {}\nretries: {}\nselected_cable: {:?}\n\nvariants_tried: {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
             programmer_cli.display(),
             fs_abs.display(),
+            target.name(),
             device,
             frequency,
             retries,
@@ -739,12 +1392,23 @@ impl GowinMcp {
                 .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
 
         Ok(Json(ProgramFsResponse {
+            run_id,
             project_root: project_root.display().to_string(),
             gowin_ide_app_path: gowin_ide_app_path.to_string(),
             fs_file: fs_abs.display().to_string(),
+            artifact_id: artifact.map(|(m, _)| m.artifact_id),
+            target: target.name().to_string(),
+            operation: operation.label().to_string(),
             selected_cable,
+            selected_cable_info,
+            chain_devices,
+            bitstream: bitstream.map(|bs| bs.info),
+            staleness,
+            validation_warnings,
             list_cables_attempts,
             variants_tried: tried,
+            post_check,
+            post_check_error,
             exit_code: exec.exit_code,
             timed_out: exec.timed_out,
             duration_ms: exec.duration_ms,
@@ -754,165 +1418,4124 @@ impl GowinMcp {
             meta_file: meta_file.display().to_string(),
         }))
     }
-}
 
-#[tool_handler]
-impl ServerHandler for GowinMcp {
-    fn get_info(&self) -> ServerInfo {
-        ServerInfo {
-            instructions: Some(
-                "gw-synth-flash-mcp: gw_sh / programmer_cli をLLMから操作するためのMCPサーバー（個人利用向け）".into(),
-            ),
-            capabilities: ServerCapabilities::builder().enable_tools().build(),
-            ..Default::default()
-        }
-    }
-}
+    #[tool(
+        name = "gowin.convert_bitstream",
+        description = ".fs を .bin / Intel HEX / MCS に変換（Gowin IDE 不要）。オフセット配置・フラッシュサイズへのパディング対応"
+    )]
+    async fn convert_bitstream(
+        &self,
+        params: Parameters<ConvertBitstreamRequest>,
+    ) -> Result<Json<ConvertBitstreamResponse>, McpError> {
+        let req = params.0;
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct RunTclRequest {
-    project_root: Option<String>,
-    gowin_ide_app_path: Option<String>,
-    tcl_path: Option<String>,
-    tcl_inline: Option<String>,
-    timeout_sec: Option<u64>,
-    env: Option<HashMap<String, String>>,
-    expected_files: Option<Vec<String>>,
-}
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct RunTclResponse {
-    project_root: String,
-    gowin_ide_app_path: String,
-    tcl_file_path: String,
-    exit_code: i32,
-    timed_out: bool,
-    duration_ms: u128,
-    stdout: String,
-    stderr: String,
-    expected_checks: Vec<ExpectedFileCheck>,
-    log_file: String,
-    meta_file: String,
-}
+        let fs_file_path = req.fs_file_path.as_deref().ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "fs_file_path が未指定です。変換元の .fs を指定してください",
+                None,
+            )
+        })?;
+        let fs_abs = resolve_under(&project_root, fs_file_path);
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct Attempt {
-    args: Vec<String>,
-    exit_code: i32,
-}
+        let format = match (req.format.as_deref(), req.output_path.as_deref()) {
+            (Some(f), _) => image::ImageFormat::parse(f).ok_or_else(|| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("format は bin / hex / mcs のいずれかを指定してください（指定: {f}）"),
+                    None,
+                )
+            })?,
+            (None, Some(out)) => Path::new(out)
+                .extension()
+                .and_then(|e| image::ImageFormat::parse(&e.to_string_lossy()))
+                .unwrap_or(image::ImageFormat::Bin),
+            (None, None) => image::ImageFormat::Bin,
+        };
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct ListCablesRequest {
-    project_root: Option<String>,
-    gowin_ide_app_path: Option<String>,
-    timeout_sec: Option<u64>,
-}
+        let output_abs = match req.output_path.as_deref() {
+            Some(p) => resolve_under(&project_root, p),
+            None => fs_abs.with_extension(format.extension()),
+        };
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct ListCablesResponse {
-    project_root: String,
-    gowin_ide_app_path: String,
-    cables: Vec<String>,
-    attempts: Vec<Attempt>,
-    log_file: String,
-    meta_file: String,
-}
+        let parse_opt = |v: Option<&str>, name: &str| -> Result<Option<u64>, McpError> {
+            v.map(|s| {
+                image::parse_size(s).map_err(|e| {
+                    McpError::new(ErrorCode::INVALID_PARAMS, format!("{name}: {e}"), None)
+                })
+            })
+            .transpose()
+        };
+        let offset = parse_opt(req.offset.as_deref(), "offset")?.unwrap_or(0);
+        let pad_to = parse_opt(req.pad_to.as_deref(), "pad_to")?;
+        let flash_size =
+            parse_opt(req.flash_size.as_deref(), "flash_size")?.unwrap_or(image::MAX_FLASH_SIZE);
+        let fill = req.fill.unwrap_or(0xFF);
 
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct ProgramFsRequest {
-    project_root: Option<String>,
-    gowin_ide_app_path: Option<String>,
-    fs_file_path: Option<String>,
+        let text = tokio::fs::read_to_string(&fs_abs).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    ".fs ファイルを読めません: {} ({e})。fs_file_path を確認してください",
+                    fs_abs.display()
+                ),
+                None,
+            )
+        })?;
+        let bs = bitstream::parse_fs(&text)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let flash = image::place(&bs.data, offset, pad_to, fill, flash_size)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+        let encoded = image::encode(format, &flash, offset);
+
+        if let Some(parent) = output_abs.parent() {
+            ensure_dir(parent)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        }
+        tokio::fs::write(&output_abs, &encoded)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let image_crc32 = format!("{:08x}", bitstream::crc32(&flash));
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.convert_bitstream",
+            "project_root": project_root.display().to_string(),
+            "fs_file": fs_abs.display().to_string(),
+            "output_file": output_abs.display().to_string(),
+            "format": format.extension(),
+            "offset": offset,
+            "pad_to": pad_to,
+            "flash_size": flash_size,
+            "fill": fill,
+            "image_size": flash.len(),
+            "image_crc32": image_crc32,
+            "bitstream": bs.info,
+        });
+
+        let log_text = format!(
+            "fs: {}\noutput: {}\nformat: {}\noffset: 0x{:X}\npad_to: {:?}\nfill: 0x{:02X}\nbitstream_bytes: {}\nimage_size: {}\nimage_crc32: {}\n",
+            fs_abs.display(),
+            output_abs.display(),
+            format.extension(),
+            offset,
+            pad_to,
+            fill,
+            bs.data.len(),
+            flash.len(),
+            image_crc32,
+        );
+
+        let (log_file, meta_file) = write_run_logs(
+            &project_root,
+            "gowin.convert_bitstream",
+            &meta_json,
+            &log_text,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ConvertBitstreamResponse {
+            project_root: project_root.display().to_string(),
+            fs_file: fs_abs.display().to_string(),
+            output_file: output_abs.display().to_string(),
+            format: format.extension().to_string(),
+            offset,
+            image_size: flash.len() as u64,
+            image_crc32,
+            bitstream: bs.info,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.build_flash_image",
+        description = "TOML / YAML のフラッシュレイアウト（ゴールデン/アップデート/データ）から単一イメージを組み立て、重なり・アラインメントを検証。program: true で書き込みまで実行"
+    )]
+    async fn build_flash_image(
+        &self,
+        params: Parameters<BuildFlashImageRequest>,
+    ) -> Result<Json<BuildFlashImageResponse>, McpError> {
+        let req = params.0;
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let layout_path = req.layout_path.as_deref().ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "layout_path が未指定です。フラッシュレイアウトの TOML / YAML を指定してください",
+                None,
+            )
+        })?;
+        let layout_abs = resolve_under(&project_root, layout_path);
+        let program = req.program.unwrap_or(false);
+
+        let format = match req.format.as_deref() {
+            Some(f) => image::ImageFormat::parse(f).ok_or_else(|| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("format は bin / hex / mcs のいずれかを指定してください（指定: {f}）"),
+                    None,
+                )
+            })?,
+            None => image::ImageFormat::Bin,
+        };
+        if program && format != image::ImageFormat::Bin {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "program: true の場合は format を bin にしてください",
+                None,
+            ));
+        }
+
+        let output_abs = match req.output_path.as_deref() {
+            Some(p) => resolve_under(&project_root, p),
+            None => layout_abs.with_extension(format.extension()),
+        };
+
+        // レイアウト内の相対パスはレイアウトファイルの置き場所基準
+        let base_dir = layout_abs
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_else(|| project_root.clone());
+        let layout_for_task = layout_abs.clone();
+        let (layout, assembled) = tokio::task::spawn_blocking(move || {
+            let layout = flash_layout::load(&layout_for_task)?;
+            let assembled = flash_layout::assemble(&layout, &base_dir)?;
+            anyhow::Ok((layout, assembled))
+        })
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?
+        .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        if !assembled.issues.is_empty() {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "フラッシュレイアウトの検証に失敗しました: {}",
+                    assembled.issues.join(" / ")
+                ),
+                None,
+            ));
+        }
+
+        let encoded = image::encode(format, &assembled.image, 0);
+        if let Some(parent) = output_abs.parent() {
+            ensure_dir(parent)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        }
+        tokio::fs::write(&output_abs, &encoded)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let image_crc32 = format!("{:08x}", bitstream::crc32(&assembled.image));
+
+        let program_result = if program {
+            Some(
+                self.program_fs(Parameters(ProgramFsRequest {
+                    project_root: Some(project_root.display().to_string()),
+                    gowin_ide_app_path: req.gowin_ide_app_path.clone(),
+                    fs_file_path: Some(output_abs.display().to_string()),
+                    device: req.device.clone().or(layout.device.clone()),
+                    frequency: req.frequency.clone(),
+                    retries: None,
+                    timeout_sec: req.timeout_sec,
+                    cable: req.cable.clone(),
+                    cable_serial: req.cable_serial.clone(),
+                    cable_index: req.cable_index,
+                    chain_position: None,
+                    post_check: None,
+                    stale_policy: None,
+                    lock_wait_sec: None,
+                    force: None,
+                    target: Some(layout.target.clone().unwrap_or_else(|| "exflash".into())),
+                    spi_address: Some("0x0".into()),
+                    verify: req.verify,
+                    artifact_id: None,
+                    board: req.board.clone(),
+                }))
+                .await?
+                .0,
+            )
+        } else {
+            None
+        };
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.build_flash_image",
+            "project_root": project_root.display().to_string(),
+            "layout_file": layout_abs.display().to_string(),
+            "output_file": output_abs.display().to_string(),
+            "format": format.extension(),
+            "flash_size": assembled.flash_size,
+            "image_size": assembled.image.len(),
+            "image_crc32": image_crc32,
+            "partitions": assembled.partitions,
+            "program_exit_code": program_result.as_ref().map(|p| p.exit_code),
+            "program_meta_file": program_result.as_ref().map(|p| p.meta_file.clone()),
+        });
+
+        let log_text = format!(
+            "layout: {}\noutput: {}\nformat: {}\nflash_size: 0x{:X}\nimage_size: {}\nimage_crc32: {}\n\n--- partitions ---\n{}\n",
+            layout_abs.display(),
+            output_abs.display(),
+            format.extension(),
+            assembled.flash_size,
+            assembled.image.len(),
+            image_crc32,
+            assembled
+                .partitions
+                .iter()
+                .map(|p| format!(
+                    "{} [{}] 0x{:06X}..0x{:06X} used={} crc32={} {}",
+                    p.name, p.kind, p.offset, p.end, p.used, p.crc32, p.file
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) = write_run_logs(
+            &project_root,
+            "gowin.build_flash_image",
+            &meta_json,
+            &log_text,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(BuildFlashImageResponse {
+            project_root: project_root.display().to_string(),
+            layout_file: layout_abs.display().to_string(),
+            output_file: output_abs.display().to_string(),
+            format: format.extension().to_string(),
+            flash_size: assembled.flash_size,
+            image_size: assembled.image.len() as u64,
+            image_crc32,
+            partitions: assembled.partitions,
+            program: program_result,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.scan_chain",
+        description = "programmer_cli で JTAG チェーンをスキャンし、各デバイスの IDCODE・ファミリ/品名・チェーン上の位置を返す"
+    )]
+    async fn scan_chain(
+        &self,
+        params: Parameters<ScanChainRequest>,
+    ) -> Result<Json<ScanChainResponse>, McpError> {
+        let req = params.0;
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+
+        let device = req.device.unwrap_or_else(|| "GW5A-25A".into());
+        let frequency = req.frequency.unwrap_or_else(|| "15MHz".into());
+        let frequency = check_device_params(&project_root, &device, &frequency)?;
+        let timeout_sec = req.timeout_sec.unwrap_or(30);
+        if timeout_sec == 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "timeout_sec は 1 以上を指定してください（0 は即タイムアウトになります）",
+                None,
+            ));
+        }
+
+        let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
+
+        let run_id = new_run_id();
+        let (selection, cable_lock) = self
+            .select_cable(
+                &project_root,
+                gowin_ide_app_path,
+                timeout_sec,
+                &run_id,
+                CableRequest {
+                    tool: "gowin.scan_chain",
+                    cable: req.cable,
+                    cable_serial: req.cable_serial.as_deref(),
+                    cable_index: req.cable_index,
+                    hold_sec: timeout_sec + 30,
+                    lock_wait_sec: req.lock_wait_sec,
+                },
+            )
+            .await?;
+        let (caps, _) = capability::load_or_probe(&programmer_cli, timeout_sec.min(20), false)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        cable_index_flag(&caps, &selection)?;
+
+        let (argv, exec, devices) = scan_chain_devices(
+            &programmer_cli,
+            &caps,
+            &selection,
+            &device,
+            &frequency,
+            timeout_sec,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.scan_chain",
+            "run_id": run_id,
+            "cable_lock": cable_lock.key,
+            "lock_waited_ms": cable_lock.waited_ms,
+            "project_root": project_root.display().to_string(),
+            "gowin_ide_app_path": gowin_ide_app_path,
+            "programmer_cli": programmer_cli.display().to_string(),
+            "selected_cable": selection.cable,
+            "selected_cable_info": selection.info,
+            "args": argv,
+            "devices": devices,
+            "exit_code": exec.exit_code,
+            "timed_out": exec.timed_out,
+            "duration_ms": exec.duration_ms,
+        });
+
+        let log_text = format!(
+            "command: {} {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- devices ---\n{}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+            programmer_cli.display(),
+            argv,
+            exec.exit_code,
+            exec.timed_out,
+            exec.duration_ms,
+            devices
+                .iter()
+                .map(|d| format!(
+                    "[{}] {} {}",
+                    d.position,
+                    d.idcode,
+                    d.part.as_deref().unwrap_or("(unknown)")
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            exec.stdout,
+            exec.stderr,
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.scan_chain", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ScanChainResponse {
+            run_id,
+            project_root: project_root.display().to_string(),
+            gowin_ide_app_path: gowin_ide_app_path.to_string(),
+            selected_cable: selection.cable,
+            selected_cable_info: selection.info,
+            args: argv,
+            devices,
+            exit_code: exec.exit_code,
+            timed_out: exec.timed_out,
+            duration_ms: exec.duration_ms,
+            stdout: exec.stdout,
+            stderr: exec.stderr,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.device_status",
+        description = "programmer_cli でステータスレジスタと USERCODE を読み、DONE やエラービットを名前付きフラグに分解して返す"
+    )]
+    async fn device_status(
+        &self,
+        params: Parameters<DeviceStatusRequest>,
+    ) -> Result<Json<DeviceStatusResponse>, McpError> {
+        let req = params.0;
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+
+        let device = req.device.unwrap_or_else(|| "GW5A-25A".into());
+        let frequency = req.frequency.unwrap_or_else(|| "15MHz".into());
+        let frequency = check_device_params(&project_root, &device, &frequency)?;
+        let timeout_sec = req.timeout_sec.unwrap_or(30);
+        if timeout_sec == 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "timeout_sec は 1 以上を指定してください（0 は即タイムアウトになります）",
+                None,
+            ));
+        }
+
+        let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
+
+        let run_id = new_run_id();
+        let (selection, cable_lock) = self
+            .select_cable(
+                &project_root,
+                gowin_ide_app_path,
+                timeout_sec,
+                &run_id,
+                CableRequest {
+                    tool: "gowin.device_status",
+                    cable: req.cable,
+                    cable_serial: req.cable_serial.as_deref(),
+                    cable_index: req.cable_index,
+                    hold_sec: timeout_sec * 2 + 30,
+                    lock_wait_sec: req.lock_wait_sec,
+                },
+            )
+            .await?;
+        let (caps, _) = capability::load_or_probe(&programmer_cli, timeout_sec.min(20), false)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        cable_index_flag(&caps, &selection)?;
+
+        let chain_args = match req.chain_position {
+            Some(position) => vec![chain_flag(&caps)?.to_string(), position.to_string()],
+            None => Vec::new(),
+        };
+
+        let (runs, status) = read_device_status(
+            &programmer_cli,
+            &caps,
+            &selection,
+            &device,
+            &frequency,
+            &chain_args,
+            timeout_sec,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let commands: Vec<CommandRun> = runs
+            .into_iter()
+            .map(|(args, exec)| CommandRun {
+                args,
+                exit_code: exec.exit_code,
+                timed_out: exec.timed_out,
+                duration_ms: exec.duration_ms,
+                stdout: exec.stdout,
+                stderr: exec.stderr,
+            })
+            .collect();
+        let configured = status.configured();
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.device_status",
+            "run_id": run_id,
+            "cable_lock": cable_lock.key,
+            "lock_waited_ms": cable_lock.waited_ms,
+            "project_root": project_root.display().to_string(),
+            "gowin_ide_app_path": gowin_ide_app_path,
+            "programmer_cli": programmer_cli.display().to_string(),
+            "device": device,
+            "frequency": frequency,
+            "selected_cable": selection.cable,
+            "selected_cable_info": selection.info,
+            "chain_position": req.chain_position,
+            "status": status,
+            "configured": configured,
+            "commands": commands
+                .iter()
+                .map(|c| serde_json::json!({
+                    "args": c.args,
+                    "exit_code": c.exit_code,
+                    "timed_out": c.timed_out,
+                    "duration_ms": c.duration_ms,
+                }))
+                .collect::<Vec<_>>(),
+        });
+
+        let mut log_text = format!(
+            "programmer_cli: {}\ndevice: {}\nselected_cable: {:?}\n\nstatus_register: {:?}\nusercode: {:?}\nflags: {:?}\nerrors: {:?}\nconfigured: {}\n",
+            programmer_cli.display(),
+            device,
+            selection.cable,
+            status.status_register,
+            status.usercode,
+            status.flags,
+            status.errors,
+            configured,
+        );
+        for c in &commands {
+            log_text.push_str(&format!(
+                "\n=== {:?} ===\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+                c.args, c.exit_code, c.timed_out, c.duration_ms, c.stdout, c.stderr
+            ));
+        }
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.device_status", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(DeviceStatusResponse {
+            run_id,
+            project_root: project_root.display().to_string(),
+            gowin_ide_app_path: gowin_ide_app_path.to_string(),
+            selected_cable: selection.cable,
+            selected_cable_info: selection.info,
+            status,
+            configured,
+            commands,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.reconfigure",
+        description = "programmer_cli の Reprogram 操作で FPGA をフラッシュから再コンフィグする（書き込みはしない）"
+    )]
+    async fn reconfigure(
+        &self,
+        params: Parameters<ReconfigureRequest>,
+    ) -> Result<Json<ReconfigureResponse>, McpError> {
+        let req = params.0;
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+
+        let device = req.device.unwrap_or_else(|| "GW5A-25A".into());
+        let frequency = req.frequency.unwrap_or_else(|| "15MHz".into());
+        let frequency = check_device_params(&project_root, &device, &frequency)?;
+        let timeout_sec = req.timeout_sec.unwrap_or(60);
+        if timeout_sec == 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "timeout_sec は 1 以上を指定してください（0 は即タイムアウトになります）",
+                None,
+            ));
+        }
+
+        let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
+
+        let run_id = new_run_id();
+        let (selection, cable_lock) = self
+            .select_cable(
+                &project_root,
+                gowin_ide_app_path,
+                timeout_sec,
+                &run_id,
+                CableRequest {
+                    tool: "gowin.reconfigure",
+                    cable: req.cable,
+                    cable_serial: req.cable_serial.as_deref(),
+                    cable_index: req.cable_index,
+                    hold_sec: timeout_sec + 30,
+                    lock_wait_sec: req.lock_wait_sec,
+                },
+            )
+            .await?;
+        let (caps, _) = capability::load_or_probe(&programmer_cli, timeout_sec.min(20), false)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        cable_index_flag(&caps, &selection)?;
+
+        let chain_args = match req.chain_position {
+            Some(position) => vec![chain_flag(&caps)?.to_string(), position.to_string()],
+            None => Vec::new(),
+        };
+        let argv = operation_argv(
+            &caps,
+            programmer::Operation::Reprogram,
+            &selection,
+            &device,
+            &frequency,
+            &chain_args,
+        )
+        .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let exec = exec_with_timeout(&programmer_cli, &argv, None, None, timeout_sec)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.reconfigure",
+            "run_id": run_id,
+            "cable_lock": cable_lock.key,
+            "lock_waited_ms": cable_lock.waited_ms,
+            "project_root": project_root.display().to_string(),
+            "gowin_ide_app_path": gowin_ide_app_path,
+            "programmer_cli": programmer_cli.display().to_string(),
+            "device": device,
+            "frequency": frequency,
+            "selected_cable": selection.cable,
+            "selected_cable_info": selection.info,
+            "chain_position": req.chain_position,
+            "list_cables_attempts": selection.list_cables_attempts,
+            "args": argv,
+            "exit_code": exec.exit_code,
+            "timed_out": exec.timed_out,
+            "duration_ms": exec.duration_ms,
+        });
+
+        let log_text = format!(
+            "command: {} {:?}\n\nselected_cable: {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+            programmer_cli.display(),
+            argv,
+            selection.cable,
+            exec.exit_code,
+            exec.timed_out,
+            exec.duration_ms,
+            exec.stdout,
+            exec.stderr,
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.reconfigure", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ReconfigureResponse {
+            run_id,
+            project_root: project_root.display().to_string(),
+            gowin_ide_app_path: gowin_ide_app_path.to_string(),
+            selected_cable: selection.cable,
+            selected_cable_info: selection.info,
+            list_cables_attempts: selection.list_cables_attempts,
+            args: argv,
+            exit_code: exec.exit_code,
+            timed_out: exec.timed_out,
+            duration_ms: exec.duration_ms,
+            stdout: exec.stdout,
+            stderr: exec.stderr,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.read_flash",
+        description = "内蔵/外部フラッシュの領域をファイルに読み出し、compare_with 指定時は .fs / .bin と比較して最初の不一致アドレスを返す"
+    )]
+    async fn read_flash(
+        &self,
+        params: Parameters<ReadFlashRequest>,
+    ) -> Result<Json<ReadFlashResponse>, McpError> {
+        let req = params.0;
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+
+        let device = req.device.unwrap_or_else(|| "GW5A-25A".into());
+        let frequency = req.frequency.unwrap_or_else(|| "15MHz".into());
+        let frequency = check_device_params(&project_root, &device, &frequency)?;
+        let timeout_sec = req.timeout_sec.unwrap_or(300);
+        if timeout_sec == 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "timeout_sec は 1 以上を指定してください（0 は即タイムアウトになります）",
+                None,
+            ));
+        }
+
+        let target_name = req.target.as_deref().unwrap_or("exflash");
+        let target = programmer::Target::parse(target_name);
+        let operation = target.and_then(|t| t.read_operation()).ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("target は embflash / exflash のいずれかを指定してください（指定: {target_name}）"),
+                None,
+            )
+        })?;
+        let target_name = target.map(|t| t.name()).unwrap_or(target_name);
+
+        let parse_opt = |v: Option<&str>, name: &str| -> Result<Option<u64>, McpError> {
+            v.map(|s| {
+                image::parse_size(s).map_err(|e| {
+                    McpError::new(ErrorCode::INVALID_PARAMS, format!("{name}: {e}"), None)
+                })
+            })
+            .transpose()
+        };
+        let address = parse_opt(req.address.as_deref(), "address")?.unwrap_or(0);
+        // 内蔵フラッシュには SPI のアドレスがない
+        if target == Some(programmer::Target::EmbFlash) && address != 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "embflash の読み出しには address を指定できません（指定: 0x{address:06X}）"
+                ),
+                None,
+            ));
+        }
+
+        let compare_abs = req
+            .compare_with
+            .as_deref()
+            .map(|p| resolve_under(&project_root, p));
+        let reference = match compare_abs.as_deref() {
+            Some(path) => Some(load_reference(path).await.map_err(|e| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!("compare_with を読めません: {e}"),
+                    None,
+                )
+            })?),
+            None => None,
+        };
+
+        // length 未指定なら比較対象と同じ長さだけ読む
+        let length = match (
+            parse_opt(req.length.as_deref(), "length")?,
+            reference.as_ref(),
+        ) {
+            (Some(n), _) => n,
+            (None, Some(r)) => r.len() as u64,
+            (None, None) => {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "length が未指定です（compare_with を指定した場合はその長さを使います）",
+                    None,
+                ));
+            }
+        };
+
+        let output_abs = match req.output_path.as_deref() {
+            Some(p) => resolve_under(&project_root, p),
+            None => project_root
+                .join(".gowin-mcp")
+                .join("readback")
+                .join(format!("{target_name}_0x{address:06X}.bin")),
+        };
+        // 読み出しに失敗したときに比較対象を失わないよう、同じファイルは断る
+        if let Some(compare) = compare_abs.as_deref() {
+            let same = ipgen::normalize(compare) == ipgen::normalize(&output_abs)
+                || matches!(
+                    (std::fs::canonicalize(compare), std::fs::canonicalize(&output_abs)),
+                    (Ok(a), Ok(b)) if a == b
+                );
+            if same {
+                return Err(McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    format!(
+                        "output_path が compare_with と同じファイルです（{}）。別のファイルを指定してください",
+                        compare.display()
+                    ),
+                    None,
+                ));
+            }
+        }
+
+        let (_ide_base, _gw_sh, programmer_cli) = gowin_paths(gowin_ide_app_path);
+        ensure_programmer_cli(&programmer_cli, gowin_ide_app_path).await?;
+
+        let run_id = new_run_id();
+        // programmer_cli には一時ファイルに読ませ、成功したときだけ output_path に置き換える
+        // （失敗やロック待ちで前回の読み出し結果を消さないように）
+        let readback_dir = project_root.join(".gowin-mcp").join("readback");
+        let temp_abs = readback_dir.join(format!(".{run_id}.tmp"));
+        for dir in [Some(readback_dir.as_path()), output_abs.parent()]
+            .into_iter()
+            .flatten()
+        {
+            ensure_dir(dir)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        }
+        let (selection, cable_lock) = self
+            .select_cable(
+                &project_root,
+                gowin_ide_app_path,
+                timeout_sec,
+                &run_id,
+                CableRequest {
+                    tool: "gowin.read_flash",
+                    cable: req.cable,
+                    cable_serial: req.cable_serial.as_deref(),
+                    cable_index: req.cable_index,
+                    hold_sec: timeout_sec + 30,
+                    lock_wait_sec: req.lock_wait_sec,
+                },
+            )
+            .await?;
+        let (caps, _) = capability::load_or_probe(&programmer_cli, timeout_sec.min(20), false)
+            .await
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        cable_index_flag(&caps, &selection)?;
+
+        let mut extra = Vec::new();
+        if let Some(position) = req.chain_position {
+            extra.push(chain_flag(&caps)?.to_string());
+            extra.push(position.to_string());
+        }
+        let out_flag = caps
+            .pick_flag(&["--outFile", "--output", "--fsFile"])
+            .unwrap_or("--fsFile");
+        extra.push(out_flag.into());
+        extra.push(temp_abs.display().to_string());
+        if target == Some(programmer::Target::ExtFlash) {
+            let addr_flag = caps
+                .pick_flag(&["--spiaddr", "--spi-addr", "--spiAddr"])
+                .unwrap_or("--spiaddr");
+            extra.push(addr_flag.into());
+            extra.push(format!("0x{address:06X}"));
+        }
+        // 長さ指定に対応していなければ、読み出し後に切り詰める
+        if let Some(flag) = caps.pick_flag(&["--length", "--size", "--readLength"]) {
+            extra.push(flag.into());
+            extra.push(format!("0x{length:X}"));
+        }
+
+        let argv = operation_argv(&caps, operation, &selection, &device, &frequency, &extra)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let exec = exec_with_timeout(&programmer_cli, &argv, None, None, timeout_sec).await;
+        let exec = match exec {
+            Ok(exec) => exec,
+            Err(e) => {
+                let _ = tokio::fs::remove_file(&temp_abs).await;
+                return Err(McpError::new(
+                    ErrorCode::INTERNAL_ERROR,
+                    e.to_string(),
+                    None,
+                ));
+            }
+        };
+
+        let mut read_bytes = None;
+        let mut comparison = None;
+        if exec.exit_code == 0
+            && let Ok(mut data) = tokio::fs::read(&temp_abs).await
+        {
+            if data.len() as u64 > length {
+                data.truncate(length as usize);
+                tokio::fs::write(&temp_abs, &data)
+                    .await
+                    .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            }
+            // 別のファイルシステムへは rename できないのでコピーする
+            if tokio::fs::rename(&temp_abs, &output_abs).await.is_err() {
+                tokio::fs::copy(&temp_abs, &output_abs)
+                    .await
+                    .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            }
+            read_bytes = Some(data.len() as u64);
+            comparison = reference
+                .as_deref()
+                .map(|r| image::compare(r, &data, address));
+        }
+        let _ = tokio::fs::remove_file(&temp_abs).await;
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.read_flash",
+            "run_id": run_id,
+            "cable_lock": cable_lock.key,
+            "lock_waited_ms": cable_lock.waited_ms,
+            "project_root": project_root.display().to_string(),
+            "gowin_ide_app_path": gowin_ide_app_path,
+            "programmer_cli": programmer_cli.display().to_string(),
+            "device": device,
+            "frequency": frequency,
+            "target": target_name,
+            "operation": operation.label(),
+            "address": address,
+            "length": length,
+            "output_file": output_abs.display().to_string(),
+            "read_bytes": read_bytes,
+            "selected_cable": selection.cable,
+            "selected_cable_info": selection.info,
+            "chain_position": req.chain_position,
+            "compare_with": compare_abs.as_ref().map(|p| p.display().to_string()),
+            "comparison": comparison,
+            "args": argv,
+            "exit_code": exec.exit_code,
+            "timed_out": exec.timed_out,
+            "duration_ms": exec.duration_ms,
+        });
+
+        let log_text = format!(
+            "command: {} {:?}\n\ntarget: {}\naddress: 0x{:06X}\nlength: {}\noutput: {}\nread_bytes: {:?}\ncomparison: {:?}\n\nexit_code: {}\ntimed_out: {}\nduration_ms: {}\n\n--- stdout ---\n{}\n\n--- stderr ---\n{}\n",
+            programmer_cli.display(),
+            argv,
+            target_name,
+            address,
+            length,
+            output_abs.display(),
+            read_bytes,
+            comparison,
+            exec.exit_code,
+            exec.timed_out,
+            exec.duration_ms,
+            exec.stdout,
+            exec.stderr,
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.read_flash", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ReadFlashResponse {
+            run_id,
+            project_root: project_root.display().to_string(),
+            gowin_ide_app_path: gowin_ide_app_path.to_string(),
+            target: target_name.to_string(),
+            address,
+            length,
+            output_file: output_abs.display().to_string(),
+            read_bytes,
+            selected_cable: selection.cable,
+            selected_cable_info: selection.info,
+            compare_with: compare_abs.map(|p| p.display().to_string()),
+            comparison,
+            args: argv,
+            exit_code: exec.exit_code,
+            timed_out: exec.timed_out,
+            duration_ms: exec.duration_ms,
+            stdout: exec.stdout,
+            stderr: exec.stderr,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.build_and_program",
+        description = "ビルド → 診断/タイミング/使用率の解析 → ゲート判定 → 書き込みを 1 回で行い、工程ごとの所要時間付きのレポートを返す"
+    )]
+    async fn build_and_program(
+        &self,
+        params: Parameters<BuildAndProgramRequest>,
+    ) -> Result<Json<BuildAndProgramResponse>, McpError> {
+        let req = params.0;
+        let started = Instant::now();
+
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+
+        // 別のボード向けのプロジェクトをビルド・書き込みしないよう、.gprj の品番と照合する
+        if let Some(b) = req.board.as_deref() {
+            check_board_part(&project_root, b).await?;
+        }
+
+        let mut stages: Vec<StageReport> = Vec::new();
+
+        // 1) ビルド
+        let stage_start = Instant::now();
+        // 成果物がこのビルドで作られたかを mtime で見る（秒に切り捨てて比べる）
+        let build_started_ms = now_ms() / 1000 * 1000;
+        let tcl_path = match (&req.tcl_path, &req.tcl_inline) {
+            (None, None) => Some("run_gowin.tcl".to_string()),
+            (p, _) => p.clone(),
+        };
+        let build = self
+            .run_tcl(Parameters(RunTclRequest {
+                project_root: Some(project_root.display().to_string()),
+                gowin_ide_app_path: Some(gowin_ide_app_path.to_string()),
+                tcl_path,
+                tcl_inline: req.tcl_inline.clone(),
+                timeout_sec: req.build_timeout_sec,
+                env: req.env.clone(),
+                expected_files: None,
+                use_cache: Some(req.use_cache.unwrap_or(true)),
+                board: None,
+            }))
+            .await?
+            .0;
+        let build_ok = build.exit_code == 0 && !build.timed_out;
+        stages.push(StageReport {
+            name: "build".into(),
+            status: match (build_ok, build.cache_hit) {
+                (true, true) => "cached",
+                (true, false) => "ok",
+                (false, _) => "failed",
+            }
+            .into(),
+            duration_ms: stage_start.elapsed().as_millis(),
+            detail: Some(build.log_file.clone()),
+        });
+
+        // 2) 解析
+        let stage_start = Instant::now();
+        let diagnostics = report::parse_diagnostics(&format!("{}\n{}", build.stdout, build.stderr));
+        let (errors, warnings): (Vec<_>, Vec<_>) = diagnostics
+            .into_iter()
+            .filter(|d| d.level == "ERROR" || d.level == "WARN")
+            .partition(|d| d.level == "ERROR");
+
+        let fs_abs = match req.fs_file_path.as_deref() {
+            Some(p) => Some(resolve_under(&project_root, p)),
+            None => report::find_latest_fs(&project_root),
+        };
+        let (timing, utilization) = fs_abs
+            .as_deref()
+            .and_then(Path::parent)
+            .map(report::load_reports)
+            .unwrap_or_default();
+        stages.push(StageReport {
+            name: "analyze".into(),
+            status: "ok".into(),
+            duration_ms: stage_start.elapsed().as_millis(),
+            detail: Some(format!(
+                "ERROR {} / WARN {} / timing: {} / resources: {}",
+                errors.len(),
+                warnings.len(),
+                timing
+                    .as_ref()
+                    .map(|t| t.report.as_str())
+                    .unwrap_or("(なし)"),
+                utilization.len()
+            )),
+        });
+
+        // 3) ゲート
+        let stage_start = Instant::now();
+        let mut gates = evaluate_gates(
+            &build,
+            &errors,
+            &warnings,
+            timing.as_ref(),
+            &utilization,
+            &req,
+        );
+        let fs_exists = match fs_abs.as_deref() {
+            Some(p) => tokio::fs::metadata(p).await.is_ok(),
+            None => false,
+        };
+        gates.push(GateResult {
+            name: "bitstream".into(),
+            passed: fs_exists,
+            detail: match fs_abs.as_deref() {
+                Some(p) if fs_exists => p.display().to_string(),
+                Some(p) => format!("見つかりません: {}", p.display()),
+                None => "impl/pnr に .fs が見つかりません".into(),
+            },
+        });
+        // 前回のビルドの .fs / レポートで判定・書き込みしないよう、ビルド開始後に
+        // 更新されたものに限る。キャッシュヒット時は成果物のハッシュが一致済み
+        if fs_exists && !build.cache_hit {
+            let (timing_report, resource_report) = fs_abs
+                .as_deref()
+                .and_then(Path::parent)
+                .map(report::find_reports)
+                .unwrap_or_default();
+            let outdated: Vec<String> = fs_abs
+                .iter()
+                .chain(timing_report.iter())
+                .chain(resource_report.iter())
+                .filter(|p| stale::mtime_ms(p).is_none_or(|m| m < build_started_ms))
+                .map(|p| p.display().to_string())
+                .collect();
+            gates.push(GateResult {
+                name: "fresh_outputs".into(),
+                passed: outdated.is_empty(),
+                detail: if outdated.is_empty() {
+                    "このビルドで更新されています".into()
+                } else {
+                    format!("ビルド開始より古いファイル: {}", outdated.join(", "))
+                },
+            });
+        }
+        let gates_passed = gates.iter().all(|g| g.passed);
+        stages.push(StageReport {
+            name: "gates".into(),
+            status: if gates_passed { "ok" } else { "failed" }.into(),
+            duration_ms: stage_start.elapsed().as_millis(),
+            detail: Some(
+                gates
+                    .iter()
+                    .filter(|g| !g.passed)
+                    .map(|g| g.name.as_str())
+                    .collect::<Vec<_>>()
+                    .join(", "),
+            )
+            .filter(|d| !d.is_empty()),
+        });
+
+        // 4) 書き込み
+        let stage_start = Instant::now();
+        let want_program = req.program.unwrap_or(true);
+        let mut program_error = None;
+        let program = if gates_passed && want_program {
+            // 書き込みの失敗もビルド・ゲートの結果と一緒に返す
+            match self
+                .program_fs(Parameters(ProgramFsRequest {
+                    project_root: Some(project_root.display().to_string()),
+                    gowin_ide_app_path: Some(gowin_ide_app_path.to_string()),
+                    fs_file_path: fs_abs.as_ref().map(|p| p.display().to_string()),
+                    device: req.device.clone(),
+                    frequency: req.frequency.clone(),
+                    retries: None,
+                    timeout_sec: req.program_timeout_sec,
+                    cable: req.cable.clone(),
+                    cable_serial: req.cable_serial.clone(),
+                    cable_index: req.cable_index,
+                    chain_position: req.chain_position,
+                    post_check: req.post_check,
+                    stale_policy: Some("block".into()),
+                    lock_wait_sec: req.lock_wait_sec,
+                    force: None,
+                    target: req.target.clone(),
+                    spi_address: None,
+                    verify: req.verify,
+                    artifact_id: None,
+                    board: req.board.clone(),
+                }))
+                .await
+            {
+                Ok(p) => Some(p.0),
+                Err(e) => {
+                    program_error = Some(e.message.to_string());
+                    None
+                }
+            }
+        } else {
+            None
+        };
+        let programmed = program
+            .as_ref()
+            .is_some_and(|p| p.exit_code == 0 && !p.timed_out);
+        stages.push(StageReport {
+            name: "program".into(),
+            status: match &program {
+                Some(_) if programmed => "ok",
+                Some(_) => "failed",
+                None if program_error.is_some() => "failed",
+                None => "skipped",
+            }
+            .into(),
+            duration_ms: stage_start.elapsed().as_millis(),
+            detail: match &program {
+                Some(p) => Some(p.log_file.clone()),
+                None if program_error.is_some() => program_error.clone(),
+                None if !want_program => Some("program: false".into()),
+                None => Some("ゲートを通過しなかったため書き込みませんでした".into()),
+            },
+        });
+
+        let total_duration_ms = started.elapsed().as_millis();
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.build_and_program",
+            "project_root": project_root.display().to_string(),
+            "gowin_ide_app_path": gowin_ide_app_path,
+            "stages": stages,
+            "gates": gates,
+            "gates_passed": gates_passed,
+            "programmed": programmed,
+            "cache_hit": build.cache_hit,
+            "build_log_file": build.log_file,
+            "artifact_id": build.artifact_id,
+            "program_log_file": program.as_ref().map(|p| &p.log_file),
+            "program_error": program_error,
+            "fs_file": fs_abs.as_ref().map(|p| p.display().to_string()),
+            "errors": errors,
+            "warning_count": warnings.len(),
+            "timing": timing,
+            "utilization": utilization,
+            "total_duration_ms": total_duration_ms,
+        });
+
+        let log_text = format!(
+            "stages:\n{}\n\ngates:\n{}\n\nerrors:\n{}\n\ntiming: {:?}\n\nutilization:\n{}\n\nbuild_log: {}\nprogram_log: {:?}\n",
+            stages
+                .iter()
+                .map(|s| format!(
+                    "  {} {} {}ms {}",
+                    s.name,
+                    s.status,
+                    s.duration_ms,
+                    s.detail.as_deref().unwrap_or("")
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            gates
+                .iter()
+                .map(|g| format!(
+                    "  [{}] {}: {}",
+                    if g.passed { "pass" } else { "FAIL" },
+                    g.name,
+                    g.detail
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            errors
+                .iter()
+                .map(|e| format!("  {} {}", e.code.as_deref().unwrap_or("-"), e.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            timing,
+            utilization
+                .iter()
+                .map(|u| format!("  {} {}/{} ({}%)", u.resource, u.used, u.total, u.percent))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            build.log_file,
+            program.as_ref().map(|p| &p.log_file),
+        );
+
+        let (log_file, meta_file) = write_run_logs(
+            &project_root,
+            "gowin.build_and_program",
+            &meta_json,
+            &log_text,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(BuildAndProgramResponse {
+            project_root: project_root.display().to_string(),
+            gowin_ide_app_path: gowin_ide_app_path.to_string(),
+            stages,
+            gates,
+            gates_passed,
+            programmed,
+            cache_hit: build.cache_hit,
+            build_exit_code: build.exit_code,
+            build_timed_out: build.timed_out,
+            build_log_file: build.log_file,
+            artifact_id: build.artifact_id,
+            errors,
+            warnings,
+            timing,
+            utilization,
+            fs_file: fs_abs.map(|p| p.display().to_string()),
+            program,
+            program_error,
+            total_duration_ms,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.build_trends",
+        description = "ビルド履歴（.gowin-mcp/history/builds.jsonl）から、リソース使用率・Fmax/WNS・警告数・所要時間などを指標ごとの時系列で返す"
+    )]
+    async fn build_trends(
+        &self,
+        params: Parameters<BuildTrendsRequest>,
+    ) -> Result<Json<BuildTrendsResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let limit = req.limit.unwrap_or(50);
+        if limit == 0 {
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "limit は 1 以上を指定してください",
+                None,
+            ));
+        }
+        let include_failed = req.include_failed.unwrap_or(false);
+        let metric_filter = req.metrics.unwrap_or_default();
+
+        let all = history::load(&project_root);
+        let total_builds = all.len();
+        let mut entries: Vec<history::HistoryEntry> = all
+            .into_iter()
+            .filter(|e| include_failed || e.success)
+            .collect();
+        let skip = entries.len().saturating_sub(limit);
+        entries.drain(..skip);
+
+        // タイミングを満たしていたビルドの次で満たさなくなったもの
+        let mut timing_broken = Vec::new();
+        let mut previous_met: Option<bool> = None;
+        for e in &entries {
+            let met = e.timing.as_ref().and_then(|t| t.met);
+            if previous_met == Some(true) && met == Some(false) {
+                timing_broken.push(e.run_id.clone());
+            }
+            if met.is_some() {
+                previous_met = met;
+            }
+        }
+
+        let series = history::trends(&entries, &metric_filter);
+        let history_file = history::history_path(&project_root);
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.build_trends",
+            "project_root": project_root.display().to_string(),
+            "history_file": history_file.display().to_string(),
+            "metrics": metric_filter,
+            "limit": limit,
+            "include_failed": include_failed,
+            "total_builds": total_builds,
+            "builds": entries.len(),
+            "timing_broken": timing_broken,
+        });
+
+        let log_text = format!(
+            "history: {}\nbuilds: {}/{}\ntiming_broken: {:?}\n\n{}\n",
+            history_file.display(),
+            entries.len(),
+            total_builds,
+            timing_broken,
+            series
+                .iter()
+                .map(|s| format!(
+                    "  {}: first {} / last {} / min {} / max {}",
+                    s.metric, s.first, s.last, s.min, s.max
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.build_trends", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(BuildTrendsResponse {
+            project_root: project_root.display().to_string(),
+            history_file: history_file.display().to_string(),
+            total_builds,
+            builds: entries.len(),
+            timing_broken,
+            series,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.compare_runs",
+        description = "2 つのビルド（run_id）を比較し、リソース使用率・クロックごとのタイミング・警告コード・Tcl/ビルドオプションの差分を返す"
+    )]
+    async fn compare_runs(
+        &self,
+        params: Parameters<CompareRunsRequest>,
+    ) -> Result<Json<CompareRunsResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let entries = history::load(&project_root);
+        let not_found = |id: &str| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "run_id {id} がビルド履歴にありません（{}）",
+                    history::history_path(&project_root).display()
+                ),
+                None,
+            )
+        };
+
+        // run_id 省略時は最新のビルド、base_run_id 省略時はそれより前の最後の正常なビルド
+        let target_pos = match req.run_id.as_deref() {
+            Some(id) => entries
+                .iter()
+                .position(|e| e.run_id == id)
+                .ok_or_else(|| not_found(id))?,
+            None => entries.len().checked_sub(1).ok_or_else(|| {
+                McpError::new(
+                    ErrorCode::INVALID_PARAMS,
+                    "ビルド履歴がありません。gowin.run_tcl か gowin.build_and_program でビルドしてください",
+                    None,
+                )
+            })?,
+        };
+        let target = entries[target_pos].clone();
+        let base = match req.base_run_id.as_deref() {
+            Some(id) => history::find(&project_root, id).ok_or_else(|| not_found(id))?,
+            None => entries[..target_pos]
+                .iter()
+                .rev()
+                .find(|e| e.success && e.timing.as_ref().and_then(|t| t.met) != Some(false))
+                .cloned()
+                .ok_or_else(|| {
+                    McpError::new(
+                        ErrorCode::INVALID_PARAMS,
+                        format!(
+                            "{} より前に正常なビルドがありません。base_run_id を指定してください",
+                            target.run_id
+                        ),
+                        None,
+                    )
+                })?,
+        };
+
+        let utilization = compare::utilization(&base, &target);
+        let clocks = compare::clocks(&base, &target);
+        let warnings = compare::warnings(&base, &target);
+        let new_warning_codes: Vec<String> = warnings
+            .iter()
+            .filter(|w| w.base_count == 0)
+            .map(|w| w.code.clone())
+            .collect();
+        let removed_warning_codes: Vec<String> = warnings
+            .iter()
+            .filter(|w| w.count == 0)
+            .map(|w| w.code.clone())
+            .collect();
+
+        // Tcl とビルドオプションは履歴の写し（古い履歴は成果物）から
+        let mut notes = Vec::new();
+        let base_inputs = compare::build_inputs(&project_root, &base);
+        let target_inputs = compare::build_inputs(&project_root, &target);
+        for (entry, inputs) in [(&base, &base_inputs), (&target, &target_inputs)] {
+            if inputs.is_none() {
+                notes.push(format!(
+                    "{} の Tcl が記録されていないため Tcl / ビルドオプションは比較できません",
+                    entry.run_id
+                ));
+            }
+        }
+        let (tcl, options) = match (&base_inputs, &target_inputs) {
+            (Some((base_tcl, base_opts)), Some((tcl, opts))) => (
+                Some(compare::text_diff(base_tcl, tcl)),
+                Some(compare::options(base_opts.as_ref(), opts.as_ref())),
+            ),
+            _ => (None, None),
+        };
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.compare_runs",
+            "project_root": project_root.display().to_string(),
+            "base_run_id": base.run_id,
+            "run_id": target.run_id,
+            "utilization": utilization,
+            "clocks": clocks,
+            "warnings": warnings,
+            "tcl": tcl,
+            "options": options,
+            "notes": notes,
+        });
+
+        let log_text = format!(
+            "base: {}\nrun: {}\n\nutilization:\n{}\n\nclocks:\n{}\n\nnew_warning_codes: {:?}\nremoved_warning_codes: {:?}\n\ntcl: {:?}\noptions: {:?}\n",
+            base.run_id,
+            target.run_id,
+            utilization
+                .iter()
+                .map(|u| format!(
+                    "  {}: {:?}% -> {:?}% ({:?})",
+                    u.resource, u.base_percent, u.percent, u.delta_percent
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            clocks
+                .iter()
+                .map(|c| format!(
+                    "  {}: {:?}MHz -> {:?}MHz ({:?})",
+                    c.clock, c.base_fmax_mhz, c.fmax_mhz, c.delta_fmax_mhz
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            new_warning_codes,
+            removed_warning_codes,
+            tcl,
+            options,
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.compare_runs", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(CompareRunsResponse {
+            project_root: project_root.display().to_string(),
+            base_run_id: base.run_id.clone(),
+            run_id: target.run_id.clone(),
+            utilization,
+            clocks,
+            warnings,
+            new_warning_codes,
+            removed_warning_codes,
+            tcl,
+            options,
+            notes,
+            base,
+            target,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.sdc_list",
+        description = "タイミング制約（.sdc）を解析し、クロック・入出力遅延・例外（false path など）を一覧。コマンドとトップモジュールのポート参照を検証"
+    )]
+    async fn sdc_list(
+        &self,
+        params: Parameters<SdcListRequest>,
+    ) -> Result<Json<SdcListResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let sdc_abs = resolve_constraint(&project_root, req.sdc_path.as_deref(), "sdc")?;
+        let text = tokio::fs::read_to_string(&sdc_abs).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("{}: {e}", sdc_abs.display()),
+                None,
+            )
+        })?;
+        let (top, top_error) = match hdl::top_level(&project_root, req.top_module.as_deref()) {
+            Ok(top) => (Some(top), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let file = sdc::parse(&text);
+        let issues = sdc::validate(&file, top.as_ref().map(|t| t.ports.as_slice()));
+        let valid = !issues.iter().any(|i| i.level == "error");
+        let clocks = sdc::clocks(&file);
+        let io_delays = sdc::io_delays(&file);
+        let exceptions = sdc::exceptions(&file);
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.sdc_list",
+            "project_root": project_root.display().to_string(),
+            "sdc_file": sdc_abs.display().to_string(),
+            "top_module": top.as_ref().map(|t| &t.module),
+            "top_error": top_error,
+            "commands": file.commands.len(),
+            "clocks": clocks,
+            "issues": issues,
+            "valid": valid,
+        });
+
+        let log_text = format!(
+            "sdc: {}\ntop: {:?}\ncommands: {}\nclocks: {}\nio_delays: {}\nexceptions: {}\n\nissues:\n{}\n",
+            sdc_abs.display(),
+            top.as_ref().map(|t| &t.module),
+            file.commands.len(),
+            clocks.len(),
+            io_delays.len(),
+            exceptions.len(),
+            issues
+                .iter()
+                .map(|i| format!("  {} {:?}: {}", i.level, i.line, i.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.sdc_list", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(SdcListResponse {
+            project_root: project_root.display().to_string(),
+            sdc_file: sdc_abs.display().to_string(),
+            top_module: top.map(|t| t.module),
+            top_error,
+            clocks,
+            io_delays,
+            exceptions,
+            commands: file.commands,
+            issues,
+            valid,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.sdc_edit",
+        description = "タイミング制約（.sdc）のコマンドを追加 / 変更 / 削除。構文とポート参照を検証し、新たなエラーがあれば書き込まない（force で続行、dry_run で確認のみ）"
+    )]
+    async fn sdc_edit(
+        &self,
+        params: Parameters<SdcEditRequest>,
+    ) -> Result<Json<SdcEditResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+
+        let action = req.action.as_deref().unwrap_or("add");
+        if !["add", "modify", "remove"].contains(&action) {
+            return Err(invalid(format!(
+                "action は add / modify / remove のいずれかを指定してください（指定: {action}）"
+            )));
+        }
+        let dry_run = req.dry_run.unwrap_or(false);
+        let force = req.force.unwrap_or(false);
+
+        // 追加なら新しいファイルも作れる
+        let sdc_abs = match (req.sdc_path.as_deref(), action) {
+            (Some(p), "add") => resolve_under(&project_root, p),
+            (p, _) => resolve_constraint(&project_root, p, "sdc")?,
+        };
+        let original = match tokio::fs::read_to_string(&sdc_abs).await {
+            Ok(t) => t,
+            Err(_) if action == "add" => String::new(),
+            Err(e) => return Err(invalid(format!("{}: {e}", sdc_abs.display()))),
+        };
+        let before_file = sdc::parse(&original);
+
+        let new_command = match (action, req.command.as_deref()) {
+            ("remove", _) => None,
+            (_, Some(c)) => Some(
+                sdc::parse_single(c)
+                    .map_err(|e| invalid(format!("command を解析できません: {e}")))?,
+            ),
+            (_, None) => return Err(invalid(format!("{action} には command が必要です"))),
+        };
+
+        // 変更 / 削除の対象は index か、クロック名
+        let target = if action == "add" {
+            None
+        } else {
+            let found: Vec<&sdc::Command> = match (req.index, req.clock.as_deref()) {
+                (Some(i), _) => before_file
+                    .commands
+                    .iter()
+                    .filter(|c| c.index == i)
+                    .collect(),
+                (None, Some(name)) => {
+                    let indices: Vec<usize> = sdc::clocks(&before_file)
+                        .into_iter()
+                        .filter(|k| k.name == name)
+                        .map(|k| k.index)
+                        .collect();
+                    before_file
+                        .commands
+                        .iter()
+                        .filter(|c| indices.contains(&c.index))
+                        .collect()
+                }
+                (None, None) => {
+                    return Err(invalid(format!(
+                        "{action} には index（gowin.sdc_list の commands[].index）か clock を指定してください"
+                    )));
+                }
+            };
+            match found.as_slice() {
+                [only] => Some((*only).clone()),
+                [] => return Err(invalid("対象のコマンドが見つかりません".into())),
+                _ => {
+                    return Err(invalid(
+                        "対象が複数あります。index で指定してください".into(),
+                    ));
+                }
+            }
+        };
+
+        let updated = match (&target, &new_command) {
+            (None, Some(cmd)) => sdc::append(&original, &cmd.text),
+            (Some(t), cmd) => sdc::replace(&original, t, cmd.as_ref().map(|c| c.text.as_str())),
+            (None, None) => original.clone(),
+        };
+
+        let top = hdl::top_level(&project_root, req.top_module.as_deref()).ok();
+        let ports = top.as_ref().map(|t| t.ports.as_slice());
+        let after_file = sdc::parse(&updated);
+        let before_errors: Vec<String> = sdc::validate(&before_file, ports)
+            .into_iter()
+            .filter(|i| i.level == "error")
+            .map(|i| i.message)
+            .collect();
+        let issues = sdc::validate(&after_file, ports);
+        let introduced: Vec<sdc::Issue> = issues
+            .iter()
+            .filter(|i| i.level == "error" && !before_errors.contains(&i.message))
+            .cloned()
+            .collect();
+        if !introduced.is_empty() && !force {
+            return Err(invalid(format!(
+                "変更後の制約にエラーがあります: {}。意図したものであれば force: true を指定してください",
+                introduced
+                    .iter()
+                    .map(|i| i.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" / ")
+            )));
+        }
+
+        let written = !dry_run && updated != original;
+        if written {
+            if let Some(parent) = sdc_abs.parent() {
+                ensure_dir(parent)
+                    .await
+                    .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            }
+            tokio::fs::write(&sdc_abs, &updated)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        }
+
+        let before = target.as_ref().map(|t| t.text.clone());
+        let after = new_command.as_ref().map(|c| c.text.clone());
+        let meta_json = serde_json::json!({
+            "tool": "gowin.sdc_edit",
+            "project_root": project_root.display().to_string(),
+            "sdc_file": sdc_abs.display().to_string(),
+            "action": action,
+            "index": target.as_ref().map(|t| t.index),
+            "before": before,
+            "after": after,
+            "introduced_errors": introduced,
+            "force": force,
+            "dry_run": dry_run,
+            "written": written,
+        });
+
+        let log_text = format!(
+            "sdc: {}\naction: {}\nbefore: {:?}\nafter: {:?}\nwritten: {}\n\nissues:\n{}\n",
+            sdc_abs.display(),
+            action,
+            before,
+            after,
+            written,
+            issues
+                .iter()
+                .map(|i| format!("  {} {:?}: {}", i.level, i.line, i.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.sdc_edit", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(SdcEditResponse {
+            project_root: project_root.display().to_string(),
+            sdc_file: sdc_abs.display().to_string(),
+            action: action.to_string(),
+            before,
+            after,
+            written,
+            dry_run,
+            clocks: sdc::clocks(&after_file),
+            issues,
+            content: dry_run.then_some(updated),
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.cst_list",
+        description = "物理制約（.cst）を解析し、ポートごとのピン割り当て（IO_LOC / IO_PORT の IO_TYPE・PULL_MODE・DRIVE など）を一覧。ピンの重複、未知の属性、トップモジュールにないポートを検証"
+    )]
+    async fn cst_list(
+        &self,
+        params: Parameters<CstListRequest>,
+    ) -> Result<Json<CstListResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let cst_abs = resolve_constraint(&project_root, req.cst_path.as_deref(), "cst")?;
+        let text = tokio::fs::read_to_string(&cst_abs).await.map_err(|e| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!("{}: {e}", cst_abs.display()),
+                None,
+            )
+        })?;
+        let (top, top_error) = match hdl::top_level(&project_root, req.top_module.as_deref()) {
+            Ok(top) => (Some(top), None),
+            Err(e) => (None, Some(e.to_string())),
+        };
+
+        let file = cst::parse(&text);
+        let pinout = pinout::for_project(&project_root);
+        let pinout_note = pinout
+            .is_none()
+            .then(|| pinout::missing_reason(&project_root))
+            .flatten();
+        let issues = cst::validate(
+            &file,
+            top.as_ref().map(|t| t.ports.as_slice()),
+            pinout
+                .as_ref()
+                .map(|(db, package, _)| (db, package.as_str())),
+        );
+        let valid = !issues.iter().any(|i| i.level == "error");
+        let pins = cst::assignments(&file);
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.cst_list",
+            "project_root": project_root.display().to_string(),
+            "cst_file": cst_abs.display().to_string(),
+            "top_module": top.as_ref().map(|t| &t.module),
+            "top_error": top_error,
+            "pinout_file": pinout.as_ref().map(|(_, _, path)| path.display().to_string()),
+            "package": pinout.as_ref().map(|(_, package, _)| package),
+            "pinout_note": pinout_note,
+            "pins": pins,
+            "issues": issues,
+            "valid": valid,
+        });
+
+        let log_text = format!(
+            "cst: {}\ntop: {:?}\nstatements: {}\n\npins:\n{}\n\nissues:\n{}\n",
+            cst_abs.display(),
+            top.as_ref().map(|t| &t.module),
+            file.statements.len(),
+            pins.iter()
+                .map(|p| format!("  {} -> {} {:?}", p.port, p.pins.join(","), p.io_type))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            issues
+                .iter()
+                .map(|i| format!("  {} {:?}: {}", i.level, i.line, i.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.cst_list", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(CstListResponse {
+            project_root: project_root.display().to_string(),
+            cst_file: cst_abs.display().to_string(),
+            top_module: top.map(|t| t.module),
+            top_error,
+            pinout_file: pinout
+                .as_ref()
+                .map(|(_, _, path)| path.display().to_string()),
+            package: pinout.map(|(_, package, _)| package),
+            pinout_note,
+            pins,
+            statements: file.statements,
+            issues,
+            valid,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.cst_edit",
+        description = "物理制約（.cst）のピン割り当てを追加 / 変更 / 削除（ピン、IO_TYPE、PULL_MODE、DRIVE などの属性）。新たなエラーがあれば書き込まない（force で続行、dry_run で確認のみ）"
+    )]
+    async fn cst_edit(
+        &self,
+        params: Parameters<CstEditRequest>,
+    ) -> Result<Json<CstEditResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+
+        let action = req.action.as_deref().unwrap_or("add");
+        if !["add", "modify", "remove"].contains(&action) {
+            return Err(invalid(format!(
+                "action は add / modify / remove のいずれかを指定してください（指定: {action}）"
+            )));
+        }
+        let port = req
+            .port
+            .as_deref()
+            .map(str::trim)
+            .filter(|p| !p.is_empty())
+            .ok_or_else(|| invalid("port を指定してください".into()))?;
+        let dry_run = req.dry_run.unwrap_or(false);
+        let force = req.force.unwrap_or(false);
+
+        let cst_abs = match (req.cst_path.as_deref(), action) {
+            (Some(p), "add") => resolve_under(&project_root, p),
+            (p, _) => resolve_constraint(&project_root, p, "cst")?,
+        };
+        let original = match tokio::fs::read_to_string(&cst_abs).await {
+            Ok(t) => t,
+            Err(_) if action == "add" => String::new(),
+            Err(e) => return Err(invalid(format!("{}: {e}", cst_abs.display()))),
+        };
+        let before_file = cst::parse(&original);
+        let before = cst::assignments(&before_file)
+            .into_iter()
+            .find(|a| a.port == port);
+
+        // board の既定のピン（信号名が port と同じもの）で未指定の項目を埋める
+        let board_pin = match req.board.as_deref() {
+            Some(name) if action != "remove" => {
+                let b = board::find(&project_root, name).map_err(|e| invalid(e.to_string()))?;
+                Some(b.pins.get(port).cloned().ok_or_else(|| {
+                    invalid(format!(
+                        "board {} に {port} の既定のピンはありません（{}）",
+                        b.name,
+                        b.pins.keys().cloned().collect::<Vec<_>>().join(", ")
+                    ))
+                })?)
+            }
+            _ => None,
+        };
+        let pin = req
+            .pin
+            .clone()
+            .or_else(|| board_pin.as_ref().map(|b| b.pin.clone()));
+        let io_type = req
+            .io_type
+            .clone()
+            .or_else(|| board_pin.as_ref().and_then(|b| b.io_type.clone()));
+        let pull_mode = req
+            .pull_mode
+            .clone()
+            .or_else(|| board_pin.as_ref().and_then(|b| b.pull_mode.clone()));
+        let pins: Option<Vec<String>> = pin.as_deref().map(|p| {
+            p.split(',')
+                .map(str::trim)
+                .filter(|p| !p.is_empty())
+                .map(str::to_string)
+                .collect()
+        });
+        // 指定された属性（値が空なら削除）
+        let mut changes: Vec<(String, String)> = Vec::new();
+        for (k, v) in [
+            ("IO_TYPE", &io_type),
+            ("PULL_MODE", &pull_mode),
+            ("DRIVE", &req.drive),
+        ] {
+            if let Some(v) = v {
+                changes.push((k.to_string(), v.trim().to_string()));
+            }
+        }
+        for (k, v) in req.attributes.iter().flatten() {
+            changes.push((k.trim().to_ascii_uppercase(), v.trim().to_string()));
+        }
+
+        let statements: Vec<&cst::Statement> = before_file
+            .statements
+            .iter()
+            .filter(|s| s.target.as_deref() == Some(port))
+            .collect();
+        let loc = statements.iter().find(|s| s.keyword == "IO_LOC");
+        let port_stmts: Vec<&&cst::Statement> = statements
+            .iter()
+            .filter(|s| s.keyword == "IO_PORT")
+            .collect();
+
+        let mut edits: Vec<(&cst::Statement, Option<String>)> = Vec::new();
+        let mut appended: Vec<String> = Vec::new();
+        match action {
+            "add" => {
+                if before.is_some() {
+                    return Err(invalid(format!(
+                        "{port} はすでに割り当てられています。変更は action: \"modify\" を使ってください"
+                    )));
+                }
+                let pins = pins
+                    .filter(|p| !p.is_empty())
+                    .ok_or_else(|| invalid("add には pin が必要です".into()))?;
+                appended.push(cst::loc_statement(port, &pins));
+                let attrs: Vec<(String, String)> =
+                    changes.into_iter().filter(|(_, v)| !v.is_empty()).collect();
+                if !attrs.is_empty() {
+                    appended.push(cst::port_statement(port, &attrs));
+                }
+            }
+            "modify" => {
+                if before.is_none() {
+                    return Err(invalid(format!("{port} の割り当てがありません")));
+                }
+                if pins.is_none() && changes.is_empty() {
+                    return Err(invalid(
+                        "modify には pin か属性（io_type / pull_mode / drive / attributes）が必要です"
+                            .into(),
+                    ));
+                }
+                if let Some(pins) = pins.as_ref() {
+                    let text = cst::loc_statement(port, pins);
+                    match loc {
+                        Some(s) => edits.push((s, Some(text))),
+                        None => appended.push(text),
+                    }
+                }
+                if !changes.is_empty() {
+                    // 既存の属性の順序を保ったまま上書き・追加し、IO_PORT は 1 つにまとめる
+                    let mut attrs: Vec<(String, String)> = Vec::new();
+                    for s in &port_stmts {
+                        for (k, v) in s.attributes() {
+                            let v = v.unwrap_or_default();
+                            match attrs.iter_mut().find(|(a, _)| *a == k) {
+                                Some(slot) => slot.1 = v,
+                                None => attrs.push((k, v)),
+                            }
+                        }
+                    }
+                    for (k, v) in changes {
+                        match attrs.iter().position(|(a, _)| *a == k) {
+                            Some(i) if v.is_empty() => {
+                                attrs.remove(i);
+                            }
+                            Some(i) => attrs[i].1 = v,
+                            None if v.is_empty() => {}
+                            None => attrs.push((k, v)),
+                        }
+                    }
+                    let text = (!attrs.is_empty()).then(|| cst::port_statement(port, &attrs));
+                    match (port_stmts.split_first(), text) {
+                        (Some((first, rest)), text) => {
+                            edits.push((first, text));
+                            edits.extend(rest.iter().map(|s| (**s, None)));
+                        }
+                        (None, Some(text)) => appended.push(text),
+                        (None, None) => {}
+                    }
+                }
+            }
+            _ => {
+                if statements.is_empty() {
+                    return Err(invalid(format!("{port} の割り当てがありません")));
+                }
+                edits.extend(statements.iter().map(|s| (*s, None)));
+            }
+        }
+        let updated = cst::append(&cst::apply(&original, &edits), &appended);
+
+        let top = hdl::top_level(&project_root, req.top_module.as_deref()).ok();
+        let ports = top.as_ref().map(|t| t.ports.as_slice());
+        let pinout = pinout::for_project(&project_root);
+        let pinout_note = pinout
+            .is_none()
+            .then(|| pinout::missing_reason(&project_root))
+            .flatten();
+        let db = pinout
+            .as_ref()
+            .map(|(db, package, _)| (db, package.as_str()));
+        let after_file = cst::parse(&updated);
+        let before_errors: Vec<String> = cst::validate(&before_file, ports, db)
+            .into_iter()
+            .filter(|i| i.level == "error")
+            .map(|i| i.message)
+            .collect();
+        let issues = cst::validate(&after_file, ports, db);
+        let introduced: Vec<sdc::Issue> = issues
+            .iter()
+            .filter(|i| i.level == "error" && !before_errors.contains(&i.message))
+            .cloned()
+            .collect();
+        if !introduced.is_empty() && !force {
+            return Err(invalid(format!(
+                "変更後の制約にエラーがあります: {}。意図したものであれば force: true を指定してください",
+                introduced
+                    .iter()
+                    .map(|i| i.message.as_str())
+                    .collect::<Vec<_>>()
+                    .join(" / ")
+            )));
+        }
+
+        let written = !dry_run && updated != original;
+        if written {
+            if let Some(parent) = cst_abs.parent() {
+                ensure_dir(parent)
+                    .await
+                    .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+            }
+            tokio::fs::write(&cst_abs, &updated)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+        }
+
+        let after = cst::assignments(&after_file)
+            .into_iter()
+            .find(|a| a.port == port);
+        let meta_json = serde_json::json!({
+            "tool": "gowin.cst_edit",
+            "project_root": project_root.display().to_string(),
+            "cst_file": cst_abs.display().to_string(),
+            "action": action,
+            "port": port,
+            "before": before,
+            "after": after,
+            "introduced_errors": introduced,
+            "force": force,
+            "dry_run": dry_run,
+            "written": written,
+            "pinout_note": pinout_note,
+        });
+
+        let log_text = format!(
+            "cst: {}\naction: {} {}\nbefore: {:?}\nafter: {:?}\nwritten: {}\n\nissues:\n{}\n",
+            cst_abs.display(),
+            action,
+            port,
+            before.as_ref().map(|a| a.pins.join(",")),
+            after.as_ref().map(|a| a.pins.join(",")),
+            written,
+            issues
+                .iter()
+                .map(|i| format!("  {} {:?}: {}", i.level, i.line, i.message))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.cst_edit", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(CstEditResponse {
+            project_root: project_root.display().to_string(),
+            cst_file: cst_abs.display().to_string(),
+            action: action.to_string(),
+            port: port.to_string(),
+            before,
+            after,
+            written,
+            dry_run,
+            issues,
+            pinout_note,
+            content: dry_run.then_some(updated),
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.import_pinout",
+        description = "Gowin のピン配置表（Pinout の xlsx を CSV で書き出したもの）を取り込み、.cst の検証（ピンの存在・専用ピン・バンクの VCCIO・差動ペア）に使うデータベースを .gowin-mcp/pinout/ に保存"
+    )]
+    async fn import_pinout(
+        &self,
+        params: Parameters<ImportPinoutRequest>,
+    ) -> Result<Json<ImportPinoutResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+
+        let csv_abs = resolve_under(
+            &project_root,
+            req.csv_path
+                .as_deref()
+                .ok_or_else(|| invalid("csv_path を指定してください".into()))?,
+        );
+        let bytes = tokio::fs::read(&csv_abs)
+            .await
+            .map_err(|e| invalid(format!("{}: {e}", csv_abs.display())))?;
+
+        // デバイスは指定 → .gprj の品番 / デバイス名
+        let gprj_device = match gprj::find(&project_root) {
+            Some(p) => tokio::fs::read_to_string(&p)
+                .await
+                .ok()
+                .and_then(|t| gprj::device(&t)),
+            None => None,
+        };
+        let device_base = req
+            .device
+            .as_deref()
+            .or(gprj_device
+                .as_ref()
+                .and_then(|(_, pn)| pn.as_deref())
+                .or(gprj_device.as_ref().map(|(name, _)| name.as_str())))
+            .and_then(device::device_base)
+            .ok_or_else(|| {
+                invalid(
+                    "device（GW1NR-9 など）を指定してください（.gprj からも分かりませんでした）"
+                        .into(),
+                )
+            })?;
+
+        let (db, notes) = pinout::import_csv(
+            &String::from_utf8_lossy(&bytes),
+            &device_base,
+            &csv_abs.display().to_string(),
+            req.package.as_deref(),
+            now_ms(),
+        )
+        .map_err(|e| invalid(format!("{}: {e}", csv_abs.display())))?;
+        let pinout_file = pinout::save(&project_root, &db)
+            .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        let io_pads = db.pads.iter().filter(|p| p.is_io()).count();
+        let pairs = db
+            .pads
+            .iter()
+            .filter(|p| p.pair.is_some() && p.true_side == Some(true))
+            .count();
+        let mut banks: Vec<String> = db.pads.iter().filter_map(|p| p.bank.clone()).collect();
+        banks.sort();
+        banks.dedup();
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.import_pinout",
+            "project_root": project_root.display().to_string(),
+            "csv_file": csv_abs.display().to_string(),
+            "device": db.device,
+            "pinout_file": pinout_file.display().to_string(),
+            "packages": db.packages,
+            "pads": db.pads.len(),
+            "io_pads": io_pads,
+            "differential_pairs": pairs,
+            "banks": banks,
+            "notes": notes,
+        });
+
+        let log_text = format!(
+            "csv: {}\ndevice: {}\npackages: {}\npads: {} (I/O {})\ndifferential pairs: {}\nbanks: {}\n\nnotes:\n{}\n",
+            csv_abs.display(),
+            db.device,
+            db.packages.join(", "),
+            db.pads.len(),
+            io_pads,
+            pairs,
+            banks.join(", "),
+            notes.join("\n"),
+        );
+
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.import_pinout", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ImportPinoutResponse {
+            project_root: project_root.display().to_string(),
+            device: db.device,
+            pinout_file: pinout_file.display().to_string(),
+            packages: db.packages,
+            pads: db.pads.len(),
+            io_pads,
+            differential_pairs: pairs,
+            banks,
+            notes,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.list_devices",
+        description = "対応する Gowin デバイス（programmer_cli の device 名）の目録。系列、LUT 数、BSRAM、PLL、IDCODE と、選べる JTAG 周波数を返す"
+    )]
+    async fn list_devices(
+        &self,
+        params: Parameters<ListDevicesRequest>,
+    ) -> Result<Json<ListDevicesResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let family = req
+            .family
+            .as_deref()
+            .map(str::trim)
+            .filter(|f| !f.is_empty());
+        let devices: Vec<device::PartInfo> = device::PARTS
+            .iter()
+            .filter(|p| family.is_none_or(|f| p.family.eq_ignore_ascii_case(f)))
+            .map(device::part_info)
+            .collect();
+        if devices.is_empty()
+            && let Some(f) = family
+        {
+            let mut families: Vec<&str> = device::PARTS.iter().map(|p| p.family).collect();
+            families.sort();
+            families.dedup();
+            let hint = match device::suggest(f, families.iter().copied()).as_slice() {
+                [] => String::new(),
+                s => format!("もしかして: {}。", s.join(" / ")),
+            };
+            return Err(McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    "family {f} のデバイスはありません。{hint}（{}）",
+                    families.join(" / ")
+                ),
+                None,
+            ));
+        }
+        let extra_devices = config::load(&project_root)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?
+            .extra_devices
+            .unwrap_or_default();
+        let frequencies: Vec<String> = device::FREQUENCIES.iter().map(|f| f.to_string()).collect();
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.list_devices",
+            "project_root": project_root.display().to_string(),
+            "family": family,
+            "devices": devices.len(),
+            "extra_devices": extra_devices,
+        });
+        let log_text = format!(
+            "family: {:?}\n\n{}\n\nextra_devices: {:?}\nfrequencies: {}\n",
+            family,
+            devices
+                .iter()
+                .map(|d| format!(
+                    "{} ({}) LUT {} BSRAM {}Kbit PLL {}",
+                    d.name, d.family, d.luts, d.bsram_kbits, d.plls
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            extra_devices,
+            frequencies.join(", "),
+        );
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.list_devices", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ListDevicesResponse {
+            devices,
+            extra_devices,
+            frequencies,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.list_boards",
+        description = "ボードのプロファイル（組み込み + .gowin-mcp/config.toml の [boards.<名前>]）を一覧。デバイス、品番、パッケージ、ケーブル、周波数、フラッシュ、既定のピン"
+    )]
+    async fn list_boards(
+        &self,
+        params: Parameters<ListBoardsRequest>,
+    ) -> Result<Json<ListBoardsResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+
+        let boards = board::all(&project_root)
+            .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.list_boards",
+            "project_root": project_root.display().to_string(),
+            "boards": boards.iter().map(|b| &b.name).collect::<Vec<_>>(),
+        });
+        let log_text = format!(
+            "{}\n",
+            boards
+                .iter()
+                .map(|b| format!(
+                    "{} ({}): {} {:?} cable={:?} frequency={:?} flash={:?} pins={}",
+                    b.name,
+                    b.origin,
+                    b.device,
+                    b.part_number,
+                    b.cable,
+                    b.frequency,
+                    b.flash,
+                    b.pins.len()
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.list_boards", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ListBoardsResponse {
+            boards,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.constraint_coverage",
+        description = "合成後のネットリスト（.vg）と .cst / .sdc を突き合わせ、IO_LOC のないポート、create_clock のないクロック、何にも当たらない制約を報告"
+    )]
+    async fn constraint_coverage(
+        &self,
+        params: Parameters<ConstraintCoverageRequest>,
+    ) -> Result<Json<ConstraintCoverageResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+
+        let netlist_abs = match req.netlist_path.as_deref() {
+            Some(p) => resolve_under(&project_root, p),
+            None => report::find_latest_netlist(&project_root).ok_or_else(|| {
+                invalid(
+                    "impl/gwsynthesis に合成後のネットリスト（.vg）が見つかりません。先に合成するか netlist_path を指定してください".into(),
+                )
+            })?,
+        };
+        let text = tokio::fs::read_to_string(&netlist_abs)
+            .await
+            .map_err(|e| invalid(format!("{}: {e}", netlist_abs.display())))?;
+        let netlist = coverage::parse_netlist(&text, req.top_module.as_deref())
+            .map_err(|e| invalid(format!("{}: {e}", netlist_abs.display())))?;
+
+        // 制約ファイルは指定があれば必須、なければ見つかったものだけ使う
+        let mut notes = Vec::new();
+        let mut read_constraint = async |explicit: Option<&str>, extension: &str| {
+            let path = match explicit {
+                Some(p) => resolve_under(&project_root, p),
+                None => match gprj::find_constraint(&project_root, extension) {
+                    Some(p) => p,
+                    None => {
+                        notes.push(format!(
+                            ".{extension} が見つからないため、すべて未制約として扱いました"
+                        ));
+                        return Ok(None);
+                    }
+                },
+            };
+            tokio::fs::read_to_string(&path)
+                .await
+                .map(|t| Some((path.clone(), t)))
+                .map_err(|e| invalid(format!("{}: {e}", path.display())))
+        };
+        let cst_src = read_constraint(req.cst_path.as_deref(), "cst").await?;
+        let sdc_src = read_constraint(req.sdc_path.as_deref(), "sdc").await?;
+        let cst_file = cst_src.as_ref().map(|(_, t)| cst::parse(t));
+        let sdc_file = sdc_src.as_ref().map(|(_, t)| sdc::parse(t));
+
+        let result = coverage::check(&netlist, cst_file.as_ref(), sdc_file.as_ref());
+        let covered = result.unconstrained_ports.is_empty()
+            && result.unconstrained_clocks.is_empty()
+            && result.unmatched_constraints.is_empty();
+        let cst_path = cst_src.map(|(p, _)| p.display().to_string());
+        let sdc_path = sdc_src.map(|(p, _)| p.display().to_string());
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.constraint_coverage",
+            "project_root": project_root.display().to_string(),
+            "netlist_file": netlist_abs.display().to_string(),
+            "top_module": &netlist.module,
+            "cst_file": &cst_path,
+            "sdc_file": &sdc_path,
+            "ports": result.port_bits.len(),
+            "unconstrained_ports": &result.unconstrained_ports,
+            "unconstrained_clocks": &result.unconstrained_clocks,
+            "unmatched_constraints": result.unmatched_constraints.len(),
+            "covered": covered,
+        });
+        let log_text = format!(
+            "netlist: {}\ntop: {}\ncst: {:?}\nsdc: {:?}\nports: {}\n\nunconstrained ports:\n{}\n\nclocks:\n{}\n\nunmatched constraints:\n{}\n",
+            netlist_abs.display(),
+            netlist.module,
+            cst_path,
+            sdc_path,
+            result.port_bits.len(),
+            result
+                .unconstrained_ports
+                .iter()
+                .map(|p| format!("  {} ({})", p.port, p.direction))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            result
+                .clocks
+                .iter()
+                .map(|c| format!(
+                    "  {} [{}] sinks={} nets={:?} constrained_by={:?}",
+                    c.root, c.kind, c.sinks, c.nets, c.constrained_by
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            result
+                .unmatched_constraints
+                .iter()
+                .map(|u| format!(
+                    "  {}:{} {} {}: {}",
+                    u.file, u.line, u.kind, u.reference, u.text
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let (log_file, meta_file) = write_run_logs(
+            &project_root,
+            "gowin.constraint_coverage",
+            &meta_json,
+            &log_text,
+        )
+        .await
+        .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(ConstraintCoverageResponse {
+            project_root: project_root.display().to_string(),
+            netlist_file: netlist_abs.display().to_string(),
+            top_module: netlist.module,
+            cst_file: cst_path,
+            sdc_file: sdc_path,
+            ports: result.port_bits.len(),
+            unconstrained_ports: result.unconstrained_ports,
+            clocks: result.clocks,
+            unconstrained_clocks: result.unconstrained_clocks,
+            unmatched_constraints: result.unmatched_constraints,
+            covered,
+            notes,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.pll_solve",
+        description = "入力周波数と欲しい出力周波数から rPLL（GW1N / GW2A）/ PLLA（GW5A）の分周設定（IDIV / FBDIV / ODIV / MDIV）を列挙し、誤差順に Verilog のインスタンス化と create_generated_clock を返す"
+    )]
+    async fn pll_solve(
+        &self,
+        params: Parameters<PllSolveRequest>,
+    ) -> Result<Json<PllSolveResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+
+        // device（デバイス名か系列）→ board → .gprj の順
+        let device_name = match (req.device.clone(), req.board.as_deref()) {
+            (Some(d), _) => d,
+            (None, Some(b)) => {
+                board::find(&project_root, b)
+                    .map_err(|e| invalid(e.to_string()))?
+                    .device
+            }
+            (None, None) => {
+                let gprj_device = match gprj::find(&project_root) {
+                    Some(p) => tokio::fs::read_to_string(&p)
+                        .await
+                        .ok()
+                        .and_then(|t| gprj::device(&t))
+                        .map(|(name, _)| name),
+                    None => None,
+                };
+                gprj_device.ok_or_else(|| {
+                    invalid("device（GW1NR-9C などのデバイス名か GW5A などの系列）を指定してください（.gprj からも判別できませんでした）".into())
+                })?
+            }
+        };
+        let mut notes = Vec::new();
+        let family = match device::find_part(&device_name) {
+            Some(part) => part.family.to_string(),
+            None if pll::primitive(&device_name).is_some() => {
+                notes.push(format!(
+                    "系列 {device_name} で計算しました。rPLL の DEVICE パラメータは実際のデバイス名に置き換えてください"
+                ));
+                device_name.to_ascii_uppercase()
+            }
+            None => {
+                let hint = match device::suggest(&device_name, device::PARTS.iter().map(|p| p.name))
+                    .as_slice()
+                {
+                    [] => String::new(),
+                    s => format!("もしかして: {}。", s.join(" / ")),
+                };
+                return Err(invalid(format!(
+                    "device {device_name} は目録にありません。{hint}一覧は gowin.list_devices"
+                )));
+            }
+        };
+        let (primitive, mut limits) = pll::primitive(&family)
+            .ok_or_else(|| invalid(format!("{family} の PLL には対応していません")))?;
+        for (value, field) in [
+            (req.pfd_min_mhz, &mut limits.pfd_min_mhz),
+            (req.pfd_max_mhz, &mut limits.pfd_max_mhz),
+            (req.vco_min_mhz, &mut limits.vco_min_mhz),
+            (req.vco_max_mhz, &mut limits.vco_max_mhz),
+        ] {
+            if let Some(v) = value {
+                *field = v;
+            }
+        }
+
+        let input_mhz = req
+            .input_mhz
+            .ok_or_else(|| invalid("input_mhz（入力クロックの周波数）を指定してください".into()))?;
+        let outputs_mhz = req.outputs_mhz.clone().unwrap_or_default();
+        let instance = req.instance.as_deref().unwrap_or("pll_inst");
+        let input_clock = req.input_clock.as_deref().unwrap_or("clk");
+        let solutions = pll::solve(
+            primitive,
+            &limits,
+            input_mhz,
+            &outputs_mhz,
+            req.max_solutions.unwrap_or(5),
+            &pll::Naming {
+                device: &device_name,
+                instance,
+                input_clock,
+            },
+        )
+        .map_err(|e| invalid(e.to_string()))?;
+        notes.push(format!(
+            "周波数の範囲（limits）はデータシートの代表的なスピードグレードの値です。ほかのグレードでは vco_min_mhz などで上書きし、最終的には IP Core Generator の結果と照合してください。create_generated_clock は入力クロック {input_clock} の create_clock を前提にしています"
+        ));
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.pll_solve",
+            "project_root": project_root.display().to_string(),
+            "device": &device_name,
+            "family": &family,
+            "primitive": primitive,
+            "input_mhz": input_mhz,
+            "outputs_mhz": &outputs_mhz,
+            "limits": &limits,
+            "solutions": solutions.iter().map(|s| serde_json::json!({
+                "idiv_sel": s.idiv_sel,
+                "fbdiv_sel": s.fbdiv_sel,
+                "odiv_sel": s.odiv_sel,
+                "mdiv_sel": s.mdiv_sel,
+                "max_error_ppm": s.max_error_ppm,
+            })).collect::<Vec<_>>(),
+        });
+        let log_text = format!(
+            "{primitive} ({device_name}) input {input_mhz} MHz → {outputs_mhz:?}\n\n{}\n",
+            solutions
+                .iter()
+                .map(|s| format!(
+                    "#{} IDIV_SEL={} FBDIV_SEL={} ODIV_SEL={:?} MDIV_SEL={:?} PFD={} VCO={} max_error={} ppm\n{}",
+                    s.rank,
+                    s.idiv_sel,
+                    s.fbdiv_sel,
+                    s.odiv_sel,
+                    s.mdiv_sel,
+                    s.pfd_mhz,
+                    s.vco_mhz,
+                    s.max_error_ppm,
+                    s.sdc
+                ))
+                .collect::<Vec<_>>()
+                .join("\n"),
+        );
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.pll_solve", &meta_json, &log_text)
+                .await
+                .map_err(|e| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None))?;
+
+        Ok(Json(PllSolveResponse {
+            device: device_name,
+            family,
+            primitive: primitive.to_string(),
+            input_mhz,
+            limits,
+            solutions,
+            notes,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+
+    #[tool(
+        name = "gowin.ip_generate",
+        description = "IP Core Generator（GowinModGen）をバッチ実行して IP（PLL / FIFO / BSRAM など）を生成。.gprj のデバイスと parameters から .mod を作り、生成した HDL を .gprj に登録する（dry_run で .mod の確認のみ）"
+    )]
+    async fn ip_generate(
+        &self,
+        params: Parameters<IpGenerateRequest>,
+    ) -> Result<Json<IpGenerateResponse>, McpError> {
+        let req = params.0;
+        let project_root = resolve_project_root(req.project_root.as_deref()).await;
+        let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+        let internal =
+            |e: anyhow::Error| McpError::new(ErrorCode::INTERNAL_ERROR, e.to_string(), None);
+
+        let ip_type = req
+            .ip_type
+            .as_deref()
+            .map(str::trim)
+            .filter(|t| !t.is_empty())
+            .ok_or_else(|| {
+                invalid("ip_type（PLL / FIFO_HS / SDPB など）を指定してください".into())
+            })?;
+        let module_name = req
+            .module_name
+            .clone()
+            .unwrap_or_else(|| format!("Gowin_{ip_type}"));
+        let file_name = req
+            .file_name
+            .clone()
+            .unwrap_or_else(|| module_name.to_ascii_lowercase());
+        for (what, name) in [("module_name", &module_name), ("file_name", &file_name)] {
+            if !ipgen::is_identifier(name) {
+                return Err(invalid(format!(
+                    "{what} {name} は使えません（英字か _ で始まる英数字と _ だけ）"
+                )));
+            }
+        }
+        let (file_type, extension) = match req.language.as_deref().unwrap_or("verilog") {
+            "verilog" => ("vlg", "v"),
+            "vhdl" => ("vhdl", "vhd"),
+            other => {
+                return Err(invalid(format!(
+                    "language は verilog / vhdl のいずれかを指定してください（指定: {other}）"
+                )));
+            }
+        };
+        let mut parameters = BTreeMap::new();
+        for (key, value) in req.parameters.clone().unwrap_or_default() {
+            let text = match value {
+                serde_json::Value::String(s) => s,
+                serde_json::Value::Bool(b) => b.to_string(),
+                serde_json::Value::Number(n) => n.to_string(),
+                other => {
+                    return Err(invalid(format!(
+                        "parameters の {key} は文字列・数値・真偽値で指定してください（指定: {other}）"
+                    )));
+                }
+            };
+            parameters.insert(key, text);
+        }
+
+        // デバイスは .gprj から（登録先でもある）
+        let gprj_path = gprj::find(&project_root).ok_or_else(|| {
+            invalid(format!(
+                "{} に .gprj がありません（IP の生成には .gprj のデバイスが必要です）",
+                project_root.display()
+            ))
+        })?;
+        let gprj_text = tokio::fs::read_to_string(&gprj_path)
+            .await
+            .map_err(|e| invalid(format!("{}: {e}", gprj_path.display())))?;
+        let (device_name, part_number) = gprj::device(&gprj_text).ok_or_else(|| {
+            invalid(format!(
+                "{} に <Device name=.. pn=..> がありません",
+                gprj_path.display()
+            ))
+        })?;
+
+        let output_dir = ipgen::normalize(&resolve_under(
+            &project_root,
+            req.output_dir
+                .as_deref()
+                .unwrap_or(&format!("src/{file_name}")),
+        ));
+        // 登録できない場所に生成してから失敗しないよう、GowinModGen の前に確かめる
+        let register = req.register.unwrap_or(true);
+        let gprj_dir = ipgen::normalize(gprj_path.parent().unwrap_or(&project_root));
+        if register && !output_dir.starts_with(&gprj_dir) {
+            return Err(invalid(format!(
+                "output_dir {} は .gprj のディレクトリ（{}）の外なので、生成した HDL を登録できません。.gprj の下を指定するか register: false にしてください",
+                output_dir.display(),
+                gprj_dir.display()
+            )));
+        }
+        let mod_content = ipgen::mod_text(&ipgen::ModSpec {
+            device: &device_name,
+            part_number: part_number.as_deref(),
+            module_name: &module_name,
+            file_name: &file_name,
+            output_dir: &output_dir,
+            ip_type,
+            file_type,
+            parameters: &parameters,
+        })
+        .map_err(|e| invalid(e.to_string()))?;
+        let mod_file = output_dir.join(format!("{file_name}.mod"));
+        let hdl_path = output_dir.join(format!("{file_name}.{extension}"));
+
+        let mut notes = Vec::new();
+        if !ipgen::KNOWN_TYPES.contains(&ip_type) {
+            notes.push(format!(
+                "ip_type {ip_type} はよく使う種類（{}）にありません。GowinModGen が受け付けるか確認してください",
+                ipgen::KNOWN_TYPES.join(" / ")
+            ));
+        }
+
+        let dry_run = req.dry_run.unwrap_or(false);
+        let gowin_ide_app_path = req
+            .gowin_ide_app_path
+            .as_deref()
+            .unwrap_or(DEFAULT_GOWIN_APP_PATH);
+        let (ide_base, _gw_sh, _programmer_cli) = gowin_paths(gowin_ide_app_path);
+        let modgen = ide_base.join("bin").join("GowinModGen");
+
+        let mut command = None;
+        let mut generated_files = Vec::new();
+        let mut registered = Vec::new();
+        // 今回の実行で書かれた HDL があるか（前回の生成物が残っているだけなら false）
+        let mut hdl_fresh = false;
+        if !dry_run {
+            if !modgen.is_file() {
+                return Err(invalid(format!(
+                    "GowinModGen が見つかりません: {}（gowin_ide_app_path を確認してください）",
+                    modgen.display()
+                )));
+            }
+            ensure_dir(&output_dir).await.map_err(internal)?;
+            tokio::fs::write(&mod_file, &mod_content)
+                .await
+                .map_err(|e| internal(e.into()))?;
+
+            // mtime の分解能が秒のファイルシステムもあるので秒に切り捨てて比べる
+            let started_at_ms = now_ms() / 1000 * 1000;
+            let args = vec!["-do".to_string(), mod_file.display().to_string()];
+            let exec = exec_with_timeout(
+                &modgen,
+                &args,
+                Some(&ide_base.join("bin")),
+                Some(&gw_sh_env(&ide_base)),
+                req.timeout_sec.unwrap_or(300),
+            )
+            .await
+            .map_err(internal)?;
+
+            let mut files = Vec::new();
+            stale::walk(&output_dir, &mut files);
+            generated_files = files
+                .iter()
+                .filter(|f| {
+                    **f != mod_file && stale::mtime_ms(f).is_some_and(|m| m >= started_at_ms)
+                })
+                .map(|f| f.display().to_string())
+                .collect();
+
+            hdl_fresh = stale::mtime_ms(&hdl_path).is_some_and(|m| m >= started_at_ms);
+            let succeeded = exec.exit_code == 0 && !exec.timed_out && hdl_fresh;
+            if exec.exit_code == 0 && !exec.timed_out && !hdl_fresh {
+                notes.push(format!(
+                    "GowinModGen は終了コード 0 でしたが {} が{}。stdout / stderr を確認してください",
+                    hdl_path.display(),
+                    if hdl_path.is_file() {
+                        "更新されていません（前回の生成物です）"
+                    } else {
+                        "できていません"
+                    }
+                ));
+            }
+            // 生成した HDL を .gprj に登録する（.ipc などは同じディレクトリに残る）
+            if succeeded && register {
+                let relative = hdl_path
+                    .strip_prefix(&gprj_dir)
+                    .map_err(|_| {
+                        invalid(format!(
+                            "{} は .gprj のディレクトリ（{}）の外なので登録できません",
+                            hdl_path.display(),
+                            gprj_dir.display()
+                        ))
+                    })?
+                    .to_string_lossy()
+                    .replace('\\', "/");
+                let (text, added) =
+                    gprj::add_files(&gprj_text, &[relative]).map_err(|e| invalid(e.to_string()))?;
+                if !added.is_empty() {
+                    tokio::fs::write(&gprj_path, text)
+                        .await
+                        .map_err(|e| internal(e.into()))?;
+                }
+                registered = added;
+            }
+            command = Some(CommandRun {
+                args,
+                exit_code: exec.exit_code,
+                timed_out: exec.timed_out,
+                duration_ms: exec.duration_ms,
+                stdout: exec.stdout,
+                stderr: exec.stderr,
+            });
+        }
+        let success = command
+            .as_ref()
+            .is_some_and(|c| c.exit_code == 0 && !c.timed_out)
+            && hdl_fresh;
+
+        let meta_json = serde_json::json!({
+            "tool": "gowin.ip_generate",
+            "project_root": project_root.display().to_string(),
+            "ip_type": ip_type,
+            "module_name": &module_name,
+            "device": &device_name,
+            "mod_file": mod_file.display().to_string(),
+            "dry_run": dry_run,
+            "exit_code": command.as_ref().map(|c| c.exit_code),
+            "success": success,
+            "generated_files": &generated_files,
+            "registered": &registered,
+        });
+        let log_text = format!(
+            "mod: {}\n{}\n{}\nexit_code: {:?}\n\n[stdout]\n{}\n\n[stderr]\n{}\n\ngenerated:\n{}\nregistered: {:?}\n",
+            mod_file.display(),
+            mod_content,
+            if dry_run { "(dry_run)" } else { "" },
+            command.as_ref().map(|c| c.exit_code),
+            command.as_ref().map_or("", |c| c.stdout.as_str()),
+            command.as_ref().map_or("", |c| c.stderr.as_str()),
+            generated_files.join("\n"),
+            registered,
+        );
+        let (log_file, meta_file) =
+            write_run_logs(&project_root, "gowin.ip_generate", &meta_json, &log_text)
+                .await
+                .map_err(internal)?;
+
+        Ok(Json(IpGenerateResponse {
+            project_root: project_root.display().to_string(),
+            ip_type: ip_type.to_string(),
+            module_name,
+            mod_file: mod_file.display().to_string(),
+            mod_content,
+            output_dir: output_dir.display().to_string(),
+            dry_run,
+            command,
+            success,
+            hdl_file: hdl_path.is_file().then(|| hdl_path.display().to_string()),
+            generated_files,
+            gprj_file: gprj_path.display().to_string(),
+            registered,
+            notes,
+            log_file: log_file.display().to_string(),
+            meta_file: meta_file.display().to_string(),
+        }))
+    }
+}
+
+#[tool_handler]
+impl ServerHandler for GowinMcp {
+    fn get_info(&self) -> ServerInfo {
+        ServerInfo {
+            instructions: Some(
+                "gw-synth-flash-mcp: gw_sh / programmer_cli をLLMから操作するためのMCPサーバー（個人利用向け）".into(),
+            ),
+            capabilities: ServerCapabilities::builder().enable_tools().build(),
+            ..Default::default()
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct RunTclRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    tcl_path: Option<String>,
+    tcl_inline: Option<String>,
+    timeout_sec: Option<u64>,
+    env: Option<HashMap<String, String>>,
+    expected_files: Option<Vec<String>>,
+    use_cache: Option<bool>,
+    // 指定すると .gprj の品番がボードと同じか確かめてからビルドする
+    board: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct RunTclResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    tcl_file_path: String,
+    exit_code: i32,
+    timed_out: bool,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+    expected_checks: Vec<ExpectedFileCheck>,
+    cache_hit: bool,
+    cache_key: Option<String>,
+    // 成功したビルドの成果物（キャッシュヒット時は元のビルドのもの）
+    artifact_id: Option<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct Attempt {
+    args: Vec<String>,
+    exit_code: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListCablesRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    timeout_sec: Option<u64>,
+    refresh_capabilities: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListCablesResponse {
+    project_root: String,
+    gowin_ide_app_path: String,
+    cables: Vec<cable::CableInfo>,
+    attempts: Vec<Attempt>,
+    detected_idcodes: Vec<String>,
+    capabilities: capability::ProgrammerCaps,
+    capabilities_cached: bool,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ProgramFsRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    fs_file_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    // 書き込みが失敗したときのやり直し回数（既定 0）
+    retries: Option<u32>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    chain_position: Option<u32>,
+    post_check: Option<bool>,
+    // warn（既定）/ block / off
+    stale_policy: Option<String>,
+    force: Option<bool>,
+    target: Option<String>,
+    spi_address: Option<String>,
+    verify: Option<bool>,
+    lock_wait_sec: Option<u64>,
+    // fs_file_path の代わりに、保存済みの成果物を指定
+    artifact_id: Option<String>,
+    // ボードのプロファイル（gowin.list_boards）
+    board: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct VariantTried {
+    label: String,
+    exit_code: i32,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ProgramFsResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    fs_file: String,
+    artifact_id: Option<String>,
+    target: String,
+    operation: String,
+    selected_cable: Option<String>,
+    selected_cable_info: Option<cable::CableInfo>,
+    chain_devices: Option<Vec<device::ChainDevice>>,
+    bitstream: Option<bitstream::FsInfo>,
+    staleness: Option<stale::StaleReport>,
+    validation_warnings: Vec<String>,
+    list_cables_attempts: Option<Vec<Attempt>>,
+    variants_tried: Vec<VariantTried>,
+    post_check: Option<status::DeviceStatus>,
+    post_check_error: Option<String>,
+    exit_code: i32,
+    timed_out: bool,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ConvertBitstreamRequest {
+    project_root: Option<String>,
+    fs_file_path: Option<String>,
+    output_path: Option<String>,
+    format: Option<String>,
+    offset: Option<String>,
+    pad_to: Option<String>,
+    // 書き込み先フラッシュの容量（既定 128MiB）。イメージがこれを超えるとエラー
+    flash_size: Option<String>,
+    fill: Option<u8>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ConvertBitstreamResponse {
+    project_root: String,
+    fs_file: String,
+    output_file: String,
+    format: String,
+    offset: u64,
+    image_size: u64,
+    image_crc32: String,
+    bitstream: bitstream::FsInfo,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildFlashImageRequest {
+    project_root: Option<String>,
+    layout_path: Option<String>,
+    output_path: Option<String>,
+    format: Option<String>,
+    program: Option<bool>,
+    gowin_ide_app_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    verify: Option<bool>,
+    board: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildFlashImageResponse {
+    project_root: String,
+    layout_file: String,
+    output_file: String,
+    format: String,
+    flash_size: u64,
+    image_size: u64,
+    image_crc32: String,
+    partitions: Vec<flash_layout::PartitionReport>,
+    program: Option<ProgramFsResponse>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ScanChainRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    lock_wait_sec: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ScanChainResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    selected_cable: Option<String>,
+    selected_cable_info: Option<cable::CableInfo>,
+    args: Vec<String>,
+    devices: Vec<device::ChainDevice>,
+    exit_code: i32,
+    timed_out: bool,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct DeviceStatusRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    chain_position: Option<u32>,
+    lock_wait_sec: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct DeviceStatusResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    selected_cable: Option<String>,
+    selected_cable_info: Option<cable::CableInfo>,
+    status: status::DeviceStatus,
+    configured: bool,
+    commands: Vec<CommandRun>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ReconfigureRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    chain_position: Option<u32>,
+    lock_wait_sec: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ReconfigureResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    selected_cable: Option<String>,
+    selected_cable_info: Option<cable::CableInfo>,
+    list_cables_attempts: Option<Vec<Attempt>>,
+    args: Vec<String>,
+    exit_code: i32,
+    timed_out: bool,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ReadFlashRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    device: Option<String>,
+    frequency: Option<String>,
+    timeout_sec: Option<u64>,
+    cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    chain_position: Option<u32>,
+    // embflash / exflash（既定: exflash）
+    target: Option<String>,
+    address: Option<String>,
+    length: Option<String>,
+    output_path: Option<String>,
+    // 読み出し結果と比較する .fs / .bin
+    compare_with: Option<String>,
+    lock_wait_sec: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ReadFlashResponse {
+    run_id: String,
+    project_root: String,
+    gowin_ide_app_path: String,
+    target: String,
+    address: u64,
+    length: u64,
+    output_file: String,
+    read_bytes: Option<u64>,
+    selected_cable: Option<String>,
+    selected_cable_info: Option<cable::CableInfo>,
+    compare_with: Option<String>,
+    comparison: Option<image::Comparison>,
+    args: Vec<String>,
+    exit_code: i32,
+    timed_out: bool,
+    duration_ms: u128,
+    stdout: String,
+    stderr: String,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildAndProgramRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    // ビルド（未指定なら <project_root>/run_gowin.tcl）
+    tcl_path: Option<String>,
+    tcl_inline: Option<String>,
+    build_timeout_sec: Option<u64>,
+    env: Option<HashMap<String, String>>,
+    // 入力が前回の成功ビルドと同じならビルドを省略（既定 true）
+    use_cache: Option<bool>,
+    // 未指定なら impl/pnr の最新 .fs
+    fs_file_path: Option<String>,
+    // ゲート
+    require_timing_met: Option<bool>,
+    max_utilization_percent: Option<f64>,
+    allow_warnings: Option<bool>,
+    // false ならゲート判定まで（書き込みしない）
+    program: Option<bool>,
+    // 書き込み（gowin.program_fs と同じ）
     device: Option<String>,
     frequency: Option<String>,
-    retries: Option<u32>,
-    timeout_sec: Option<u64>,
+    program_timeout_sec: Option<u64>,
     cable: Option<String>,
+    cable_serial: Option<String>,
+    cable_index: Option<u32>,
+    chain_position: Option<u32>,
+    target: Option<String>,
+    verify: Option<bool>,
+    post_check: Option<bool>,
+    lock_wait_sec: Option<u64>,
+    // ボードのプロファイル（.gprj の品番の確認と書き込みの既定値）
+    board: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct VariantTried {
-    label: String,
-    exit_code: i32,
+struct StageReport {
+    name: String,
+    // ok / cached / failed / skipped
+    status: String,
+    duration_ms: u128,
+    detail: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
-struct ProgramFsResponse {
+struct GateResult {
+    name: String,
+    passed: bool,
+    detail: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildAndProgramResponse {
     project_root: String,
     gowin_ide_app_path: String,
-    fs_file: String,
-    selected_cable: Option<String>,
-    list_cables_attempts: Option<Vec<Attempt>>,
-    variants_tried: Vec<VariantTried>,
+    stages: Vec<StageReport>,
+    gates: Vec<GateResult>,
+    gates_passed: bool,
+    programmed: bool,
+    cache_hit: bool,
+    build_exit_code: i32,
+    build_timed_out: bool,
+    build_log_file: String,
+    artifact_id: Option<String>,
+    errors: Vec<report::Diagnostic>,
+    warnings: Vec<report::Diagnostic>,
+    timing: Option<report::TimingSummary>,
+    utilization: Vec<report::ResourceUsage>,
+    fs_file: Option<String>,
+    program: Option<ProgramFsResponse>,
+    // 書き込み段階がエラーで終わったときのメッセージ
+    program_error: Option<String>,
+    total_duration_ms: u128,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildTrendsRequest {
+    project_root: Option<String>,
+    // 指標名の前方一致（例: "util_percent", "fmax_mhz:clk"）。省略時はすべて
+    metrics: Option<Vec<String>>,
+    // 直近何ビルド分か（既定 50）
+    limit: Option<usize>,
+    include_failed: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct BuildTrendsResponse {
+    project_root: String,
+    history_file: String,
+    total_builds: usize,
+    builds: usize,
+    // タイミングを満たさなくなったビルドの run_id
+    timing_broken: Vec<String>,
+    series: Vec<history::TrendSeries>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CompareRunsRequest {
+    project_root: Option<String>,
+    // 省略時は run_id より前の最後の正常なビルド（成功かつタイミング違反なし）
+    base_run_id: Option<String>,
+    // 省略時は最新のビルド
+    run_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CompareRunsResponse {
+    project_root: String,
+    base_run_id: String,
+    run_id: String,
+    utilization: Vec<compare::ResourceDelta>,
+    clocks: Vec<compare::ClockDelta>,
+    // 件数が変わった警告コード
+    warnings: Vec<compare::WarningDelta>,
+    new_warning_codes: Vec<String>,
+    removed_warning_codes: Vec<String>,
+    // 成果物がどちらかにない場合は None（理由は notes）
+    tcl: Option<compare::TextDiff>,
+    options: Option<Vec<compare::OptionChange>>,
+    notes: Vec<String>,
+    base: history::HistoryEntry,
+    target: history::HistoryEntry,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct SdcListRequest {
+    project_root: Option<String>,
+    // 省略時は .gprj の .sdc、なければ src/*.sdc
+    sdc_path: Option<String>,
+    top_module: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct SdcListResponse {
+    project_root: String,
+    sdc_file: String,
+    top_module: Option<String>,
+    // トップを特定できなければ理由（ポート参照の検証は省略）
+    top_error: Option<String>,
+    clocks: Vec<sdc::Clock>,
+    io_delays: Vec<sdc::IoDelay>,
+    exceptions: Vec<sdc::Exception>,
+    commands: Vec<sdc::Command>,
+    issues: Vec<sdc::Issue>,
+    valid: bool,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct SdcEditRequest {
+    project_root: Option<String>,
+    sdc_path: Option<String>,
+    top_module: Option<String>,
+    // add（既定）/ modify / remove
+    action: Option<String>,
+    // add / modify する SDC コマンド 1 つ
+    command: Option<String>,
+    // modify / remove の対象（gowin.sdc_list の commands[].index）
+    index: Option<usize>,
+    // index の代わりにクロック名で対象を指定
+    clock: Option<String>,
+    dry_run: Option<bool>,
+    force: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct SdcEditResponse {
+    project_root: String,
+    sdc_file: String,
+    action: String,
+    before: Option<String>,
+    after: Option<String>,
+    written: bool,
+    dry_run: bool,
+    clocks: Vec<sdc::Clock>,
+    issues: Vec<sdc::Issue>,
+    // dry_run のときの変更後の内容
+    content: Option<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CstListRequest {
+    project_root: Option<String>,
+    // 省略時は .gprj の .cst、なければ src/*.cst
+    cst_path: Option<String>,
+    top_module: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CstListResponse {
+    project_root: String,
+    cst_file: String,
+    top_module: Option<String>,
+    // トップを特定できなければ理由（ポートの検証は省略）
+    top_error: Option<String>,
+    // gowin.import_pinout で取り込んだピン配置データベース（なければピンの存在などは見ない）
+    pinout_file: Option<String>,
+    package: Option<String>,
+    // データベースを使えなかった理由と取り込み方
+    pinout_note: Option<String>,
+    pins: Vec<cst::PinAssignment>,
+    statements: Vec<cst::Statement>,
+    issues: Vec<sdc::Issue>,
+    valid: bool,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CstEditRequest {
+    project_root: Option<String>,
+    cst_path: Option<String>,
+    top_module: Option<String>,
+    // add（既定）/ modify / remove
+    action: Option<String>,
+    // "led[0]" のようにビットごと
+    port: Option<String>,
+    // "10"、差動ペアは "32,33"
+    pin: Option<String>,
+    io_type: Option<String>,
+    pull_mode: Option<String>,
+    drive: Option<String>,
+    // その他の IO_PORT 属性（BANK_VCCIO など）。modify で値を "" にすると削除
+    attributes: Option<BTreeMap<String, String>>,
+    // 未指定の pin / io_type / pull_mode をボードの既定のピンから埋める
+    board: Option<String>,
+    dry_run: Option<bool>,
+    force: Option<bool>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CstEditResponse {
+    project_root: String,
+    cst_file: String,
+    action: String,
+    port: String,
+    before: Option<cst::PinAssignment>,
+    after: Option<cst::PinAssignment>,
+    written: bool,
+    dry_run: bool,
+    issues: Vec<sdc::Issue>,
+    // ピン配置データベースを使えなかった理由と取り込み方
+    pinout_note: Option<String>,
+    // dry_run のときの変更後の内容
+    content: Option<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ImportPinoutRequest {
+    project_root: Option<String>,
+    // ピン配置表の CSV（"Pin Name" の見出し行と、パッケージごとのピン番号の列）
+    csv_path: Option<String>,
+    // GW1NR-9 / 品番など。省略時は .gprj のデバイス
+    device: Option<String>,
+    // 取り込むパッケージ列（省略時はすべて）
+    package: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ImportPinoutResponse {
+    project_root: String,
+    device: String,
+    pinout_file: String,
+    packages: Vec<String>,
+    pads: usize,
+    io_pads: usize,
+    differential_pairs: usize,
+    banks: Vec<String>,
+    notes: Vec<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListDevicesRequest {
+    project_root: Option<String>,
+    // GW1N / GW1NR / GW2A / GW5A / GW5AST など
+    family: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListDevicesResponse {
+    devices: Vec<device::PartInfo>,
+    // .gowin-mcp/config.toml の extra_devices
+    extra_devices: Vec<String>,
+    frequencies: Vec<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListBoardsRequest {
+    project_root: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ListBoardsResponse {
+    boards: Vec<board::Board>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ConstraintCoverageRequest {
+    project_root: Option<String>,
+    // 未指定なら impl/gwsynthesis の最新 .vg
+    netlist_path: Option<String>,
+    cst_path: Option<String>,
+    sdc_path: Option<String>,
+    top_module: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct ConstraintCoverageResponse {
+    project_root: String,
+    netlist_file: String,
+    top_module: String,
+    cst_file: Option<String>,
+    sdc_file: Option<String>,
+    // ネットリストのトップのポート（ビット単位）の数
+    ports: usize,
+    unconstrained_ports: Vec<coverage::PortBit>,
+    clocks: Vec<coverage::ClockNet>,
+    unconstrained_clocks: Vec<String>,
+    unmatched_constraints: Vec<coverage::Unmatched>,
+    covered: bool,
+    notes: Vec<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct PllSolveRequest {
+    project_root: Option<String>,
+    // デバイス名（GW1NR-9C）か系列（GW5A）。未指定なら board → .gprj
+    device: Option<String>,
+    board: Option<String>,
+    input_mhz: Option<f64>,
+    // 1 つ目が rPLL の CLKOUT / PLLA の CLKOUT0
+    outputs_mhz: Option<Vec<f64>>,
+    max_solutions: Option<usize>,
+    // 生成する Verilog のインスタンス名（既定 pll_inst）
+    instance: Option<String>,
+    // 入力クロックのポート名 / SDC のクロック名（既定 clk）
+    input_clock: Option<String>,
+    pfd_min_mhz: Option<f64>,
+    pfd_max_mhz: Option<f64>,
+    vco_min_mhz: Option<f64>,
+    vco_max_mhz: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct PllSolveResponse {
+    device: String,
+    family: String,
+    // rPLL / PLLA
+    primitive: String,
+    input_mhz: f64,
+    limits: pll::Limits,
+    solutions: Vec<pll::Solution>,
+    notes: Vec<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct IpGenerateRequest {
+    project_root: Option<String>,
+    gowin_ide_app_path: Option<String>,
+    // GowinModGen の -type（PLL / FIFO_HS / SDPB など）
+    ip_type: Option<String>,
+    // 既定 Gowin_<ip_type>
+    module_name: Option<String>,
+    // 既定 module_name の小文字
+    file_name: Option<String>,
+    // 既定 src/<file_name>
+    output_dir: Option<String>,
+    // verilog / vhdl
+    language: Option<String>,
+    // .mod の -<名前> <値>（例: {"idiv_sel": 2, "fbdiv_sel": 24}）
+    parameters: Option<BTreeMap<String, serde_json::Value>>,
+    // 生成した HDL を .gprj に登録する（既定 true）
+    register: Option<bool>,
+    dry_run: Option<bool>,
+    timeout_sec: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct IpGenerateResponse {
+    project_root: String,
+    ip_type: String,
+    module_name: String,
+    mod_file: String,
+    mod_content: String,
+    output_dir: String,
+    dry_run: bool,
+    command: Option<CommandRun>,
+    success: bool,
+    hdl_file: Option<String>,
+    generated_files: Vec<String>,
+    gprj_file: String,
+    // 今回 .gprj に加えたパス（登録済みなら空）
+    registered: Vec<String>,
+    notes: Vec<String>,
+    log_file: String,
+    meta_file: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+struct CommandRun {
+    args: Vec<String>,
     exit_code: i32,
     timed_out: bool,
     duration_ms: u128,
     stdout: String,
     stderr: String,
-    log_file: String,
-    meta_file: String,
 }
 
-fn parse_cable_names(text: &str) -> Vec<String> {
-    let mut found: Vec<String> = Vec::new();
-    let mut seen = std::collections::HashSet::new();
-
-    for line in text.lines() {
-        let l = line.trim();
-        if l.is_empty() {
-            continue;
-        }
-        let stripped = l
-            .trim_start_matches(|c: char| {
-                c.is_ascii_digit()
-                    || c == '['
-                    || c == ']'
-                    || c == '.'
-                    || c == ')'
-                    || c == '-'
-                    || c.is_whitespace()
-            })
-            .trim();
-
-        // 引用符つき
-        for cap in stripped.match_indices('"') {
-            let _ = cap;
-        }
-        // "..." をざっくり抽出
-        let mut rest = stripped;
-        while let Some(start) = rest.find('"') {
-            let after = &rest[start + 1..];
-            if let Some(end) = after.find('"') {
-                let v = after[..end].trim();
-                let low = v.to_lowercase();
-                if (low.contains("cable") || low.contains("gowin")) && seen.insert(v.to_string()) {
-                    found.push(v.to_string());
-                }
-                rest = &after[end + 1..];
-            } else {
-                break;
-            }
-        }
+// ケーブルの指定とロックの条件
+struct CableRequest<'a> {
+    tool: &'a str,
+    cable: Option<String>,
+    cable_serial: Option<&'a str>,
+    cable_index: Option<u32>,
+    // ロックの有効期限
+    hold_sec: u64,
+    lock_wait_sec: Option<u64>,
+}
 
-        // 典型名
-        if stripped.to_lowercase().contains("gowin usb cable") && seen.insert(stripped.to_string())
-        {
-            found.push(stripped.to_string());
+struct CableSelection {
+    cable: Option<String>,
+    info: Option<cable::CableInfo>,
+    chip_idcode: Option<String>,
+    list_cables_attempts: Option<Vec<Attempt>>,
+    explicit: bool,
+}
+
+// 制約ファイルのパス（未指定なら .gprj / src から探す）
+fn resolve_constraint(
+    project_root: &Path,
+    explicit: Option<&str>,
+    extension: &str,
+) -> Result<PathBuf, McpError> {
+    match explicit {
+        Some(p) => Ok(resolve_under(project_root, p)),
+        None => gprj::find_constraint(project_root, extension).ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                format!(
+                    ".{extension} ファイルが見つかりません（.gprj のファイル一覧と src/ を確認しました）。{extension}_path を指定してください"
+                ),
+                None,
+            )
+        }),
+    }
+}
+
+// ボードの品番と .gprj の品番が違えばエラー（どちらかに品番がなければ照合しない）
+async fn check_board_part(project_root: &Path, board_name: &str) -> Result<(), McpError> {
+    let b = board::find(project_root, board_name)
+        .map_err(|e| McpError::new(ErrorCode::INVALID_PARAMS, e.to_string(), None))?;
+    if let Some(want) = b.part_number.as_deref()
+        && let Some(gprj_path) = gprj::find(project_root)
+        && let Some((_, Some(pn))) = tokio::fs::read_to_string(&gprj_path)
+            .await
+            .ok()
+            .and_then(|t| gprj::device(&t))
+        && !pn.eq_ignore_ascii_case(want)
+    {
+        return Err(McpError::new(
+            ErrorCode::INVALID_PARAMS,
+            format!(
+                "board {} の品番は {want} ですが、{} は {pn} です",
+                b.name,
+                gprj_path.display()
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+// device / frequency を目録と照合する（"did you mean" つき）。
+// 問題なければ programmer_cli に渡す表記の frequency（"15 MHz" → "15MHz"）を返す
+fn check_device_params(
+    project_root: &Path,
+    device: &str,
+    frequency: &str,
+) -> Result<String, McpError> {
+    let invalid = |msg: String| McpError::new(ErrorCode::INVALID_PARAMS, msg, None);
+    let extra = config::load(project_root)
+        .map_err(|e| invalid(e.to_string()))?
+        .extra_devices
+        .unwrap_or_default();
+    if device::find_part(device).is_none() && !extra.iter().any(|d| d.eq_ignore_ascii_case(device))
+    {
+        let names = device::PARTS
+            .iter()
+            .map(|p| p.name)
+            .chain(extra.iter().map(String::as_str));
+        let hint = match device::suggest(device, names).as_slice() {
+            [] => String::new(),
+            s => format!("もしかして: {}。", s.join(" / ")),
+        };
+        return Err(invalid(format!(
+            "device {device} は目録にありません。{hint}一覧は gowin.list_devices、目録にない名前を使うには .gowin-mcp/config.toml の extra_devices に追加してください"
+        )));
+    }
+    let allowed = device::frequencies_for(device);
+    match device::normalize_frequency(frequency) {
+        Some(f) if allowed.contains(&f) => Ok(f.to_string()),
+        Some(f) => Err(invalid(format!(
+            "frequency {f} は {device} では選べません（{}）",
+            allowed.join(" / ")
+        ))),
+        None => {
+            let hint = match device::suggest(frequency, allowed.iter().copied()).as_slice() {
+                [] => String::new(),
+                s => format!("もしかして: {}。", s.join(" / ")),
+            };
+            Err(invalid(format!(
+                "frequency {frequency} は選べません。{hint}（{}）",
+                allowed.join(" / ")
+            )))
         }
+    }
+}
 
-        // Cable: xxx
-        if let Some(idx) = stripped.to_lowercase().find("cable") {
-            let tail = &stripped[idx..];
-            if let Some(pos) = tail.find(':').or_else(|| tail.find('=')) {
-                let v = tail[pos + 1..].trim().trim_matches('"');
-                if !v.is_empty() && seen.insert(v.to_string()) {
-                    found.push(v.to_string());
-                }
-            }
+// シリアル/インデックス指定のときのケーブル番号フラグ。--cable だけでは同じ名前の
+// 別のケーブルに書きかねないので、対応していなければエラー
+fn cable_index_flag(
+    caps: &capability::ProgrammerCaps,
+    selection: &CableSelection,
+) -> Result<Option<&'static str>, McpError> {
+    if !selection.explicit || selection.info.is_none() {
+        return Ok(None);
+    }
+    caps.pick_flag(&["--cable-index", "--cableIndex"])
+        .map(Some)
+        .ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "この programmer_cli はケーブルの番号指定に対応していません（--help に --cable-index なし）。cable_serial / cable_index を外し、対象のケーブルだけをつないで実行してください",
+                None,
+            )
+        })
+}
+
+fn cable_args(
+    caps: &capability::ProgrammerCaps,
+    selection: &CableSelection,
+) -> Result<Vec<String>, McpError> {
+    let mut argv = Vec::new();
+    if let Some(cable) = selection.cable.as_deref() {
+        argv.push("--cable".into());
+        argv.push(cable.to_string());
+    }
+    if let Some(flag) = cable_index_flag(caps, selection)?
+        && let Some(info) = selection.info.as_ref()
+    {
+        argv.push(flag.into());
+        argv.push(info.index.to_string());
+    }
+    Ok(argv)
+}
+
+async fn ensure_programmer_cli(
+    programmer_cli: &Path,
+    gowin_ide_app_path: &str,
+) -> Result<(), McpError> {
+    if tokio::fs::metadata(programmer_cli).await.is_err() {
+        return Err(McpError::new(
+            ErrorCode::INVALID_PARAMS,
+            format!(
+                "programmer_cli が見つかりません: {}。gowin_ide_app_path を確認してください（現在: {}）",
+                programmer_cli.display(),
+                gowin_ide_app_path
+            ),
+            None,
+        ));
+    }
+    Ok(())
+}
+
+// --help にスキャン用フラグがあればそれを、なければ "Read Device Codes" 操作を使う
+async fn scan_chain_devices(
+    programmer_cli: &Path,
+    caps: &capability::ProgrammerCaps,
+    selection: &CableSelection,
+    device: &str,
+    frequency: &str,
+    timeout_sec: u64,
+) -> Result<(Vec<String>, ExecMeta, Vec<device::ChainDevice>)> {
+    let scan_flag = if caps.known() {
+        caps.pick_flag(&["--scan-device", "--scan"])
+    } else {
+        None
+    };
+    let argv = match scan_flag {
+        Some(flag) => {
+            let mut argv = vec![flag.to_string()];
+            argv.extend(cable_args(caps, selection)?);
+            argv.extend(["--frequency".into(), frequency.to_string()]);
+            argv
         }
+        None => operation_argv(
+            caps,
+            programmer::Operation::ReadDeviceCodes,
+            selection,
+            device,
+            frequency,
+            &[],
+        )?,
+    };
+
+    let exec = exec_with_timeout(programmer_cli, &argv, None, None, timeout_sec).await?;
+    let devices = device::parse_chain(&format!("{}\n{}", exec.stdout, exec.stderr));
+    Ok((argv, exec, devices))
+}
+
+// --run <操作番号> --device <device> [ケーブル] [extra] --frequency <freq>
+fn operation_argv(
+    caps: &capability::ProgrammerCaps,
+    operation: programmer::Operation,
+    selection: &CableSelection,
+    device: &str,
+    frequency: &str,
+    extra: &[String],
+) -> Result<Vec<String>> {
+    let index = caps
+        .operation_index(operation)
+        .ok_or_else(|| missing_operation(operation))?;
+    let mut argv = vec![
+        caps.run_flag().to_string(),
+        index.to_string(),
+        "--device".into(),
+        device.to_string(),
+    ];
+    argv.extend(cable_args(caps, selection)?);
+    argv.extend(extra.iter().cloned());
+    argv.extend(["--frequency".into(), frequency.to_string()]);
+    Ok(argv)
+}
+
+fn missing_operation(operation: programmer::Operation) -> anyhow::Error {
+    anyhow!(
+        "programmer_cli --help に「{}」操作が見つかりません（この programmer_cli では使えない可能性があります）",
+        operation.label()
+    )
+}
+
+fn chain_flag(caps: &capability::ProgrammerCaps) -> Result<&'static str, McpError> {
+    caps.pick_flag(&["--device-index", "--deviceIndex", "--chain-index"])
+        .ok_or_else(|| {
+            McpError::new(
+                ErrorCode::INVALID_PARAMS,
+                "この programmer_cli はチェーン上の位置指定に対応していません（--help に該当フラグなし）",
+                None,
+            )
+        })
+}
+
+// ステータスレジスタを読み、USERCODE 操作があればそれも読む
+async fn read_device_status(
+    programmer_cli: &Path,
+    caps: &capability::ProgrammerCaps,
+    selection: &CableSelection,
+    device: &str,
+    frequency: &str,
+    chain_args: &[String],
+    timeout_sec: u64,
+) -> Result<(Vec<(Vec<String>, ExecMeta)>, status::DeviceStatus)> {
+    let mut runs = Vec::new();
+    let mut operations = vec![programmer::Operation::ReadStatusRegister];
+    if caps
+        .operation_index(programmer::Operation::ReadUserCode)
+        .is_some()
+    {
+        operations.push(programmer::Operation::ReadUserCode);
+    }
+
+    for operation in operations {
+        let argv = operation_argv(caps, operation, selection, device, frequency, chain_args)?;
+        let exec = exec_with_timeout(programmer_cli, &argv, None, None, timeout_sec).await?;
+        runs.push((argv, exec));
+    }
+
+    let text = runs
+        .iter()
+        .map(|(_, e)| format!("{}\n{}", e.stdout, e.stderr))
+        .collect::<Vec<_>>()
+        .join("\n");
+    let decoded = status::decode(
+        status::find_status_register(&text),
+        status::find_usercode(&text),
+    );
+    Ok((runs, decoded))
+}
+
+// 比較用の参照イメージ。.fs はビットストリーム本体、それ以外はそのままのバイト列
+async fn load_reference(path: &Path) -> Result<Vec<u8>> {
+    if path
+        .extension()
+        .is_some_and(|e| e.eq_ignore_ascii_case("fs"))
+    {
+        let text = tokio::fs::read_to_string(path)
+            .await
+            .with_context(|| format!("read({})", path.display()))?;
+        Ok(bitstream::parse_fs(&text)?.data)
+    } else {
+        tokio::fs::read(path)
+            .await
+            .with_context(|| format!("read({})", path.display()))
+    }
+}
+
+fn evaluate_gates(
+    build: &RunTclResponse,
+    errors: &[report::Diagnostic],
+    warnings: &[report::Diagnostic],
+    timing: Option<&report::TimingSummary>,
+    utilization: &[report::ResourceUsage],
+    req: &BuildAndProgramRequest,
+) -> Vec<GateResult> {
+    let mut gates = vec![GateResult {
+        name: "build".into(),
+        passed: build.exit_code == 0 && !build.timed_out,
+        detail: if build.timed_out {
+            "タイムアウトしました".into()
+        } else {
+            format!("exit_code {}", build.exit_code)
+        },
+    }];
+
+    gates.push(GateResult {
+        name: "no_errors".into(),
+        passed: errors.is_empty(),
+        detail: format!("ERROR {} 件", errors.len()),
+    });
+    if !req.allow_warnings.unwrap_or(true) {
+        gates.push(GateResult {
+            name: "no_warnings".into(),
+            passed: warnings.is_empty(),
+            detail: format!("WARN {} 件", warnings.len()),
+        });
+    }
+
+    if req.require_timing_met.unwrap_or(true) {
+        let (passed, detail) = match timing.map(|t| (t.met, &t.report)) {
+            Some((Some(true), r)) => (true, format!("制約を満たしています（{r}）")),
+            Some((Some(false), r)) => (false, format!("タイミング違反があります（{r}）")),
+            Some((None, r)) => (false, format!("タイミングの結果を判定できません（{r}）")),
+            None => (false, "タイミングレポートが見つかりません".into()),
+        };
+        gates.push(GateResult {
+            name: "timing_met".into(),
+            passed,
+            detail,
+        });
     }
 
-    found
+    if let Some(limit) = req.max_utilization_percent {
+        let over: Vec<String> = utilization
+            .iter()
+            .filter(|u| u.percent > limit)
+            .map(|u| format!("{} {}%", u.resource, u.percent))
+            .collect();
+        gates.push(GateResult {
+            name: "utilization".into(),
+            passed: !utilization.is_empty() && over.is_empty(),
+            detail: if utilization.is_empty() {
+                "リソースレポートが見つかりません".into()
+            } else if over.is_empty() {
+                format!("すべて {limit}% 以下")
+            } else {
+                format!("{limit}% 超過: {}", over.join(", "))
+            },
+        });
+    }
+
+    gates
+}
+
+fn describe_cable(c: &cable::CableInfo) -> String {
+    let mut s = format!("[{}] {}", c.index, c.name);
+    if let Some(serial) = c.serial.as_deref() {
+        s.push_str(&format!(" serial={serial}"));
+    }
+    if let Some(location) = c.location.as_deref() {
+        s.push_str(&format!(" location={location}"));
+    }
+    if let Some(idcode) = c.idcode.as_deref() {
+        s.push_str(&format!(" idcode={idcode}"));
+    }
+    s
+}
+
+fn cable_not_found(selector: &str, cables: &[cable::CableInfo]) -> McpError {
+    McpError::new(
+        ErrorCode::INVALID_PARAMS,
+        format!(
+            "{selector} に一致するケーブルがありません。検出されたケーブル: {}",
+            if cables.is_empty() {
+                "なし".to_string()
+            } else {
+                cables
+                    .iter()
+                    .map(describe_cable)
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            }
+        ),
+        None,
+    )
 }
 
 #[tokio::main]
@@ -956,6 +5579,27 @@ fn print_help() {
     println!("    - gowin.run_tcl: Execute arbitrary Tcl scripts via gw_sh");
     println!("    - gowin.list_cables: Enumerate available programming cables");
     println!("    - gowin.program_fs: Program .fs files to SRAM");
+    println!("    - gowin.convert_bitstream: Convert .fs to .bin / Intel HEX / MCS");
+    println!("    - gowin.build_flash_image: Assemble a multiboot flash image from a layout");
+    println!("    - gowin.scan_chain: Identify devices in the JTAG chain");
+    println!("    - gowin.device_status: Read status register, USERCODE and DONE state");
+    println!("    - gowin.reconfigure: Reboot the FPGA from its flash image");
+    println!("    - gowin.read_flash: Read back flash and compare against a bitstream");
+    println!("    - gowin.build_and_program: Build, check gates, then program");
+    println!("    - gowin.build_trends: Time series of utilization, timing and warnings per build");
+    println!(
+        "    - gowin.compare_runs: Diff utilization, timing, warnings and options of two builds"
+    );
+    println!("    - gowin.sdc_list: Parse timing constraints (.sdc) and check port references");
+    println!("    - gowin.sdc_edit: Add, change or remove .sdc commands with validation");
+    println!("    - gowin.cst_list: Parse pin constraints (.cst) and check pins and ports");
+    println!("    - gowin.cst_edit: Add, change or remove .cst pin assignments with validation");
+    println!("    - gowin.import_pinout: Import a Gowin pinout table (CSV) for .cst pin checks");
+    println!("    - gowin.list_devices: List supported devices and their JTAG frequencies");
+    println!("    - gowin.list_boards: List board profiles (device, cable, flash, default pins)");
+    println!("    - gowin.constraint_coverage: Find unconstrained ports and clocks in the netlist");
+    println!("    - gowin.pll_solve: Compute rPLL / PLLA divider settings for target clocks");
+    println!("    - gowin.ip_generate: Generate IP cores with GowinModGen and register the HDL");
     println!();
     println!("    This server communicates via stdio using the Model Context Protocol (MCP).");
     println!("    Configure your MCP client (VS Code, Claude Code, etc.) to start this server.");
@@ -0,0 +1,575 @@
+// デバイスのピン配置データベース（<project_root>/.gowin-mcp/pinout/<デバイス>.json）
+//
+// Gowin が公開しているピン配置表（Pinout の xlsx の "Pin Definitions" などのシートを
+// CSV で書き出したもの）を gowin.import_pinout で取り込む。
+// ベンダーのデータは同梱しない（再配布の条件が明らかでなく、表の改版にも追従できないため）。
+// データベースがなければ cst_list / cst_edit はピンの検証を省き、その理由と取り込み方を返す。
+//
+//   Pin Name, Function, BANK, Dual Function, Differential Pair, LVDS, QN88P, LQ144P
+//   IOL2A,    I/O,      3,    ...,           True_of_IOL2B,     True, 22,    ...
+//
+// 見出し行は "Pin Name" を含む最初の行。見出しが QN88P / MG121N のような列を
+// パッケージごとのピン番号として読む。.cst の IO_LOC の検証（存在するピンか、
+// 専用ピンでないか）、バンクの VCCIO と IO_TYPE の整合、差動ペアの確認に使う。
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::{cst, device, gprj, hdl, sdc::Issue};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Pad {
+    // IOL2A / VCCIO0 / DONE など
+    pub name: String,
+    pub function: Option<String>,
+    pub bank: Option<String>,
+    // コンフィグ / 兼用機能（MODE0、SSPI_CS_N など）
+    pub config: Option<String>,
+    // 差動ペアの相手と、こちらが True（P）側か
+    pub pair: Option<String>,
+    pub true_side: Option<bool>,
+    // True LVDS 出力に使えるか（表に列がなければ None）
+    pub true_lvds: Option<bool>,
+    // パッケージ → ピン番号
+    pub pins: BTreeMap<String, String>,
+}
+
+impl Pad {
+    pub fn is_io(&self) -> bool {
+        self.name.starts_with("IO")
+            || self
+                .function
+                .as_deref()
+                .is_some_and(|f| f.to_ascii_uppercase().contains("I/O"))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct PinoutDb {
+    pub device: String,
+    pub source: String,
+    pub imported_at_ms: u64,
+    pub packages: Vec<String>,
+    pub pads: Vec<Pad>,
+}
+
+impl PinoutDb {
+    pub fn pad_at(&self, package: &str, pin: &str) -> Option<&Pad> {
+        self.pads.iter().find(|p| {
+            p.pins
+                .get(package)
+                .is_some_and(|n| n.eq_ignore_ascii_case(pin))
+        })
+    }
+
+    fn pad(&self, name: &str) -> Option<&Pad> {
+        self.pads.iter().find(|p| p.name == name)
+    }
+
+    // 品番のパッケージ（"QN88P"）に対応する列。完全一致がなければ前方一致（"QN88"）
+    pub fn package_column(&self, package: &str) -> Option<String> {
+        let upper = package.to_ascii_uppercase();
+        self.packages
+            .iter()
+            .find(|p| **p == upper)
+            .or_else(|| {
+                self.packages
+                    .iter()
+                    .filter(|p| upper.starts_with(p.as_str()) || p.starts_with(&upper))
+                    .max_by_key(|p| p.len())
+            })
+            .cloned()
+    }
+}
+
+pub fn pinout_dir(project_root: &Path) -> PathBuf {
+    project_root.join(".gowin-mcp").join("pinout")
+}
+
+pub fn db_path(project_root: &Path, device_base: &str) -> PathBuf {
+    pinout_dir(project_root).join(format!("{device_base}.json"))
+}
+
+// 引用符（"..."、"" はエスケープ）つきの CSV
+fn parse_csv(text: &str) -> Vec<Vec<String>> {
+    let mut rows = Vec::new();
+    let mut row = Vec::new();
+    let mut cell = String::new();
+    let mut quoted = false;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if quoted && chars.peek() == Some(&'"') => {
+                chars.next();
+                cell.push('"');
+            }
+            '"' => quoted = !quoted,
+            ',' if !quoted => row.push(std::mem::take(&mut cell).trim().to_string()),
+            '\n' if !quoted => {
+                row.push(std::mem::take(&mut cell).trim().to_string());
+                rows.push(std::mem::take(&mut row));
+            }
+            '\r' if !quoted => {}
+            c => cell.push(c),
+        }
+    }
+    if !cell.is_empty() || !row.is_empty() {
+        row.push(cell.trim().to_string());
+        rows.push(row);
+    }
+    rows
+}
+
+// "QN88P" / "MG121N" / "LQ144" のようなパッケージ名か
+fn is_package(header: &str) -> bool {
+    let chars: Vec<char> = header.chars().collect();
+    let letters = chars.iter().take_while(|c| c.is_ascii_uppercase()).count();
+    let digits = chars[letters..]
+        .iter()
+        .take_while(|c| c.is_ascii_digit())
+        .count();
+    (1..=3).contains(&letters)
+        && digits >= 2
+        && chars[letters + digits..]
+            .iter()
+            .all(|c| c.is_ascii_uppercase())
+}
+
+// "True_of_IOL2B" → IOL2B
+fn pad_name_in(text: &str) -> Option<String> {
+    text.split(|c: char| !c.is_ascii_alphanumeric())
+        .find(|w| {
+            w.len() > 3
+                && ["IOL", "IOR", "IOT", "IOB"]
+                    .iter()
+                    .any(|p| w.starts_with(p))
+                && w[3..].starts_with(|c: char| c.is_ascii_digit())
+        })
+        .map(str::to_string)
+}
+
+fn non_empty(cell: Option<&String>) -> Option<String> {
+    cell.map(|c| c.trim())
+        .filter(|c| !c.is_empty() && *c != "-" && !c.eq_ignore_ascii_case("NA"))
+        .map(str::to_string)
+}
+
+// (データベース, 読み飛ばした行などの注意)
+pub fn import_csv(
+    text: &str,
+    device: &str,
+    source: &str,
+    only_package: Option<&str>,
+    imported_at_ms: u64,
+) -> Result<(PinoutDb, Vec<String>)> {
+    let rows = parse_csv(text);
+    let header_at = rows
+        .iter()
+        .position(|r| r.iter().any(|c| c.eq_ignore_ascii_case("pin name")))
+        .ok_or_else(|| anyhow!("\"Pin Name\" 列のある見出し行が見つかりません"))?;
+    let header: Vec<String> = rows[header_at]
+        .iter()
+        .map(|h| h.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect();
+    let column = |pred: &dyn Fn(&str) -> bool| header.iter().position(|h| pred(&h.to_lowercase()));
+    let name_col = column(&|h| h == "pin name").expect("header row");
+    let function_col = column(&|h| h == "function" || h == "pin function");
+    let bank_col = column(&|h| h.contains("bank") && !h.contains("vcc"));
+    let config_col = column(&|h| h.contains("dual") || h.contains("config"));
+    let diff_col = column(&|h| h.contains("diff"));
+    let lvds_col = column(&|h| h.contains("lvds"));
+
+    let mut packages: Vec<(usize, String)> = header
+        .iter()
+        .enumerate()
+        .filter(|(_, h)| is_package(h))
+        .map(|(i, h)| (i, h.clone()))
+        .collect();
+    if let Some(only) = only_package {
+        let only = only.to_ascii_uppercase();
+        packages.retain(|(_, p)| *p == only);
+        if packages.is_empty() {
+            return Err(anyhow!(
+                "パッケージ {only} の列がありません（列: {}）",
+                header.join(", ")
+            ));
+        }
+    }
+    if packages.is_empty() {
+        return Err(anyhow!(
+            "パッケージの列（QN88P などピン番号の列）が見つかりません（列: {}）",
+            header.join(", ")
+        ));
+    }
+
+    let mut notes = Vec::new();
+    let mut pads: Vec<Pad> = Vec::new();
+    for (i, row) in rows.iter().enumerate().skip(header_at + 1) {
+        let Some(name) = non_empty(row.get(name_col)) else {
+            continue;
+        };
+        let pins: BTreeMap<String, String> = packages
+            .iter()
+            .filter_map(|(col, pkg)| {
+                let pin = non_empty(row.get(*col))?;
+                (!pin.eq_ignore_ascii_case("NC")).then(|| (pkg.clone(), pin))
+            })
+            .collect();
+        if pins.is_empty() {
+            continue;
+        }
+        // 電源・GND は同じ名前が何行もある
+        if name.starts_with("IO") && pads.iter().any(|p| p.name == name) {
+            notes.push(format!(
+                "{} 行目: {name} が重複しているので読み飛ばしました",
+                i + 1
+            ));
+            continue;
+        }
+        let diff = diff_col.and_then(|c| non_empty(row.get(c)));
+        pads.push(Pad {
+            pair: diff.as_deref().and_then(pad_name_in),
+            true_side: diff.as_deref().and_then(|d| {
+                let d = d.to_ascii_lowercase();
+                if d.starts_with("true") {
+                    Some(true)
+                } else if d.starts_with("comp") {
+                    Some(false)
+                } else {
+                    None
+                }
+            }),
+            true_lvds: lvds_col.map(|c| {
+                non_empty(row.get(c)).is_some_and(|v| {
+                    let v = v.to_ascii_lowercase();
+                    v.contains("true") || v == "yes" || v == "y"
+                })
+            }),
+            function: function_col.and_then(|c| non_empty(row.get(c))),
+            bank: bank_col.and_then(|c| non_empty(row.get(c))),
+            config: config_col.and_then(|c| non_empty(row.get(c))),
+            name,
+            pins,
+        });
+    }
+    if pads.is_empty() {
+        return Err(anyhow!("ピンの行がありません"));
+    }
+
+    // 差動ペアの列がなければ、IOL2A / IOL2B のような名前の A / B を組にする（A が True 側）
+    if diff_col.is_none() {
+        let names: Vec<String> = pads.iter().map(|p| p.name.clone()).collect();
+        for pad in pads.iter_mut().filter(|p| p.name.starts_with("IO")) {
+            // 壊れた CSV では名前の末尾が多バイト文字（U+FFFD など）のこともある
+            let Some((at, side)) = pad.name.char_indices().last() else {
+                continue;
+            };
+            let stem = &pad.name[..at];
+            let other = match side {
+                'A' => format!("{stem}B"),
+                'B' => format!("{stem}A"),
+                _ => continue,
+            };
+            if names.contains(&other) {
+                pad.true_side = Some(side == 'A');
+                pad.pair = Some(other);
+            }
+        }
+        notes.push("差動ペアの列がないので、名前の A / B を組にしました".into());
+    }
+
+    Ok((
+        PinoutDb {
+            device: device.to_string(),
+            source: source.to_string(),
+            imported_at_ms,
+            packages: packages.into_iter().map(|(_, p)| p).collect(),
+            pads,
+        },
+        notes,
+    ))
+}
+
+pub fn save(project_root: &Path, db: &PinoutDb) -> Result<PathBuf> {
+    let path = db_path(project_root, &db.device);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("mkdir -p {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(db)?)
+        .with_context(|| format!("write({})", path.display()))?;
+    Ok(path)
+}
+
+// .gprj のデバイスに合うデータベースと、使うパッケージ列
+pub fn for_project(project_root: &Path) -> Option<(PinoutDb, String, PathBuf)> {
+    lookup(project_root).ok()
+}
+
+// for_project が使えない理由。ピン配置表は同梱しないので、取り込み方もあわせて返す
+pub fn missing_reason(project_root: &Path) -> Option<String> {
+    lookup(project_root).err()
+}
+
+fn lookup(project_root: &Path) -> std::result::Result<(PinoutDb, String, PathBuf), String> {
+    const HOW: &str =
+        "Gowin のピン配置表を CSV で書き出し、gowin.import_pinout で取り込むと検証します";
+    let skipped = "ピンの存在・バンクの VCCIO・差動ペアは検証していません";
+    let part = gprj::find(project_root)
+        .and_then(|p| std::fs::read_to_string(p).ok())
+        .and_then(|t| gprj::device(&t))
+        .and_then(|(name, pn)| Some((name, pn?)));
+    let Some((name, part_number)) = part else {
+        return Err(format!(".gprj に品番（pn）がないため、{skipped}"));
+    };
+    let Some(base) = device::device_base(&part_number).or_else(|| device::device_base(&name))
+    else {
+        return Err(format!(
+            "品番 {part_number} のデバイスが分からないため、{skipped}"
+        ));
+    };
+    let path = db_path(project_root, &base);
+    let Some(db) = std::fs::read(&path)
+        .ok()
+        .and_then(|b| serde_json::from_slice::<PinoutDb>(&b).ok())
+    else {
+        return Err(format!(
+            "{base} のピン配置データベース（{}）がないため、{skipped}。{HOW}",
+            path.display()
+        ));
+    };
+    let Some(package) = device::package(&part_number).and_then(|p| db.package_column(&p)) else {
+        return Err(format!(
+            "{} に品番 {part_number} のパッケージの列がないため、{skipped}。{HOW}",
+            path.display()
+        ));
+    };
+    Ok((db, package, path))
+}
+
+// IO_TYPE が求める VCCIO（"LVCMOS33" → 3.3、"SSTL135" → 1.35）
+pub fn vccio(io_type: &str) -> Option<f64> {
+    let upper = io_type.to_ascii_uppercase();
+    let digits: String = upper
+        .chars()
+        .skip_while(|c| !c.is_ascii_digit())
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    match digits.as_str() {
+        "" if ["RSDS", "MINILVDS", "PPLVDS"]
+            .iter()
+            .any(|t| upper.starts_with(t)) =>
+        {
+            Some(2.5)
+        }
+        "" => None,
+        d => {
+            let v: f64 = d.parse().ok()?;
+            Some(v / 10f64.powi(d.len() as i32 - 1))
+        }
+    }
+}
+
+pub fn is_differential(io_type: &str) -> bool {
+    let upper = io_type.to_ascii_uppercase();
+    ["LVDS", "RSDS", "LVPECL", "MIPI"]
+        .iter()
+        .any(|t| upper.contains(t))
+        || upper.ends_with("_D")
+}
+
+// データベースを使った .cst の検証
+pub fn validate(
+    db: &PinoutDb,
+    package: &str,
+    file: &cst::CstFile,
+    ports: Option<&[hdl::Port]>,
+) -> Vec<Issue> {
+    let mut issues = Vec::new();
+    let issue = |level: &str, line: Option<usize>, message: String| Issue {
+        level: level.into(),
+        line,
+        message,
+    };
+    // 入力だけはバンクの電圧を決めない（方向が分からなければ出力とみなす）
+    let is_input = |port: &str| {
+        ports
+            .and_then(|ps| ps.iter().find(|p| p.name == hdl::base_name(port)))
+            .is_some_and(|p| p.direction == "input")
+    };
+
+    // バンク → (電圧, ポート, 行)
+    let mut banks: BTreeMap<String, Vec<(f64, String, Option<usize>)>> = BTreeMap::new();
+    let mut inputs: Vec<(String, f64, String, Option<usize>)> = Vec::new();
+    for a in cst::assignments(file) {
+        let line = a.loc_line;
+        let mut pads: Vec<&Pad> = Vec::new();
+        for pin in &a.pins {
+            match db.pad_at(package, pin) {
+                None => issues.push(issue(
+                    "error",
+                    line,
+                    format!("ピン {pin} は {} {package} にありません", db.device),
+                )),
+                Some(pad) if !pad.is_io() => issues.push(issue(
+                    "error",
+                    line,
+                    format!(
+                        "ピン {pin}（{}）は I/O ではありません{}",
+                        pad.name,
+                        pad.function
+                            .as_deref()
+                            .map(|f| format!("（{f}）"))
+                            .unwrap_or_default()
+                    ),
+                )),
+                Some(pad) => {
+                    if let Some(cfg) = pad.config.as_deref() {
+                        issues.push(issue(
+                            "warning",
+                            line,
+                            format!(
+                                "ピン {pin}（{}）はコンフィグ兼用（{cfg}）です。ユーザー I/O として使う設定が必要な場合があります",
+                                pad.name
+                            ),
+                        ));
+                    }
+                    pads.push(pad);
+                }
+            }
+        }
+        if pads.len() != a.pins.len() {
+            continue;
+        }
+
+        let io_type = a.io_type.as_deref().unwrap_or("");
+        if is_differential(io_type) {
+            let ok = match pads.as_slice() {
+                // N 側もこのパッケージでボンディングされている必要がある
+                [p] => {
+                    p.true_side != Some(false)
+                        && p.pair
+                            .as_deref()
+                            .and_then(|q| db.pad(q))
+                            .is_some_and(|q| q.pins.contains_key(package))
+                }
+                [p, n] => p.pair.as_deref() == Some(n.name.as_str()) && p.true_side != Some(false),
+                _ => false,
+            };
+            if !ok {
+                issues.push(issue(
+                    "error",
+                    line,
+                    format!(
+                        "{}（{io_type}）: 差動ペアの True（P）側のピン、または \"P,N\" の組を指定してください（{}）",
+                        a.port,
+                        pads.iter()
+                            .map(|p| format!(
+                                "{}={}{}",
+                                p.name,
+                                match p.true_side {
+                                    Some(true) => "True",
+                                    Some(false) => "Comp",
+                                    None => "?",
+                                },
+                                p.pair
+                                    .as_deref()
+                                    .map(|q| format!(" の相手 {q}"))
+                                    .unwrap_or_default()
+                            ))
+                            .collect::<Vec<_>>()
+                            .join(", ")
+                    ),
+                ));
+            } else if io_type.eq_ignore_ascii_case("LVDS25")
+                && !is_input(&a.port)
+                && pads[0].true_lvds == Some(false)
+            {
+                issues.push(issue(
+                    "error",
+                    line,
+                    format!(
+                        "{}: {} は True LVDS 出力に使えないピンです（LVDS25E などのエミュレーションを使ってください）",
+                        a.port, pads[0].name
+                    ),
+                ));
+            }
+        } else if pads.len() > 1 {
+            issues.push(issue(
+                "error",
+                line,
+                format!(
+                    "{}: 差動でない IO_TYPE（{}）にピンが {} つあります",
+                    a.port,
+                    if io_type.is_empty() {
+                        "未指定"
+                    } else {
+                        io_type
+                    },
+                    pads.len()
+                ),
+            ));
+        }
+
+        let Some(bank) = pads.first().and_then(|p| p.bank.clone()) else {
+            continue;
+        };
+        if let Some(v) = a
+            .attributes
+            .get("BANK_VCCIO")
+            .and_then(|v| v.parse::<f64>().ok())
+        {
+            banks.entry(bank.clone()).or_default().push((
+                v,
+                format!("{} の BANK_VCCIO", a.port),
+                a.port_line,
+            ));
+        }
+        let Some(v) = vccio(io_type) else {
+            continue;
+        };
+        if is_input(&a.port) {
+            if !is_differential(io_type) {
+                inputs.push((bank, v, a.port.clone(), line));
+            }
+        } else {
+            banks
+                .entry(bank)
+                .or_default()
+                .push((v, format!("{}（{io_type}）", a.port), line));
+        }
+    }
+
+    for (bank, uses) in &banks {
+        let first = &uses[0];
+        if let Some(other) = uses.iter().find(|u| u.0 != first.0) {
+            issues.push(issue(
+                "error",
+                other.2,
+                format!(
+                    "バンク {bank} の VCCIO が合いません: {} は {}V、{} は {}V",
+                    first.1, first.0, other.1, other.0
+                ),
+            ));
+        }
+    }
+    for (bank, v, port, line) in inputs {
+        if let Some((bank_v, _, _)) = banks.get(&bank).and_then(|u| u.first())
+            && v > *bank_v
+        {
+            issues.push(issue(
+                "warning",
+                line,
+                format!(
+                    "{port}: 入力の {v}V がバンク {bank} の VCCIO {bank_v}V より高くなっています"
+                ),
+            ));
+        }
+    }
+    issues
+}
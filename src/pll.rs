@@ -0,0 +1,542 @@
+// PLL の分周設定の計算（rPLL: GW1N / GW2A 系、PLLA: GW5A 系）
+//
+//   rPLL: PFD = FCLKIN / (IDIV_SEL+1)
+//         CLKOUT = PFD * (FBDIV_SEL+1)、VCO = CLKOUT * ODIV_SEL
+//         CLKOUTD = CLKOUT / DYN_SDIV_SEL（2〜128 の偶数）、CLKOUTD3 = CLKOUT / 3
+//   PLLA: PFD = FCLKIN / IDIV_SEL、VCO = PFD * FBDIV_SEL * MDIV_SEL
+//         CLKOUTn = VCO / ODIVn_SEL（n = 0〜6）
+//
+// 周波数の範囲はデータシートの代表的なスピードグレード（C6/I5 など）の値。
+// 全組み合わせを調べ、誤差の最大値（ppm）→ PFD → VCO の高い順に並べる。
+
+use std::collections::BTreeSet;
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Limits {
+    pub clkin_min_mhz: f64,
+    pub clkin_max_mhz: f64,
+    pub pfd_min_mhz: f64,
+    pub pfd_max_mhz: f64,
+    pub vco_min_mhz: f64,
+    pub vco_max_mhz: f64,
+    pub clkout_min_mhz: f64,
+    pub clkout_max_mhz: f64,
+}
+
+const fn limits(clkin: (f64, f64), pfd: (f64, f64), vco: (f64, f64), clkout: (f64, f64)) -> Limits {
+    Limits {
+        clkin_min_mhz: clkin.0,
+        clkin_max_mhz: clkin.1,
+        pfd_min_mhz: pfd.0,
+        pfd_max_mhz: pfd.1,
+        vco_min_mhz: vco.0,
+        vco_max_mhz: vco.1,
+        clkout_min_mhz: clkout.0,
+        clkout_max_mhz: clkout.1,
+    }
+}
+
+const RPLL_ODIV: [u32; 11] = [2, 4, 8, 16, 32, 48, 64, 80, 96, 112, 128];
+
+// 系列 → (プリミティブ, 範囲)
+pub fn primitive(family: &str) -> Option<(&'static str, Limits)> {
+    match family.to_ascii_uppercase().as_str() {
+        "GW1N" | "GW1NR" | "GW1NZ" | "GW1NS" | "GW1NSR" | "GW1NSER" => Some((
+            "rPLL",
+            limits((3.0, 400.0), (3.0, 400.0), (400.0, 1200.0), (3.125, 600.0)),
+        )),
+        "GW2A" | "GW2AR" | "GW2AN" => Some((
+            "rPLL",
+            limits(
+                (3.0, 500.0),
+                (3.0, 500.0),
+                (500.0, 1250.0),
+                (3.90625, 625.0),
+            ),
+        )),
+        "GW5A" | "GW5AT" | "GW5AST" => Some((
+            "PLLA",
+            limits((10.0, 800.0), (10.0, 400.0), (800.0, 1600.0), (6.25, 800.0)),
+        )),
+        _ => None,
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Output {
+    // CLKOUT / CLKOUTD / CLKOUTD3 / CLKOUT0..6
+    pub pin: String,
+    pub requested_mhz: f64,
+    pub actual_mhz: f64,
+    pub error_ppm: f64,
+    // DYN_SDIV_SEL / ODIVn_SEL（CLKOUTD3 は 3、rPLL の CLKOUT は None）
+    pub divider: Option<u32>,
+    // 入力クロックに対する倍率（create_generated_clock の -multiply_by / -divide_by）
+    pub multiply_by: u64,
+    pub divide_by: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Solution {
+    pub rank: usize,
+    pub idiv_sel: u32,
+    pub fbdiv_sel: u32,
+    // rPLL のみ
+    pub odiv_sel: Option<u32>,
+    // PLLA のみ
+    pub mdiv_sel: Option<u32>,
+    pub pfd_mhz: f64,
+    pub vco_mhz: f64,
+    pub outputs: Vec<Output>,
+    pub max_error_ppm: f64,
+    pub verilog: String,
+    pub sdc: String,
+}
+
+// インスタンス化と制約の名前
+pub struct Naming<'a> {
+    pub device: &'a str,
+    pub instance: &'a str,
+    pub input_clock: &'a str,
+}
+
+fn ppm(actual: f64, requested: f64) -> f64 {
+    ((actual - requested) / requested * 1e6 * 1000.0).round() / 1000.0
+}
+
+fn round_mhz(f: f64) -> f64 {
+    (f * 1e6).round() / 1e6
+}
+
+fn gcd(a: u64, b: u64) -> u64 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn ratio(multiply_by: u64, divide_by: u64) -> (u64, u64) {
+    let g = gcd(multiply_by, divide_by).max(1);
+    (multiply_by / g, divide_by / g)
+}
+
+fn within(f: f64, (min, max): (f64, f64)) -> bool {
+    f >= min - 1e-9 && f <= max + 1e-9
+}
+
+// 分周比の候補から、want に最も近い出力を作るもの
+fn best_divider(
+    source: f64,
+    want: f64,
+    candidates: impl Iterator<Item = u32>,
+) -> Option<(u32, f64)> {
+    candidates
+        .map(|d| (d, source / d as f64))
+        .min_by(|a, b| (a.1 - want).abs().total_cmp(&(b.1 - want).abs()))
+}
+
+// PLLA の ODIVn_SEL（1〜128）のうち、VCO / want に近く出力が範囲内のもの
+fn odiv_near(vco: f64, want: f64, out_range: (f64, f64)) -> impl Iterator<Item = u32> {
+    let d = (vco / want).round().clamp(1.0, 128.0) as u32;
+    (d.saturating_sub(1).max(1)..=(d + 1).min(128))
+        .filter(move |d| within(vco / *d as f64, out_range))
+}
+
+// 誤差、PFD、VCO、重複除去のキー（同じ出力になる設定は 1 つだけ残す）、分周設定
+struct Candidate {
+    max_error_ppm: f64,
+    pfd_mhz: f64,
+    vco_mhz: f64,
+    key: Vec<u64>,
+    idiv: u32,
+    fbdiv: u32,
+    third: u32,
+}
+
+pub fn solve(
+    primitive: &str,
+    limits: &Limits,
+    input_mhz: f64,
+    outputs_mhz: &[f64],
+    max_solutions: usize,
+    naming: &Naming,
+) -> Result<Vec<Solution>> {
+    if outputs_mhz.is_empty() {
+        return Err(anyhow!(
+            "outputs_mhz に出力周波数を 1 つ以上指定してください"
+        ));
+    }
+    if let Some(bad) = outputs_mhz.iter().find(|f| !f.is_finite() || **f <= 0.0) {
+        return Err(anyhow!("出力周波数は正の値にしてください（指定: {bad}）"));
+    }
+    if !within(input_mhz, (limits.clkin_min_mhz, limits.clkin_max_mhz)) {
+        return Err(anyhow!(
+            "入力 {input_mhz} MHz は {primitive} の範囲（{}〜{} MHz）外です",
+            limits.clkin_min_mhz,
+            limits.clkin_max_mhz
+        ));
+    }
+    let max_outputs = if primitive == "rPLL" { 2 } else { 7 };
+    if outputs_mhz.len() > max_outputs {
+        return Err(anyhow!(
+            "{primitive} の出力は {max_outputs} つまでです（rPLL は CLKOUT と CLKOUTD / CLKOUTD3）"
+        ));
+    }
+    // rPLL の CLKOUTD / CLKOUTD3 は CLKOUT をさらに分周するので下限だけ緩い
+    for (i, want) in outputs_mhz.iter().enumerate() {
+        let min = if primitive == "rPLL" && i > 0 {
+            limits.clkout_min_mhz / 128.0
+        } else {
+            limits.clkout_min_mhz
+        };
+        if !within(*want, (min, limits.clkout_max_mhz)) {
+            return Err(anyhow!(
+                "出力 {want} MHz は {primitive} の範囲（{min}〜{} MHz）外です",
+                limits.clkout_max_mhz
+            ));
+        }
+    }
+    let pfd_range = (limits.pfd_min_mhz, limits.pfd_max_mhz);
+    let vco_range = (limits.vco_min_mhz, limits.vco_max_mhz);
+    let out_range = (limits.clkout_min_mhz, limits.clkout_max_mhz);
+
+    let mut candidates = Vec::new();
+    if primitive == "rPLL" {
+        for idiv in 0..64u32 {
+            let pfd = input_mhz / (idiv + 1) as f64;
+            if !within(pfd, pfd_range) {
+                continue;
+            }
+            for fbdiv in 0..64u32 {
+                let clkout = pfd * (fbdiv + 1) as f64;
+                if !within(clkout, out_range) {
+                    continue;
+                }
+                for odiv in RPLL_ODIV {
+                    let vco = clkout * odiv as f64;
+                    if !within(vco, vco_range) {
+                        continue;
+                    }
+                    let mut errors = vec![ppm(clkout, outputs_mhz[0])];
+                    if let Some(&want) = outputs_mhz.get(1) {
+                        let (_, f) = best_divider(clkout, want, (2..=128).step_by(2).chain([3]))
+                            .expect("dividers");
+                        errors.push(ppm(f, want));
+                    }
+                    candidates.push(Candidate {
+                        max_error_ppm: errors.iter().map(|e| e.abs()).fold(0.0, f64::max),
+                        pfd_mhz: pfd,
+                        vco_mhz: vco,
+                        // 副出力も CLKOUT から決まる
+                        key: vec![(clkout * 1e6).round() as u64],
+                        idiv,
+                        fbdiv,
+                        third: odiv,
+                    });
+                }
+            }
+        }
+    } else {
+        for idiv in 1..=64u32 {
+            let pfd = input_mhz / idiv as f64;
+            if !within(pfd, pfd_range) {
+                continue;
+            }
+            for fbdiv in 1..=64u32 {
+                for mdiv in 2..=128u32 {
+                    let vco = pfd * (fbdiv * mdiv) as f64;
+                    if !within(vco, vco_range) {
+                        continue;
+                    }
+                    let actual: Option<Vec<f64>> = outputs_mhz
+                        .iter()
+                        .map(|&want| {
+                            best_divider(vco, want, odiv_near(vco, want, out_range)).map(|b| b.1)
+                        })
+                        .collect();
+                    if let Some(actual) = actual {
+                        candidates.push(Candidate {
+                            max_error_ppm: actual
+                                .iter()
+                                .zip(outputs_mhz)
+                                .map(|(f, want)| ppm(*f, *want).abs())
+                                .fold(0.0, f64::max),
+                            pfd_mhz: pfd,
+                            vco_mhz: vco,
+                            key: actual.iter().map(|f| (f * 1e6).round() as u64).collect(),
+                            idiv,
+                            fbdiv,
+                            third: mdiv,
+                        });
+                    }
+                }
+            }
+        }
+    }
+    if candidates.is_empty() {
+        return Err(anyhow!(
+            "入力 {input_mhz} MHz から {primitive} の範囲（PFD {}〜{} MHz、VCO {}〜{} MHz、出力 {}〜{} MHz）で作れる設定がありません",
+            limits.pfd_min_mhz,
+            limits.pfd_max_mhz,
+            limits.vco_min_mhz,
+            limits.vco_max_mhz,
+            limits.clkout_min_mhz,
+            limits.clkout_max_mhz
+        ));
+    }
+
+    // 誤差 → PFD が高い → VCO が高い（どちらもジッタが小さい）→ 分周比が小さい順
+    candidates.sort_by(|a, b| {
+        a.max_error_ppm
+            .total_cmp(&b.max_error_ppm)
+            .then(b.pfd_mhz.total_cmp(&a.pfd_mhz))
+            .then(b.vco_mhz.total_cmp(&a.vco_mhz))
+            .then(a.idiv.cmp(&b.idiv))
+            .then(a.fbdiv.cmp(&b.fbdiv))
+    });
+    let mut seen = BTreeSet::new();
+    let solutions = candidates
+        .into_iter()
+        .filter(|c| seen.insert(c.key.clone()))
+        .take(max_solutions.max(1))
+        .enumerate()
+        .map(|(i, c)| {
+            if primitive == "rPLL" {
+                rpll_solution(i + 1, &c, input_mhz, outputs_mhz, naming)
+            } else {
+                plla_solution(i + 1, &c, input_mhz, outputs_mhz, out_range, naming)
+            }
+        })
+        .collect();
+    Ok(solutions)
+}
+
+fn output(pin: String, want: f64, actual: f64, divider: Option<u32>, mul: u64, div: u64) -> Output {
+    let (multiply_by, divide_by) = ratio(mul, div);
+    Output {
+        pin,
+        requested_mhz: want,
+        actual_mhz: round_mhz(actual),
+        error_ppm: ppm(actual, want),
+        divider,
+        multiply_by,
+        divide_by,
+    }
+}
+
+fn generated_clocks(outputs: &[Output], naming: &Naming) -> String {
+    outputs
+        .iter()
+        .map(|o| {
+            format!(
+                "create_generated_clock -name {}_{} -source [get_ports {{{}}}] -master_clock {} -multiply_by {} -divide_by {} [get_pins {{{}/{}}}]\n",
+                naming.instance,
+                o.pin.to_ascii_lowercase(),
+                naming.input_clock,
+                naming.input_clock,
+                o.multiply_by,
+                o.divide_by,
+                naming.instance,
+                o.pin
+            )
+        })
+        .collect()
+}
+
+fn fclkin(input_mhz: f64) -> String {
+    let s = format!("{:.6}", round_mhz(input_mhz));
+    s.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+fn rpll_solution(
+    rank: usize,
+    c: &Candidate,
+    input_mhz: f64,
+    outputs_mhz: &[f64],
+    naming: &Naming,
+) -> Solution {
+    let (idiv, fbdiv, odiv) = (c.idiv, c.fbdiv, c.third);
+    let pfd = input_mhz / (idiv + 1) as f64;
+    let clkout = pfd * (fbdiv + 1) as f64;
+    let (mul, div) = ((fbdiv + 1) as u64, (idiv + 1) as u64);
+
+    let mut outputs = vec![output(
+        "CLKOUT".into(),
+        outputs_mhz[0],
+        clkout,
+        None,
+        mul,
+        div,
+    )];
+    let mut sdiv = 2;
+    if let Some(&want) = outputs_mhz.get(1) {
+        let (d, f) = best_divider(clkout, want, (2..=128).step_by(2).chain([3])).expect("dividers");
+        let pin = if d == 3 {
+            "CLKOUTD3"
+        } else {
+            sdiv = d;
+            "CLKOUTD"
+        };
+        outputs.push(output(pin.into(), want, f, Some(d), mul, div * d as u64));
+    }
+
+    let wire = |pin: &str| {
+        if outputs.iter().any(|o| o.pin == pin) {
+            format!("{}_{}", naming.instance, pin.to_ascii_lowercase())
+        } else {
+            String::new()
+        }
+    };
+    let verilog = format!(
+        r#"wire {inst}_lock;
+{wires}
+rPLL #(
+    .FCLKIN("{fclkin}"),
+    .DEVICE("{device}"),
+    .IDIV_SEL({idiv}),
+    .FBDIV_SEL({fbdiv}),
+    .ODIV_SEL({odiv}),
+    .DYN_IDIV_SEL("false"),
+    .DYN_FBDIV_SEL("false"),
+    .DYN_ODIV_SEL("false"),
+    .DYN_SDIV_SEL({sdiv}),
+    .PSDA_SEL("0000"),
+    .DYN_DA_EN("true"),
+    .DUTYDA_SEL("1000"),
+    .CLKFB_SEL("internal"),
+    .CLKOUT_BYPASS("false"),
+    .CLKOUTP_BYPASS("false"),
+    .CLKOUTD_BYPASS("false"),
+    .CLKOUTD_SRC("CLKOUT"),
+    .CLKOUTD3_SRC("CLKOUT")
+) {inst} (
+    .CLKOUT({clkout}),
+    .LOCK({inst}_lock),
+    .CLKOUTP(),
+    .CLKOUTD({clkoutd}),
+    .CLKOUTD3({clkoutd3}),
+    .RESET(1'b0),
+    .RESET_P(1'b0),
+    .CLKIN({input}),
+    .CLKFB(1'b0),
+    .FBDSEL(6'b0),
+    .IDSEL(6'b0),
+    .ODSEL(6'b0),
+    .PSDA(4'b0),
+    .DUTYDA(4'b0),
+    .FDLY(4'b0)
+);
+"#,
+        inst = naming.instance,
+        wires = outputs
+            .iter()
+            .map(|o| format!("wire {};", wire(&o.pin)))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        fclkin = fclkin(input_mhz),
+        device = naming.device,
+        clkout = wire("CLKOUT"),
+        clkoutd = wire("CLKOUTD"),
+        clkoutd3 = wire("CLKOUTD3"),
+        input = naming.input_clock,
+    );
+
+    Solution {
+        rank,
+        idiv_sel: idiv,
+        fbdiv_sel: fbdiv,
+        odiv_sel: Some(odiv),
+        mdiv_sel: None,
+        pfd_mhz: round_mhz(pfd),
+        vco_mhz: round_mhz(clkout * odiv as f64),
+        max_error_ppm: c.max_error_ppm,
+        sdc: generated_clocks(&outputs, naming),
+        outputs,
+        verilog,
+    }
+}
+
+fn plla_solution(
+    rank: usize,
+    c: &Candidate,
+    input_mhz: f64,
+    outputs_mhz: &[f64],
+    out_range: (f64, f64),
+    naming: &Naming,
+) -> Solution {
+    let (idiv, fbdiv, mdiv) = (c.idiv, c.fbdiv, c.third);
+    let pfd = input_mhz / idiv as f64;
+    let vco = pfd * (fbdiv * mdiv) as f64;
+    let outputs: Vec<Output> = outputs_mhz
+        .iter()
+        .enumerate()
+        .map(|(n, &want)| {
+            let (d, f) =
+                best_divider(vco, want, odiv_near(vco, want, out_range)).expect("checked in solve");
+            output(
+                format!("CLKOUT{n}"),
+                want,
+                f,
+                Some(d),
+                (fbdiv * mdiv) as u64,
+                (idiv * d) as u64,
+            )
+        })
+        .collect();
+
+    let mut params = vec![
+        format!("    .FCLKIN(\"{}\")", fclkin(input_mhz)),
+        format!("    .IDIV_SEL({idiv})"),
+        format!("    .FBDIV_SEL({fbdiv})"),
+        format!("    .MDIV_SEL({mdiv})"),
+        "    .MDIV_FRAC_SEL(0)".into(),
+        "    .CLKFB_SEL(\"INTERNAL\")".into(),
+    ];
+    let mut ports = vec![
+        format!("    .CLKIN({})", naming.input_clock),
+        "    .CLKFB(1'b0)".into(),
+        "    .RESET(1'b0)".into(),
+        "    .PLLPWD(1'b0)".into(),
+        "    .RESET_I(1'b0)".into(),
+        "    .RESET_O(1'b0)".into(),
+        format!("    .LOCK({}_lock)", naming.instance),
+    ];
+    for n in 0..7 {
+        match outputs.get(n) {
+            Some(o) => {
+                params.push(format!("    .ODIV{n}_SEL({})", o.divider.unwrap_or(1)));
+                params.push(format!("    .CLKOUT{n}_EN(\"TRUE\")"));
+                ports.push(format!("    .CLKOUT{n}({}_clkout{n})", naming.instance));
+            }
+            None => {
+                params.push(format!("    .CLKOUT{n}_EN(\"FALSE\")"));
+                ports.push(format!("    .CLKOUT{n}()"));
+            }
+        }
+    }
+    let verilog = format!(
+        "wire {inst}_lock;\n{wires}\nPLLA #(\n{params}\n) {inst} (\n{ports}\n);\n",
+        inst = naming.instance,
+        wires = (0..outputs.len())
+            .map(|n| format!("wire {}_clkout{n};", naming.instance))
+            .collect::<Vec<_>>()
+            .join("\n"),
+        params = params.join(",\n"),
+        ports = ports.join(",\n"),
+    );
+
+    Solution {
+        rank,
+        idiv_sel: idiv,
+        fbdiv_sel: fbdiv,
+        odiv_sel: None,
+        mdiv_sel: Some(mdiv),
+        pfd_mhz: round_mhz(pfd),
+        vco_mhz: round_mhz(vco),
+        max_error_ppm: c.max_error_ppm,
+        sdc: generated_clocks(&outputs, naming),
+        outputs,
+        verilog,
+    }
+}
@@ -0,0 +1,102 @@
+// programmer_cli の操作（書き込み先ごとの操作番号など）
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Target {
+    Sram,
+    EmbFlash,
+    ExtFlash,
+}
+
+impl Target {
+    pub fn parse(s: &str) -> Option<Self> {
+        match s.trim().to_lowercase().as_str() {
+            "sram" => Some(Self::Sram),
+            "embflash" | "emb_flash" | "internal_flash" => Some(Self::EmbFlash),
+            "exflash" | "ext_flash" | "external_flash" | "spi_flash" => Some(Self::ExtFlash),
+            _ => None,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Self::Sram => "sram",
+            Self::EmbFlash => "embflash",
+            Self::ExtFlash => "exflash",
+        }
+    }
+
+    // フラッシュの読み出し操作（SRAM は対象外）
+    pub fn read_operation(self) -> Option<Operation> {
+        match self {
+            Self::Sram => None,
+            Self::EmbFlash => Some(Operation::EmbFlashRead),
+            Self::ExtFlash => Some(Operation::ExtFlashRead),
+        }
+    }
+
+    pub fn operation(self, verify: bool) -> Operation {
+        match (self, verify) {
+            (Self::Sram, false) => Operation::SramProgram,
+            (Self::Sram, true) => Operation::SramProgramVerify,
+            (Self::EmbFlash, false) => Operation::EmbFlashProgram,
+            (Self::EmbFlash, true) => Operation::EmbFlashProgramVerify,
+            (Self::ExtFlash, false) => Operation::ExtFlashProgram,
+            (Self::ExtFlash, true) => Operation::ExtFlashProgramVerify,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Operation {
+    ReadDeviceCodes,
+    ReadStatusRegister,
+    ReadUserCode,
+    Reprogram,
+    SramProgram,
+    SramProgramVerify,
+    EmbFlashProgram,
+    EmbFlashProgramVerify,
+    ExtFlashProgram,
+    ExtFlashProgramVerify,
+    EmbFlashRead,
+    ExtFlashRead,
+}
+
+impl Operation {
+    // programmer_cli --help に載る操作名
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::ReadDeviceCodes => "Read Device Codes",
+            Self::ReadStatusRegister => "Read Status Register",
+            Self::ReadUserCode => "Read User Code",
+            Self::Reprogram => "Reprogram",
+            Self::SramProgram => "SRAM Program",
+            Self::SramProgramVerify => "SRAM Program and Verify",
+            Self::EmbFlashProgram => "embFlash Erase,Program",
+            Self::EmbFlashProgramVerify => "embFlash Erase,Program,Verify",
+            Self::ExtFlashProgram => "exFlash Erase,Program",
+            Self::ExtFlashProgramVerify => "exFlash Erase,Program,Verify",
+            Self::EmbFlashRead => "embFlash Read",
+            Self::ExtFlashRead => "exFlash Read",
+        }
+    }
+
+    // --help から操作一覧を取れなかったときの番号（Gowin Programmer の既定の並び）。
+    // 番号がバージョンで揺れる操作は None（--help に載っている場合のみ使う）
+    pub fn default_index(self) -> Option<u32> {
+        match self {
+            Self::ReadDeviceCodes => Some(0),
+            Self::ReadStatusRegister
+            | Self::ReadUserCode
+            | Self::EmbFlashRead
+            | Self::ExtFlashRead => None,
+            Self::Reprogram => Some(1),
+            Self::SramProgram => Some(2),
+            Self::SramProgramVerify => Some(4),
+            Self::EmbFlashProgram => Some(5),
+            Self::EmbFlashProgramVerify => Some(6),
+            Self::ExtFlashProgram => Some(8),
+            Self::ExtFlashProgramVerify => Some(9),
+        }
+    }
+}
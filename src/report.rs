@@ -0,0 +1,330 @@
+// gw_sh の出力と impl/pnr のレポート（タイミング / リソース使用率）の解析
+//
+// 診断メッセージの例:
+//   ERROR (EX3937) : Instantiating unknown module 'foo'("/path/src/top.v":12)
+//   WARN  (PA1001) : Net 'x' has no driver
+//
+// レポートは .tr / .tr.html（タイミング）と .rpt.txt / .rpt.html（リソース）。
+// HTML はタグを落として表の行を "|" 区切りのテキストにしてから同じ処理をする。
+
+use std::path::{Path, PathBuf};
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Diagnostic {
+    pub level: String,
+    pub code: Option<String>,
+    pub message: String,
+    pub file: Option<String>,
+    pub line: Option<u32>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ClockTiming {
+    pub clock: String,
+    pub constraint_mhz: Option<f64>,
+    pub fmax_mhz: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TimingSummary {
+    pub report: String,
+    // 判定できなければ None
+    pub met: Option<bool>,
+    pub clocks: Vec<ClockTiming>,
+    pub setup_tns: Option<f64>,
+    pub hold_tns: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ResourceUsage {
+    pub resource: String,
+    pub used: u64,
+    pub total: u64,
+    pub percent: f64,
+}
+
+pub fn parse_diagnostics(text: &str) -> Vec<Diagnostic> {
+    let mut found = Vec::new();
+    for line in text.lines() {
+        let t = line.trim();
+        let upper: String = t.chars().take(8).collect::<String>().to_ascii_uppercase();
+        let level = ["ERROR", "WARNING", "WARN", "INFO", "NOTE"]
+            .into_iter()
+            .find(|l| upper.starts_with(l));
+        let Some(level) = level else {
+            continue;
+        };
+        let rest = t[level.len()..].trim_start();
+        let (code, rest) = match rest.strip_prefix('(').and_then(|r| r.split_once(')')) {
+            Some((code, after)) => (Some(code.trim().to_string()), after.trim_start()),
+            None => (None, rest),
+        };
+        // "ERROR:" / "ERROR (X) :" 以外（"Errors: 0" など）は診断ではない
+        let Some(message) = rest.strip_prefix(':') else {
+            continue;
+        };
+        let (message, file, line_no) = split_location(message.trim());
+        found.push(Diagnostic {
+            level: if level == "WARNING" { "WARN" } else { level }.to_string(),
+            code,
+            message,
+            file,
+            line: line_no,
+        });
+    }
+    found
+}
+
+// 末尾の ("path":12) を場所として切り出す
+fn split_location(message: &str) -> (String, Option<String>, Option<u32>) {
+    if let Some(open) = message.rfind("(\"")
+        && message.ends_with(')')
+    {
+        let inner = &message[open + 2..message.len() - 1];
+        if let Some((file, line)) = inner.rsplit_once("\":")
+            && let Ok(line) = line.trim().parse()
+        {
+            return (
+                message[..open].trim_end().to_string(),
+                Some(file.to_string()),
+                Some(line),
+            );
+        }
+    }
+    (message.to_string(), None, None)
+}
+
+// HTML を "|" 区切りの行テキストにする（プレーンテキストはそのまま）
+pub fn flatten_report(text: &str) -> String {
+    if !text.contains("<html") && !text.contains("<table") {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('<') {
+        out.push_str(&rest[..start]);
+        let Some(end) = rest[start..].find('>') else {
+            break;
+        };
+        let tag = rest[start + 1..start + end].to_ascii_lowercase();
+        if tag.starts_with("/td") || tag.starts_with("/th") {
+            out.push('|');
+        } else if tag.starts_with("/tr") || tag.starts_with("br") || tag.starts_with("/p") {
+            out.push('\n');
+        }
+        rest = &rest[start + end + 1..];
+    }
+    out.push_str(rest);
+    out.replace("&nbsp;", " ")
+        .replace("&lt;", "<")
+        .replace("&gt;", ">")
+        .replace("&amp;", "&")
+}
+
+// "|" 区切り、なければタブか 2 つ以上の空白で区切る
+fn cells(line: &str) -> Vec<&str> {
+    let parts: Vec<&str> = if line.contains('|') {
+        line.split('|').collect()
+    } else {
+        line.split('\t').flat_map(|p| p.split("  ")).collect()
+    };
+    parts
+        .into_iter()
+        .map(str::trim)
+        .filter(|c| !c.is_empty())
+        .collect()
+}
+
+fn mhz(cell: &str) -> Option<f64> {
+    let low = cell.to_ascii_lowercase();
+    let idx = low.find("mhz")?;
+    low[..idx].trim().trim_end_matches('(').trim().parse().ok()
+}
+
+fn number(cell: &str) -> Option<f64> {
+    cell.trim().parse().ok()
+}
+
+pub fn parse_timing(report: &Path, text: &str) -> TimingSummary {
+    let flat = flatten_report(text);
+    let mut clocks: Vec<ClockTiming> = Vec::new();
+    let mut setup_tns: Option<f64> = None;
+    let mut hold_tns: Option<f64> = None;
+
+    for line in flat.lines() {
+        let row = cells(line);
+
+        // Max Frequency Summary: | NO. | Clock | Constraint | Actual Fmax | ...
+        let mhz_cells: Vec<(usize, f64)> = row
+            .iter()
+            .enumerate()
+            .filter_map(|(i, c)| mhz(c).map(|v| (i, v)))
+            .collect();
+        if let Some(&(first, constraint)) = mhz_cells.first() {
+            let clock = row[..first]
+                .iter()
+                .rev()
+                .find(|c| number(c).is_none())
+                .map(|c| c.to_string());
+            if let Some(clock) = clock
+                && !clocks.iter().any(|c| c.clock == clock)
+            {
+                let (constraint_mhz, fmax_mhz) = match mhz_cells.get(1) {
+                    Some(&(_, fmax)) => (Some(constraint), Some(fmax)),
+                    None => (None, Some(constraint)),
+                };
+                clocks.push(ClockTiming {
+                    clock,
+                    constraint_mhz,
+                    fmax_mhz,
+                });
+            }
+            continue;
+        }
+
+        // Total Negative Slack Summary: | Clock | Setup/Hold | TNS | Endpoints |
+        let kind = row.iter().position(|c| {
+            let l = c.to_ascii_lowercase();
+            l == "setup" || l == "hold"
+        });
+        if let Some(k) = kind
+            && let Some(tns) = row[k + 1..].iter().find_map(|c| number(c))
+        {
+            let slot = if row[k].eq_ignore_ascii_case("setup") {
+                &mut setup_tns
+            } else {
+                &mut hold_tns
+            };
+            *slot = Some(slot.unwrap_or(0.0) + tns.min(0.0));
+        }
+    }
+
+    let low = flat.to_ascii_lowercase();
+    let slack_violated = [setup_tns, hold_tns].iter().flatten().any(|t| *t < 0.0);
+    let fmax_violated = clocks
+        .iter()
+        .any(|c| matches!((c.constraint_mhz, c.fmax_mhz), (Some(want), Some(got)) if got < want));
+    let met = if slack_violated || fmax_violated {
+        Some(false)
+    } else if low.contains("all constraints were met")
+        || setup_tns.is_some()
+        || clocks.iter().any(|c| c.constraint_mhz.is_some())
+    {
+        Some(true)
+    } else {
+        None
+    };
+
+    TimingSummary {
+        report: report.display().to_string(),
+        met,
+        clocks,
+        setup_tns,
+        hold_tns,
+    }
+}
+
+// "Logic | 290/20736  2%" のような行
+pub fn parse_utilization(text: &str) -> Vec<ResourceUsage> {
+    let flat = flatten_report(text);
+    let mut found: Vec<ResourceUsage> = Vec::new();
+    for line in flat.lines() {
+        let row = cells(line);
+        let Some(name) = row.first() else {
+            continue;
+        };
+        let name = name.trim_start_matches('-').trim();
+        let ratio = row.iter().skip(1).find_map(|c| {
+            c.split_whitespace().find_map(|tok| {
+                let (used, total) = tok.split_once('/')?;
+                Some((
+                    used.trim().parse::<u64>().ok()?,
+                    total.trim().parse::<u64>().ok()?,
+                ))
+            })
+        });
+        let Some((used, total)) = ratio else {
+            continue;
+        };
+        if total == 0 || name.is_empty() || found.iter().any(|r| r.resource == name) {
+            continue;
+        }
+        found.push(ResourceUsage {
+            resource: name.to_string(),
+            used,
+            total,
+            percent: (used as f64 * 10000.0 / total as f64).round() / 100.0,
+        });
+    }
+    found
+}
+
+fn newest_matching(dir: &Path, suffixes: &[&str]) -> Option<PathBuf> {
+    // suffixes は優先順（同じ優先度の中では新しいもの）
+    let entries: Vec<PathBuf> = std::fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.is_file())
+        .collect();
+    for suffix in suffixes {
+        let newest = entries
+            .iter()
+            .filter(|p| p.to_string_lossy().ends_with(suffix))
+            .max_by_key(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+        if let Some(p) = newest {
+            return Some(p.clone());
+        }
+    }
+    None
+}
+
+// (タイミングレポート, リソースレポート)
+pub fn find_reports(pnr_dir: &Path) -> (Option<PathBuf>, Option<PathBuf>) {
+    (
+        newest_matching(pnr_dir, &[".tr", ".tr.html", "_tr.html"]),
+        newest_matching(pnr_dir, &[".rpt.txt", ".rpt.html", "_rpt.html"]),
+    )
+}
+
+// project_root 直下または 1 階層下の impl/<sub> から、最も新しい <suffix> のファイル
+fn find_latest_in_impl(project_root: &Path, sub: &str, suffix: &str) -> Option<PathBuf> {
+    let mut dirs = vec![project_root.join("impl").join(sub)];
+    if let Ok(entries) = std::fs::read_dir(project_root) {
+        for e in entries.flatten() {
+            let p = e.path().join("impl").join(sub);
+            if p.is_dir() {
+                dirs.push(p);
+            }
+        }
+    }
+    dirs.iter()
+        .filter_map(|d| newest_matching(d, &[suffix]))
+        .max_by_key(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok())
+}
+
+pub fn find_latest_fs(project_root: &Path) -> Option<PathBuf> {
+    find_latest_in_impl(project_root, "pnr", ".fs")
+}
+
+// 合成後のネットリスト（impl/gwsynthesis/*.vg）
+pub fn find_latest_netlist(project_root: &Path) -> Option<PathBuf> {
+    find_latest_in_impl(project_root, "gwsynthesis", ".vg")
+}
+
+// pnr ディレクトリのタイミング / リソースレポートを読む（なければ None / 空）
+pub fn load_reports(pnr_dir: &Path) -> (Option<TimingSummary>, Vec<ResourceUsage>) {
+    let (timing_report, resource_report) = find_reports(pnr_dir);
+    let timing = timing_report.and_then(|path| {
+        let bytes = std::fs::read(&path).ok()?;
+        Some(parse_timing(&path, &String::from_utf8_lossy(&bytes)))
+    });
+    let utilization = resource_report
+        .and_then(|path| std::fs::read(path).ok())
+        .map(|b| parse_utilization(&String::from_utf8_lossy(&b)))
+        .unwrap_or_default();
+    (timing, utilization)
+}
@@ -0,0 +1,755 @@
+// タイミング制約（.sdc）の解析と編集
+//
+//   create_clock -name clk -period 20 -waveform {0 10} [get_ports {clk}]
+//   create_generated_clock -name clk2 -source [get_ports {clk}] -divide_by 2 [get_pins {pll/CLKOUT}]
+//   set_input_delay -clock clk 2.0 [get_ports {din[*]}]
+//   set_false_path -from [get_clocks {clk}] -to [get_clocks {clk2}]
+//
+// Tcl の単語分割（{...}、"..."、[...]、行末の \ による継続、# コメント）だけを行い、
+// Gowin が対応する SDC コマンドを構造化する。各コマンドはファイル中の範囲を持つので、
+// 編集はその範囲の置き換え・削除・末尾への追加で行い、他の行（コメントなど）は触らない。
+
+use std::collections::BTreeMap;
+
+use anyhow::{Result, anyhow};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::hdl;
+
+// (行, メッセージ)
+type LexResult<T> = std::result::Result<T, (usize, &'static str)>;
+
+// Gowin のタイミング制約エディタが扱うコマンド
+pub const COMMANDS: &[&str] = &[
+    "create_clock",
+    "create_generated_clock",
+    "set_clock_latency",
+    "set_clock_uncertainty",
+    "set_clock_groups",
+    "set_input_delay",
+    "set_output_delay",
+    "set_max_delay",
+    "set_min_delay",
+    "set_false_path",
+    "set_multicycle_path",
+    "set_max_fanout",
+    "report_timing",
+    "report_high_fanout_nets",
+    "report_route_congestion",
+    "report_min_pulse_width",
+];
+
+// 値を取らないオプション（-source は set_clock_latency では値を取らない）
+const BOOL_FLAGS: &[&str] = &[
+    "-add",
+    "-add_delay",
+    "-asynchronous",
+    "-clock_fall",
+    "-combinational",
+    "-end",
+    "-exclusive",
+    "-fall",
+    "-hold",
+    "-invert",
+    "-logically_exclusive",
+    "-max",
+    "-min",
+    "-network_latency_included",
+    "-physically_exclusive",
+    "-rise",
+    "-setup",
+    "-source_latency_included",
+    "-start",
+    "-setup_hold",
+    "-source",
+];
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(untagged)]
+pub enum Word {
+    Text(String),
+    // [get_ports {a b}] のようなコマンド置換
+    Query {
+        query: String,
+        patterns: Vec<String>,
+    },
+}
+
+impl Word {
+    fn text(&self) -> Option<&str> {
+        match self {
+            Word::Text(t) => Some(t),
+            Word::Query { .. } => None,
+        }
+    }
+
+    // 参照しているオブジェクト名（Text なら空白区切りのリスト）
+    pub fn names(&self) -> Vec<String> {
+        match self {
+            Word::Text(t) => t.split_whitespace().map(str::to_string).collect(),
+            Word::Query { patterns, .. } => patterns.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Command {
+    pub index: usize,
+    // 1 始まり
+    pub line: usize,
+    pub name: String,
+    // オプション名（"-" 付き）→ 値（値を取らないものは None）
+    pub options: Vec<(String, Option<Word>)>,
+    pub positional: Vec<Word>,
+    pub text: String,
+    #[serde(skip)]
+    pub span: (usize, usize),
+}
+
+impl Command {
+    pub fn option(&self, name: &str) -> Option<&Word> {
+        self.options
+            .iter()
+            .find(|(k, _)| k == name)
+            .and_then(|(_, v)| v.as_ref())
+    }
+
+    pub fn has_flag(&self, name: &str) -> bool {
+        self.options.iter().any(|(k, _)| k == name)
+    }
+
+    fn number(&self, name: &str) -> Option<f64> {
+        self.option(name)?.text()?.trim().parse().ok()
+    }
+
+    // 参照しているオブジェクト（get_ports / get_nets / get_pins / get_cells / get_clocks, パターン）
+    pub fn queries(&self) -> Vec<(&str, &str)> {
+        self.options
+            .iter()
+            .filter_map(|(_, v)| v.as_ref())
+            .chain(&self.positional)
+            .filter_map(|w| match w {
+                Word::Query { query, patterns } => {
+                    Some(patterns.iter().map(move |p| (query.as_str(), p.as_str())))
+                }
+                Word::Text(_) => None,
+            })
+            .flatten()
+            .collect()
+    }
+
+    // 参照しているポート（get_ports の引数）
+    pub fn port_refs(&self) -> Vec<String> {
+        self.queries()
+            .into_iter()
+            .filter(|(q, _)| *q == "get_ports")
+            .map(|(_, p)| p.to_string())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Clock {
+    pub index: usize,
+    pub line: usize,
+    pub name: String,
+    pub generated: bool,
+    pub period_ns: Option<f64>,
+    pub frequency_mhz: Option<f64>,
+    pub waveform: Option<Vec<f64>>,
+    pub targets: Vec<String>,
+    pub source: Option<String>,
+    pub master_clock: Option<String>,
+    pub multiply_by: Option<f64>,
+    pub divide_by: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IoDelay {
+    pub index: usize,
+    pub line: usize,
+    // input / output
+    pub direction: String,
+    pub clock: Option<String>,
+    pub delay_ns: Option<f64>,
+    pub min: bool,
+    pub max: bool,
+    pub ports: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Exception {
+    pub index: usize,
+    pub line: usize,
+    // false_path / max_delay / min_delay / multicycle_path / clock_groups
+    pub kind: String,
+    pub from: Vec<String>,
+    pub to: Vec<String>,
+    pub through: Vec<String>,
+    pub value: Option<f64>,
+    // set_clock_groups の -group（グループごと）
+    pub groups: Vec<Vec<String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Issue {
+    // error / warning
+    pub level: String,
+    pub line: Option<usize>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct SdcFile {
+    pub commands: Vec<Command>,
+    pub errors: Vec<Issue>,
+}
+
+#[derive(Debug, Clone)]
+enum Tok {
+    Word(Word),
+    End,
+}
+
+struct Lexer<'a> {
+    src: &'a str,
+    pos: usize,
+}
+
+impl<'a> Lexer<'a> {
+    fn peek(&self) -> Option<char> {
+        self.src[self.pos..].chars().next()
+    }
+
+    fn bump(&mut self) -> Option<char> {
+        let c = self.peek()?;
+        self.pos += c.len_utf8();
+        Some(c)
+    }
+
+    fn line_of(&self, pos: usize) -> usize {
+        self.src[..pos].matches('\n').count() + 1
+    }
+
+    // 単語間の空白（\ + 改行は空白扱い）。改行・; は End
+    fn skip_blank(&mut self) {
+        loop {
+            match self.peek() {
+                Some(' ' | '\t' | '\r') => {
+                    self.bump();
+                }
+                Some('\\') if self.src[self.pos + 1..].starts_with('\n') => {
+                    self.pos += 2;
+                }
+                Some('\\') if self.src[self.pos + 1..].starts_with("\r\n") => {
+                    self.pos += 3;
+                }
+                _ => return,
+            }
+        }
+    }
+
+    fn braced(&mut self) -> LexResult<String> {
+        let start = self.pos;
+        self.bump();
+        let mut depth = 1;
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    self.bump();
+                }
+                '{' => depth += 1,
+                '}' => {
+                    depth -= 1;
+                    if depth == 0 {
+                        return Ok(self.src[start + 1..self.pos - 1].to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        Err((self.line_of(start), "{ が閉じていません"))
+    }
+
+    fn quoted(&mut self) -> LexResult<String> {
+        let start = self.pos;
+        self.bump();
+        let mut out = String::new();
+        while let Some(c) = self.bump() {
+            match c {
+                '\\' => {
+                    if let Some(n) = self.bump() {
+                        out.push(n);
+                    }
+                }
+                '"' => return Ok(out),
+                c => out.push(c),
+            }
+        }
+        Err((self.line_of(start), "\" が閉じていません"))
+    }
+
+    fn bracketed(&mut self) -> LexResult<Word> {
+        let start = self.pos;
+        self.bump();
+        let mut inner = Vec::new();
+        loop {
+            self.skip_blank();
+            while matches!(self.peek(), Some('\n' | ';')) {
+                self.bump();
+                self.skip_blank();
+            }
+            match self.peek() {
+                None => {
+                    return Err((self.line_of(start), "[ が閉じていません"));
+                }
+                Some(']') => {
+                    self.bump();
+                    break;
+                }
+                _ => inner.push(self.word()?),
+            }
+        }
+        let mut words = inner.into_iter();
+        let query = match words.next() {
+            Some(Word::Text(t)) => t,
+            _ => return Err((self.line_of(start), "[] の中が空です")),
+        };
+        let patterns = words
+            .filter(|w| !matches!(w.text(), Some(t) if t.starts_with('-')))
+            .flat_map(|w| w.names())
+            .collect();
+        Ok(Word::Query { query, patterns })
+    }
+
+    fn word(&mut self) -> LexResult<Word> {
+        match self.peek() {
+            Some('{') => Ok(Word::Text(self.braced()?)),
+            Some('"') => Ok(Word::Text(self.quoted()?)),
+            Some('[') => self.bracketed(),
+            _ => {
+                let start = self.pos;
+                while let Some(c) = self.peek() {
+                    if c.is_whitespace() || matches!(c, ';' | '[' | ']') {
+                        break;
+                    }
+                    if c == '\\' {
+                        self.bump();
+                    }
+                    self.bump();
+                }
+                Ok(Word::Text(self.src[start..self.pos].to_string()))
+            }
+        }
+    }
+
+    fn next(&mut self) -> LexResult<Option<(usize, Tok)>> {
+        self.skip_blank();
+        let start = self.pos;
+        match self.peek() {
+            None => Ok(None),
+            Some('\n' | ';') => {
+                self.bump();
+                Ok(Some((start, Tok::End)))
+            }
+            Some(']') => Err((self.line_of(start), "対応する [ のない ] があります")),
+            _ => Ok(Some((start, Tok::Word(self.word()?)))),
+        }
+    }
+}
+
+fn is_option(word: &Word) -> bool {
+    matches!(word.text(), Some(t) if t.starts_with('-')
+        && t[1..].chars().next().is_some_and(|c| c.is_ascii_alphabetic()))
+}
+
+fn build_command(
+    index: usize,
+    line: usize,
+    words: Vec<Word>,
+    text: &str,
+    span: (usize, usize),
+) -> Command {
+    let mut iter = words.into_iter().peekable();
+    let name = iter
+        .next()
+        .and_then(|w| w.text().map(str::to_string))
+        .unwrap_or_default();
+    let mut options = Vec::new();
+    let mut positional = Vec::new();
+    while let Some(w) = iter.next() {
+        if is_option(&w) {
+            let flag = w.text().unwrap_or_default().to_string();
+            // -source は create_generated_clock では値を取る
+            let takes_value = !BOOL_FLAGS.contains(&flag.as_str())
+                || (flag == "-source" && name == "create_generated_clock");
+            let value = if takes_value && iter.peek().is_some_and(|n| !is_option(n)) {
+                iter.next()
+            } else {
+                None
+            };
+            options.push((flag, value));
+        } else {
+            positional.push(w);
+        }
+    }
+    Command {
+        index,
+        line,
+        name,
+        options,
+        positional,
+        text: text.to_string(),
+        span,
+    }
+}
+
+pub fn parse(src: &str) -> SdcFile {
+    let mut lexer = Lexer { src, pos: 0 };
+    let mut commands: Vec<Command> = Vec::new();
+    let mut errors = Vec::new();
+    let mut words: Vec<Word> = Vec::new();
+    let mut start = 0;
+
+    let mut finish = |words: &mut Vec<Word>, start: usize, end: usize, line: usize| {
+        let text = src[start..end].trim_end();
+        let cmd = build_command(
+            commands.len(),
+            line,
+            std::mem::take(words),
+            text,
+            (start, start + text.len()),
+        );
+        commands.push(cmd);
+    };
+
+    loop {
+        // コマンドの先頭の # はコメント
+        if words.is_empty() {
+            lexer.skip_blank();
+            if lexer.peek() == Some('#') {
+                lexer.pos = src[lexer.pos..]
+                    .find('\n')
+                    .map(|i| lexer.pos + i)
+                    .unwrap_or(src.len());
+                continue;
+            }
+        }
+        match lexer.next() {
+            Ok(Some((pos, Tok::Word(w)))) => {
+                if words.is_empty() {
+                    start = pos;
+                }
+                words.push(w);
+            }
+            Ok(Some((pos, Tok::End))) => {
+                if !words.is_empty() {
+                    finish(&mut words, start, pos, lexer.line_of(start));
+                }
+            }
+            Ok(None) => {
+                if !words.is_empty() {
+                    finish(&mut words, start, src.len(), lexer.line_of(start));
+                }
+                break;
+            }
+            Err((line, message)) => {
+                // 閉じていない括弧があると以降の区切りが分からないので打ち切る
+                errors.push(Issue {
+                    level: "error".into(),
+                    line: Some(line),
+                    message: message.into(),
+                });
+                break;
+            }
+        }
+    }
+
+    SdcFile { commands, errors }
+}
+
+fn names_of(cmd: &Command, option: &str) -> Vec<String> {
+    cmd.options
+        .iter()
+        .filter(|(k, _)| k == option)
+        .filter_map(|(_, v)| v.as_ref())
+        .flat_map(Word::names)
+        .collect()
+}
+
+fn first_name(word: Option<&Word>) -> Option<String> {
+    word.and_then(|w| w.names().into_iter().next())
+}
+
+pub fn clocks(file: &SdcFile) -> Vec<Clock> {
+    file.commands
+        .iter()
+        .filter(|c| c.name == "create_clock" || c.name == "create_generated_clock")
+        .map(|c| {
+            let generated = c.name == "create_generated_clock";
+            let targets: Vec<String> = c.positional.iter().flat_map(Word::names).collect();
+            let period_ns = c.number("-period");
+            Clock {
+                index: c.index,
+                line: c.line,
+                // -name がなければ最初のターゲット名がクロック名になる
+                name: first_name(c.option("-name"))
+                    .or_else(|| targets.first().cloned())
+                    .unwrap_or_default(),
+                generated,
+                period_ns,
+                frequency_mhz: period_ns
+                    .filter(|p| *p > 0.0)
+                    .map(|p| (1000.0 / p * 1000.0).round() / 1000.0),
+                waveform: c
+                    .option("-waveform")
+                    .and_then(|w| w.names().iter().map(|v| v.parse().ok()).collect()),
+                targets,
+                source: first_name(c.option("-source")),
+                master_clock: first_name(c.option("-master_clock")),
+                multiply_by: c.number("-multiply_by"),
+                divide_by: c.number("-divide_by"),
+            }
+        })
+        .collect()
+}
+
+pub fn io_delays(file: &SdcFile) -> Vec<IoDelay> {
+    file.commands
+        .iter()
+        .filter(|c| c.name == "set_input_delay" || c.name == "set_output_delay")
+        .map(|c| IoDelay {
+            index: c.index,
+            line: c.line,
+            direction: if c.name == "set_input_delay" {
+                "input"
+            } else {
+                "output"
+            }
+            .into(),
+            clock: first_name(c.option("-clock")),
+            delay_ns: c
+                .positional
+                .iter()
+                .find_map(|w| w.text()?.trim().parse().ok()),
+            min: c.has_flag("-min"),
+            max: c.has_flag("-max"),
+            ports: c
+                .positional
+                .iter()
+                .filter(|w| matches!(w, Word::Query { .. }))
+                .flat_map(Word::names)
+                .collect(),
+        })
+        .collect()
+}
+
+pub fn exceptions(file: &SdcFile) -> Vec<Exception> {
+    file.commands
+        .iter()
+        .filter_map(|c| {
+            let kind = match c.name.as_str() {
+                "set_false_path" => "false_path",
+                "set_max_delay" => "max_delay",
+                "set_min_delay" => "min_delay",
+                "set_multicycle_path" => "multicycle_path",
+                "set_clock_groups" => "clock_groups",
+                _ => return None,
+            };
+            Some(Exception {
+                index: c.index,
+                line: c.line,
+                kind: kind.into(),
+                from: names_of(c, "-from"),
+                to: names_of(c, "-to"),
+                through: names_of(c, "-through"),
+                value: c
+                    .positional
+                    .iter()
+                    .find_map(|w| w.text()?.trim().parse().ok()),
+                groups: c
+                    .options
+                    .iter()
+                    .filter(|(k, _)| k == "-group")
+                    .filter_map(|(_, v)| v.as_ref().map(Word::names))
+                    .collect(),
+            })
+        })
+        .collect()
+}
+
+// コマンド名・必須オプション・クロック名の重複・参照先（ポート / クロック）を確認する
+pub fn validate(file: &SdcFile, ports: Option<&[hdl::Port]>) -> Vec<Issue> {
+    let mut issues = file.errors.clone();
+    let clocks = clocks(file);
+    let issue = |level: &str, line: usize, message: String| Issue {
+        level: level.into(),
+        line: Some(line),
+        message,
+    };
+
+    for c in &file.commands {
+        if !COMMANDS.contains(&c.name.as_str()) {
+            issues.push(issue(
+                "error",
+                c.line,
+                format!("{} は Gowin が対応する SDC コマンドではありません", c.name),
+            ));
+            continue;
+        }
+        if let Some(ports) = ports {
+            for r in c.port_refs() {
+                if !hdl::port_exists(ports, &r) {
+                    issues.push(issue(
+                        "error",
+                        c.line,
+                        format!("ポート {r} はトップモジュールにありません"),
+                    ));
+                }
+            }
+        }
+        for (k, _) in c.options.iter().filter(|(_, v)| v.is_none()) {
+            let boolean = BOOL_FLAGS.contains(&k.as_str())
+                && !(k == "-source" && c.name == "create_generated_clock");
+            if !boolean {
+                issues.push(issue(
+                    "error",
+                    c.line,
+                    format!("{} {k} に値がありません", c.name),
+                ));
+            }
+        }
+        // get_clocks / -clock で参照するクロックが定義されているか
+        let mut clock_refs: Vec<&String> = Vec::new();
+        for (k, w) in c
+            .options
+            .iter()
+            .filter_map(|(k, v)| Some((k.as_str(), v.as_ref()?)))
+            .chain(c.positional.iter().map(|w| ("", w)))
+        {
+            match w {
+                Word::Query { query, patterns } if query == "get_clocks" => {
+                    clock_refs.extend(patterns)
+                }
+                Word::Text(name) if k == "-clock" => clock_refs.push(name),
+                _ => {}
+            }
+        }
+        for p in clock_refs {
+            if !clocks.iter().any(|k| hdl::glob_match(p, &k.name)) {
+                issues.push(issue(
+                    "error",
+                    c.line,
+                    format!("クロック {p} は定義されていません"),
+                ));
+            }
+        }
+    }
+
+    let mut seen: BTreeMap<&str, usize> = BTreeMap::new();
+    for k in &clocks {
+        if k.name.is_empty() {
+            issues.push(issue(
+                "error",
+                k.line,
+                "クロック名もターゲットもありません".into(),
+            ));
+        }
+        if let Some(first) = seen.insert(&k.name, k.line)
+            && !k.name.is_empty()
+        {
+            issues.push(issue(
+                "error",
+                k.line,
+                format!("クロック {} は {first} 行目でも定義されています", k.name),
+            ));
+        }
+        if k.generated {
+            if k.source.is_none() {
+                issues.push(issue(
+                    "error",
+                    k.line,
+                    format!("create_generated_clock {} に -source がありません", k.name),
+                ));
+            }
+        } else {
+            match k.period_ns {
+                None => issues.push(issue(
+                    "error",
+                    k.line,
+                    format!("create_clock {} に -period がありません", k.name),
+                )),
+                Some(p) if p <= 0.0 => issues.push(issue(
+                    "error",
+                    k.line,
+                    format!("create_clock {} の -period は正の値にしてください", k.name),
+                )),
+                Some(p) => {
+                    if let Some(w) = k.waveform.as_ref()
+                        && (w.len() != 2 || w[0] >= w[1] || w[1] > p)
+                    {
+                        issues.push(issue(
+                            "warning",
+                            k.line,
+                            format!("create_clock {} の -waveform が周期と合いません", k.name),
+                        ));
+                    }
+                }
+            }
+            if k.targets.is_empty() && file.commands[k.index].option("-name").is_none() {
+                issues.push(issue(
+                    "error",
+                    k.line,
+                    "create_clock にターゲットも -name もありません".into(),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+// 1 つのコマンドとして解析できるか（編集時の入力確認）
+pub fn parse_single(command: &str) -> Result<Command> {
+    let file = parse(command);
+    if let Some(e) = file.errors.first() {
+        return Err(anyhow!("{}", e.message));
+    }
+    match file.commands.as_slice() {
+        [only] => Ok(only.clone()),
+        [] => Err(anyhow!("コマンドがありません")),
+        _ => Err(anyhow!(
+            "1 つのコマンドだけを指定してください（{} 個あります）",
+            file.commands.len()
+        )),
+    }
+}
+
+// 既存コマンドの置き換え / 削除 / 末尾への追加をしたテキスト
+pub fn replace(src: &str, cmd: &Command, with: Option<&str>) -> String {
+    let (start, mut end) = cmd.span;
+    let mut out = src[..start].to_string();
+    match with {
+        Some(text) => out.push_str(text.trim()),
+        None => {
+            // 行ごと消す
+            if src[end..].starts_with("\r\n") {
+                end += 2;
+            } else if src[end..].starts_with('\n') {
+                end += 1;
+            }
+        }
+    }
+    out.push_str(&src[end..]);
+    out
+}
+
+pub fn append(src: &str, text: &str) -> String {
+    let mut out = src.to_string();
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(text.trim());
+    out.push('\n');
+    out
+}
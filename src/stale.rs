@@ -0,0 +1,173 @@
+// 古いビットストリームの検出
+//
+// ソース（.gowin-mcp/config.toml の sources、なければ .gprj のファイル一覧）を
+// - .fs の mtime と比べ、新しいものを newer_than_fs に
+// - 最後に成功したビルド時のハッシュ（.gowin-mcp/state/last_build.json）と比べ、
+//   変わったものを changed_since_build に
+// 挙げる。
+
+use std::{
+    collections::BTreeMap,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::{config, gprj};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BuildRecord {
+    pub finished_at_ms: u64,
+    pub tool: String,
+    // project_root からの相対パス → sha256
+    pub sources: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StaleReport {
+    // config / gprj / none
+    pub source_origin: String,
+    pub sources_checked: usize,
+    pub fs_mtime_ms: u64,
+    pub last_build_ms: Option<u64>,
+    pub newer_than_fs: Vec<String>,
+    pub changed_since_build: Vec<String>,
+    pub stale: bool,
+}
+
+fn record_path(project_root: &Path) -> PathBuf {
+    project_root
+        .join(".gowin-mcp")
+        .join("state")
+        .join("last_build.json")
+}
+
+pub fn hash_file(path: &Path) -> Result<String> {
+    let bytes = std::fs::read(path).with_context(|| format!("read({})", path.display()))?;
+    Ok(Sha256::digest(&bytes)
+        .iter()
+        .map(|b| format!("{b:02x}"))
+        .collect())
+}
+
+pub fn mtime_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).and_then(|m| m.modified()).ok()?;
+    modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_millis() as u64)
+}
+
+fn relative(project_root: &Path, path: &Path) -> String {
+    path.strip_prefix(project_root)
+        .unwrap_or(path)
+        .display()
+        .to_string()
+}
+
+pub fn walk(dir: &Path, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut paths: Vec<PathBuf> = entries.flatten().map(|e| e.path()).collect();
+    paths.sort();
+    for p in paths {
+        let hidden = p
+            .file_name()
+            .is_some_and(|n| n.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if p.is_dir() {
+            walk(&p, out);
+        } else {
+            out.push(p);
+        }
+    }
+}
+
+// (由来, ソースファイル一覧)
+pub fn project_sources(project_root: &Path) -> Result<(String, Vec<PathBuf>)> {
+    let cfg = config::load(project_root)?;
+    if let Some(sources) = cfg.sources {
+        let mut files = Vec::new();
+        for s in sources {
+            let p = crate::resolve_under(project_root, &s);
+            if p.is_dir() {
+                walk(&p, &mut files);
+            } else {
+                files.push(p);
+            }
+        }
+        return Ok(("config".into(), files));
+    }
+    match gprj::find(project_root) {
+        Some(gprj_path) => {
+            let mut files = gprj::source_files(&gprj_path)?;
+            files.push(gprj_path);
+            Ok(("gprj".into(), files))
+        }
+        None => Ok(("none".into(), Vec::new())),
+    }
+}
+
+// ビルド成功時に呼び、その時点のソースのハッシュを残す
+pub fn record_build(project_root: &Path, tool: &str, finished_at_ms: u64) -> Result<()> {
+    let (_, files) = project_sources(project_root)?;
+    let sources = files
+        .iter()
+        .filter_map(|p| Some((relative(project_root, p), hash_file(p).ok()?)))
+        .collect();
+    let record = BuildRecord {
+        finished_at_ms,
+        tool: tool.to_string(),
+        sources,
+    };
+    let path = record_path(project_root);
+    if let Some(dir) = path.parent() {
+        std::fs::create_dir_all(dir).with_context(|| format!("mkdir -p {}", dir.display()))?;
+    }
+    std::fs::write(&path, serde_json::to_vec_pretty(&record)?)
+        .with_context(|| format!("write({})", path.display()))
+}
+
+pub fn last_build(project_root: &Path) -> Option<BuildRecord> {
+    let bytes = std::fs::read(record_path(project_root)).ok()?;
+    serde_json::from_slice(&bytes).ok()
+}
+
+pub fn check(project_root: &Path, fs_path: &Path) -> Result<StaleReport> {
+    let fs_mtime_ms =
+        mtime_ms(fs_path).with_context(|| format!("metadata({})", fs_path.display()))?;
+    let (source_origin, files) = project_sources(project_root)?;
+    let record = last_build(project_root);
+
+    let mut newer_than_fs = Vec::new();
+    let mut changed_since_build = Vec::new();
+    for p in &files {
+        let rel = relative(project_root, p);
+        if mtime_ms(p).is_some_and(|m| m > fs_mtime_ms) {
+            newer_than_fs.push(rel.clone());
+        }
+        if let Some(r) = record.as_ref() {
+            let now = hash_file(p).ok();
+            if now.as_ref() != r.sources.get(&rel) {
+                changed_since_build.push(rel);
+            }
+        }
+    }
+
+    Ok(StaleReport {
+        source_origin,
+        sources_checked: files.len(),
+        fs_mtime_ms,
+        last_build_ms: record.map(|r| r.finished_at_ms),
+        stale: !newer_than_fs.is_empty() || !changed_since_build.is_empty(),
+        newer_than_fs,
+        changed_since_build,
+    })
+}
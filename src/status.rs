@@ -0,0 +1,134 @@
+// ステータスレジスタ / USERCODE の解析
+//
+// ビット割り当ては Gowin の JTAG ステータスレジスタ（GW1N/GW2A/GW5A 共通部分）。
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+const STATUS_BITS: &[(u32, &str)] = &[
+    (0, "CRC_ERROR"),
+    (1, "BAD_COMMAND"),
+    (2, "ID_VERIFY_FAILED"),
+    (3, "TIMEOUT"),
+    (4, "AUTO_BOOT_2ND_FAIL"),
+    (5, "MEMORY_ERASE"),
+    (6, "PREAMBLE"),
+    (7, "SYSTEM_EDIT_MODE"),
+    (8, "PRG_SPIFLASH_DIRECT"),
+    (9, "AUTO_BOOT_1ST_FAILED"),
+    (10, "NON_JTAG_CNF_ACTIVE"),
+    (11, "BYPASS"),
+    (12, "I2C_SRAM_F"),
+    (13, "DONE_FINAL"),
+    (14, "SECURITY_FINAL"),
+    (15, "ENCRYPTED_FORMAT"),
+    (16, "KEY_IS_RIGHT"),
+    (17, "SSPI_MODE"),
+    (18, "SER_CRC_DONE"),
+];
+
+const ERROR_FLAGS: &[&str] = &[
+    "CRC_ERROR",
+    "BAD_COMMAND",
+    "ID_VERIFY_FAILED",
+    "TIMEOUT",
+    "AUTO_BOOT_2ND_FAIL",
+    "AUTO_BOOT_1ST_FAILED",
+];
+
+const DONE_BIT: u32 = 13;
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DeviceStatus {
+    pub status_register: Option<String>,
+    pub usercode: Option<String>,
+    // 立っているビットの名前
+    pub flags: Vec<String>,
+    pub done: Option<bool>,
+    pub errors: Vec<String>,
+    // configured / not_configured / error / unknown（ステータスレジスタが読めなかった）
+    pub state: String,
+}
+
+impl DeviceStatus {
+    // DONE が立ち、エラービットが無い
+    pub fn configured(&self) -> bool {
+        self.done == Some(true) && self.errors.is_empty()
+    }
+}
+
+pub fn decode(status: Option<u32>, usercode: Option<u32>) -> DeviceStatus {
+    let flags: Vec<String> = status
+        .map(|v| {
+            STATUS_BITS
+                .iter()
+                .filter(|(bit, _)| v & (1 << bit) != 0)
+                .map(|(_, name)| name.to_string())
+                .collect()
+        })
+        .unwrap_or_default();
+    let errors = flags
+        .iter()
+        .filter(|f| ERROR_FLAGS.contains(&f.as_str()))
+        .cloned()
+        .collect::<Vec<_>>();
+    let done = status.map(|v| v & (1 << DONE_BIT) != 0);
+    let state = match (done, errors.is_empty()) {
+        (None, _) => "unknown",
+        (Some(_), false) => "error",
+        (Some(true), true) => "configured",
+        (Some(false), true) => "not_configured",
+    };
+    DeviceStatus {
+        status_register: status.map(|v| format!("0x{v:08X}")),
+        usercode: usercode.map(|v| format!("0x{v:08X}")),
+        flags,
+        done,
+        errors,
+        state: state.to_string(),
+    }
+}
+
+// "Status Register: 0x0001E020" / "status code = 0x..." の値を拾う。
+// 単なる "status" は IDCODE など別の値の行にも出るので見出しに使わない
+pub fn find_status_register(text: &str) -> Option<u32> {
+    hex_after_key(
+        text,
+        &[
+            "status register",
+            "status reg",
+            "status code",
+            "status word",
+        ],
+    )
+}
+
+// "User Code: 0x00000000" / "USERCODE=0x..."
+pub fn find_usercode(text: &str) -> Option<u32> {
+    hex_after_key(text, &["usercode", "user code"])
+}
+
+fn hex_after_key(text: &str, keys: &[&str]) -> Option<u32> {
+    for key in keys {
+        for line in text.lines() {
+            let low = line.to_ascii_lowercase();
+            let Some(pos) = low.find(key) else {
+                continue;
+            };
+            // 見出しの直後（区切りの ":" / "=" と空白だけを挟む）の値に限る
+            let rest = low[pos + key.len()..].trim_start_matches([' ', '\t', ':', '=']);
+            let Some(hex) = rest.strip_prefix("0x") else {
+                continue;
+            };
+            let digits: String = hex
+                .chars()
+                .take_while(|c| c.is_ascii_hexdigit() || *c == '_')
+                .filter(|c| *c != '_')
+                .collect();
+            if let Ok(v) = u32::from_str_radix(&digits, 16) {
+                return Some(v);
+            }
+        }
+    }
+    None
+}
@@ -0,0 +1,3007 @@
+use rmcp::{
+    RoleClient, ServiceExt,
+    service::RunningService,
+    transport::{ConfigureCommandExt, TokioChildProcess},
+};
+use serde_json::json;
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+fn resolve_server_exe() -> PathBuf {
+    if let Ok(exe) = std::env::var("CARGO_BIN_EXE_gw_synth_flash_mcp") {
+        return PathBuf::from(exe);
+    }
+    if let Ok(exe) = std::env::var("CARGO_BIN_EXE_gw-synth-flash-mcp") {
+        return PathBuf::from(exe);
+    }
+
+    let manifest_dir = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+    let exe_path = manifest_dir
+        .join("target")
+        .join("debug")
+        .join("gw-synth-flash-mcp");
+    if exe_path.exists() {
+        return exe_path;
+    }
+
+    let status = std::process::Command::new("cargo")
+        .args(["build", "--quiet"])
+        .current_dir(&manifest_dir)
+        .status()
+        .expect("failed to run cargo build");
+    assert!(status.success(), "cargo build failed: {status}");
+    assert!(
+        exe_path.exists(),
+        "expected built binary at: {}",
+        exe_path.display()
+    );
+
+    exe_path
+}
+
+// Gowin IDE も実機も不要なツール呼び出し用の一時プロジェクト
+fn temp_project(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!(
+        "gw-synth-flash-mcp-test-{}-{name}",
+        std::process::id()
+    ));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).expect("create temp project");
+    dir
+}
+
+fn bits(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:08b}")).collect()
+}
+
+// GW5A-25 (IDCODE 0x0001281B) 向けの最小限の .fs
+fn write_fake_fs(path: &Path) {
+    let lines = [
+        "//Copyright (C)2014-2024 Gowin Semiconductor Corporation.".to_string(),
+        "//Part Number: GW5A-LV25MG121NC1/I0".to_string(),
+        "//Device: GW5A-25".to_string(),
+        "//Device Version: A".to_string(),
+        "//CRC Check: ON".to_string(),
+        "//Compress: OFF".to_string(),
+        bits(&[0xFF; 16]),
+        bits(&[0xA5, 0xC3]),
+        bits(&[0x06, 0x00, 0x00, 0x00, 0x00, 0x01, 0x28, 0x1B]),
+        bits(&[0x3B, 0x80, 0x00, 0x02]),
+        bits(&[0x12, 0x34, 0x56, 0x78, 0x9A, 0xBC, 0xDE, 0xF0]),
+        bits(&[0x0F, 0x1E, 0x2D, 0x3C, 0x4B, 0x5A, 0x69, 0x78]),
+    ];
+    std::fs::write(path, lines.join("\n")).expect("write fake .fs");
+}
+
+// programmer_cli の代わりに sh スクリプトを置いた偽の GowinIDE.app。
+// 呼び出し引数は <app>/calls.log に追記される
+fn fake_gowin_app(root: &Path, script: &str) -> PathBuf {
+    use std::os::unix::fs::PermissionsExt;
+
+    let app = root.join("FakeGowinIDE.app");
+    let bin = app.join("Contents/Resources/Gowin_EDA/Programmer/bin");
+    std::fs::create_dir_all(&bin).expect("create fake programmer dir");
+    let cli = bin.join("programmer_cli");
+    let body = format!(
+        "#!/bin/sh\necho \"$@\" >> \"{}\"\n{script}\n",
+        app.join("calls.log").display()
+    );
+    std::fs::write(&cli, body).expect("write fake programmer_cli");
+    std::fs::set_permissions(&cli, std::fs::Permissions::from_mode(0o755))
+        .expect("chmod fake programmer_cli");
+    app
+}
+
+fn fake_calls(app: &Path) -> Vec<String> {
+    std::fs::read_to_string(app.join("calls.log"))
+        .unwrap_or_default()
+        .lines()
+        .map(str::to_string)
+        .collect()
+}
+
+const TWO_BOARDS: &str = r#"case "$1" in
+--help)
+    echo "Usage: programmer_cli [options]"
+    echo "  --list-cables            List connected cables"
+    echo "  -r, --run <index>        Operation index"
+    echo "  --cable-index <n>        Cable index"
+    echo "  --fsFile <file>          Bitstream file"
+    echo "Operations:"
+    echo "  2: SRAM Program"
+    echo "  14: SRAM Program and Verify"
+    ;;
+--list-cables)
+    echo "[0] Gowin USB Cable(FT2CH) SN: AAA111 Location: 1-1"
+    echo "    IDCODE: 0x0001281B"
+    echo "[1] Gowin USB Cable(FT2CH) SN: BBB222 Location: 1-2"
+    echo "    IDCODE: 0x0001281B"
+    ;;
+esac
+exit 0"#;
+
+// ケーブルロックはテストごとに <root>/locks に分ける（並列実行でぶつからないように）
+async fn start_server(root: &Path) -> RunningService<RoleClient, ()> {
+    let exe = resolve_server_exe();
+    let lock_dir = root.join("locks");
+    ().serve(
+        TokioChildProcess::new(Command::new(exe).configure(|cmd| {
+            cmd.env("GOWIN_MCP_LOCK_DIR", &lock_dir)
+                .env("GOWIN_MCP_CACHE_DIR", root.join("cache"));
+        }))
+        .expect("spawn mcp server"),
+    )
+    .await
+    .expect("connect")
+}
+
+async fn call(
+    service: &RunningService<RoleClient, ()>,
+    name: &'static str,
+    args: serde_json::Value,
+) -> Result<serde_json::Value, String> {
+    service
+        .call_tool(rmcp::model::CallToolRequestParams {
+            meta: None,
+            task: None,
+            name: name.into(),
+            arguments: Some(args.as_object().expect("arguments must be object").clone()),
+        })
+        .await
+        .map_err(|e| e.to_string())
+        .and_then(|r| r.into_typed().map_err(|e| e.to_string()))
+}
+
+#[tokio::test]
+async fn program_fs_refuses_device_mismatch() {
+    let root = temp_project("mismatch");
+    write_fake_fs(&root.join("top.fs"));
+
+    let service = start_server(&root).await;
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+            "fs_file_path": "top.fs",
+            "device": "GW1NR-9C",
+        }),
+    )
+    .await
+    .expect_err("device mismatch must be refused");
+    assert!(err.contains("force"), "unexpected error: {err}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn convert_bitstream_bin_and_hex() {
+    let root = temp_project("convert");
+    write_fake_fs(&root.join("top.fs"));
+
+    let service = start_server(&root).await;
+
+    let bin = call(
+        &service,
+        "gowin.convert_bitstream",
+        json!({
+            "project_root": root.display().to_string(),
+            "fs_file_path": "top.fs",
+            "offset": "0x10",
+            "pad_to": "64",
+        }),
+    )
+    .await
+    .expect("convert to bin");
+    assert_eq!(bin["format"], "bin");
+    assert_eq!(bin["bitstream"]["idcode"], "0x0001281B");
+    assert_eq!(bin["bitstream"]["frame_count"], 2);
+    let bytes = std::fs::read(root.join("top.bin")).expect("read top.bin");
+    assert_eq!(bytes.len(), 64);
+    assert!(bytes[..0x10].iter().all(|b| *b == 0xFF));
+    assert_eq!(&bytes[0x20..0x24], &[0xA5, 0xC3, 0x06, 0x00]);
+
+    let hex = call(
+        &service,
+        "gowin.convert_bitstream",
+        json!({
+            "project_root": root.display().to_string(),
+            "fs_file_path": "top.fs",
+            "output_path": "out/top.mcs",
+            "offset": "0x100000",
+        }),
+    )
+    .await
+    .expect("convert to mcs");
+    assert_eq!(hex["format"], "mcs");
+    let text = std::fs::read_to_string(root.join("out/top.mcs")).expect("read top.mcs");
+    let lines: Vec<&str> = text.lines().collect();
+    assert_eq!(lines[0], ":020000040010EA");
+    assert!(lines[1].starts_with(":10000000FFFF"));
+    assert_eq!(*lines.last().unwrap(), ":00000001FF");
+
+    for (offset, pad_to) in [("0xFFFFFFFFFFFFFFFF", None), ("0", Some("4G"))] {
+        let err = call(
+            &service,
+            "gowin.convert_bitstream",
+            json!({
+                "project_root": root.display().to_string(),
+                "fs_file_path": "top.fs",
+                "offset": offset,
+                "pad_to": pad_to,
+            }),
+        )
+        .await
+        .expect_err("image beyond the flash size must be refused");
+        assert!(
+            err.contains("大きすぎ") || err.contains("フラッシュサイズ"),
+            "unexpected error: {err}"
+        );
+    }
+    let err = call(
+        &service,
+        "gowin.convert_bitstream",
+        json!({
+            "project_root": root.display().to_string(),
+            "fs_file_path": "top.fs",
+            "pad_to": "64K",
+            "flash_size": "32K",
+        }),
+    )
+    .await
+    .expect_err("pad_to beyond flash_size must be refused");
+    assert!(err.contains("フラッシュサイズ"), "unexpected error: {err}");
+
+    // 暗号鍵の行は 0 以外を含む 16 進のときだけ暗号化とみなす
+    let plain = std::fs::read_to_string(root.join("top.fs")).expect("read top.fs");
+    for (key, encrypted) in [
+        ("off", json!(false)),
+        ("None", json!(false)),
+        ("00000000000000000000000000000000", json!(false)),
+        ("0x0123456789ABCDEF0123456789ABCDEF", json!(true)),
+        ("default", json!(null)),
+    ] {
+        let text = plain.replace(
+            "//Compress: OFF",
+            &format!("//Compress: OFF\n//Encryption Key: {key}"),
+        );
+        std::fs::write(root.join("key.fs"), text).expect("write key.fs");
+        let res = call(
+            &service,
+            "gowin.convert_bitstream",
+            json!({
+                "project_root": root.display().to_string(),
+                "fs_file_path": "key.fs",
+            }),
+        )
+        .await
+        .expect("convert with an encryption key line");
+        assert_eq!(res["bitstream"]["encrypted"], encrypted, "key {key}: {res}");
+    }
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn build_flash_image_layout() {
+    let root = temp_project("layout");
+    write_fake_fs(&root.join("golden.fs"));
+    write_fake_fs(&root.join("update.fs"));
+    std::fs::write(root.join("user.dat"), [0xDE, 0xAD, 0xBE, 0xEF]).expect("write user.dat");
+    std::fs::write(
+        root.join("flash.toml"),
+        r#"
+flash_size = "1K"
+alignment = 0x100
+device = "GW5A-25A"
+
+[[partitions]]
+name = "golden"
+file = "golden.fs"
+offset = 0
+size = "0x100"
+
+[[partitions]]
+name = "update"
+file = "update.fs"
+offset = "0x100"
+size = "0x100"
+
+[[partitions]]
+name = "user"
+file = "user.dat"
+offset = "0x200"
+"#,
+    )
+    .expect("write layout");
+    std::fs::write(
+        root.join("overlap.toml"),
+        r#"
+flash_size = "1K"
+
+[[partitions]]
+name = "golden"
+file = "golden.fs"
+offset = 0
+
+[[partitions]]
+name = "update"
+file = "update.fs"
+offset = 8
+"#,
+    )
+    .expect("write overlapping layout");
+    std::fs::write(
+        root.join("flash.yaml"),
+        r#"
+flash_size: 1K
+alignment: 0x100
+device: GW5A-25A
+partitions:
+  - { name: golden, file: golden.fs, offset: 0, size: 0x100 }
+  - { name: update, file: update.fs, offset: 0x100, size: 0x100 }
+  - { name: user, file: user.dat, offset: 0x200 }
+"#,
+    )
+    .expect("write yaml layout");
+    std::fs::write(
+        root.join("huge.toml"),
+        r#"
+flash_size = "1K"
+
+[[partitions]]
+name = "user"
+file = "user.dat"
+offset = "0xFFFFFFFFFFFFFFF0"
+size = "0x100"
+"#,
+    )
+    .expect("write overflowing layout");
+
+    let service = start_server(&root).await;
+
+    let built = call(
+        &service,
+        "gowin.build_flash_image",
+        json!({
+            "project_root": root.display().to_string(),
+            "layout_path": "flash.toml",
+        }),
+    )
+    .await
+    .expect("build flash image");
+    assert_eq!(built["partitions"].as_array().map(Vec::len), Some(3));
+    let image = std::fs::read(root.join("flash.bin")).expect("read flash.bin");
+    assert_eq!(image.len(), 0x204);
+    assert_eq!(&image[0x110..0x112], &[0xA5, 0xC3]);
+    assert_eq!(&image[0x200..], &[0xDE, 0xAD, 0xBE, 0xEF]);
+
+    call(
+        &service,
+        "gowin.build_flash_image",
+        json!({
+            "project_root": root.display().to_string(),
+            "layout_path": "flash.yaml",
+        }),
+    )
+    .await
+    .expect("build flash image from yaml");
+    let from_yaml = std::fs::read(root.join("flash.bin")).expect("read flash.bin");
+    assert_eq!(from_yaml, image);
+
+    let err = call(
+        &service,
+        "gowin.build_flash_image",
+        json!({
+            "project_root": root.display().to_string(),
+            "layout_path": "huge.toml",
+        }),
+    )
+    .await
+    .expect_err("overflowing partition must be rejected");
+    assert!(err.contains("大きすぎ"), "unexpected error: {err}");
+
+    let err = call(
+        &service,
+        "gowin.build_flash_image",
+        json!({
+            "project_root": root.display().to_string(),
+            "layout_path": "overlap.toml",
+        }),
+    )
+    .await
+    .expect_err("overlapping partitions must be rejected");
+    assert!(err.contains("golden"), "unexpected error: {err}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn program_fs_selects_cable_by_serial() {
+    let root = temp_project("serial");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+
+    let service = start_server(&root).await;
+
+    let list = call(
+        &service,
+        "gowin.list_cables",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+        }),
+    )
+    .await
+    .expect("list cables");
+    let cables = list["cables"].as_array().expect("cables array");
+    assert_eq!(cables.len(), 2);
+    assert_eq!(cables[1]["name"], "Gowin USB Cable(FT2CH)");
+    assert_eq!(cables[1]["cable_type"], "FT2CH");
+    assert_eq!(cables[1]["serial"], "BBB222");
+    assert_eq!(cables[1]["location"], "1-2");
+    assert_eq!(cables[1]["idcode"], "0x0001281B");
+
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable_serial": "bbb222",
+        }),
+    )
+    .await
+    .expect("program by serial");
+    assert_eq!(program["exit_code"], 0);
+    assert_eq!(program["selected_cable_info"]["index"], 1);
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.contains("--cable-index 1"), "unexpected argv: {last}");
+
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable_serial": "ZZZ999",
+        }),
+    )
+    .await
+    .expect_err("unknown serial must be rejected");
+    assert!(err.contains("AAA111"), "unexpected error: {err}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn programmer_capabilities_are_cached() {
+    let root = temp_project("caps");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+
+    let service = start_server(&root).await;
+    let args = json!({
+        "project_root": root.display().to_string(),
+        "gowin_ide_app_path": app.display().to_string(),
+    });
+
+    let first = call(&service, "gowin.list_cables", args.clone())
+        .await
+        .expect("list cables");
+    assert_eq!(first["capabilities_cached"], false);
+    assert_eq!(first["capabilities"]["list_cables_flag"], "--list-cables");
+    assert_eq!(first["attempts"].as_array().map(Vec::len), Some(1));
+
+    let second = call(&service, "gowin.list_cables", args)
+        .await
+        .expect("list cables again");
+    assert_eq!(second["capabilities_cached"], true);
+
+    // キャッシュはインストールごとなので、別のプロジェクトからも使える
+    let other = temp_project("caps-other");
+    let third = call(
+        &service,
+        "gowin.list_cables",
+        json!({
+            "project_root": other.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+        }),
+    )
+    .await
+    .expect("list cables from another project");
+    assert_eq!(third["capabilities_cached"], true);
+    assert!(!other.join(".gowin-mcp/cache").exists());
+    let _ = std::fs::remove_dir_all(&other);
+    let help_calls = fake_calls(&app).iter().filter(|c| *c == "--help").count();
+    assert_eq!(help_calls, 1);
+
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable": "Gowin USB Cable(FT2CH)",
+            "verify": true,
+        }),
+    )
+    .await
+    .expect("program and verify");
+    assert_eq!(program["operation"], "SRAM Program and Verify");
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.starts_with("--run 14 "), "unexpected argv: {last}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+// --cable-index がなく、SRAM Program の番号が既定と違う programmer_cli。書き込みは必ず失敗する
+const NO_CABLE_INDEX: &str = r#"case "$1" in
+--help)
+    echo "Usage: programmer_cli [options]"
+    echo "  --list-cables            List connected cables"
+    echo "  --run <index>            Operation index"
+    echo "  --fsFile <file>          Bitstream file"
+    echo "Operations:"
+    echo "  7: SRAM Program"
+    ;;
+--list-cables)
+    echo "[0] Gowin USB Cable(FT2CH) SN: AAA111 Location: 1-1"
+    echo "[1] Gowin USB Cable(FT2CH) SN: BBB222 Location: 1-2"
+    ;;
+--run)
+    echo "Error: no device"
+    exit 1
+    ;;
+esac
+exit 0"#;
+
+#[tokio::test]
+async fn program_fs_builds_argv_from_capabilities() {
+    let root = temp_project("argv");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, NO_CABLE_INDEX);
+
+    let service = start_server(&root).await;
+
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable": "Gowin USB Cable(FT2CH)",
+            "frequency": "20 mhz",
+            "retries": 2,
+        }),
+    )
+    .await
+    .expect("program_fs reports the failed run");
+    assert_eq!(program["exit_code"], 1);
+    let runs: Vec<String> = fake_calls(&app)
+        .into_iter()
+        .filter(|c| c.starts_with("--run"))
+        .collect();
+    assert!(
+        runs.iter().all(|c| c.starts_with("--run 7 ")),
+        "unexpected argv: {runs:?}"
+    );
+    // 検証したのと同じ表記を programmer_cli に渡す
+    assert!(
+        runs.iter().all(|c| c.contains(" 20MHz")),
+        "unexpected argv: {runs:?}"
+    );
+    let labels: Vec<&str> = program["variants_tried"]
+        .as_array()
+        .expect("variants_tried")
+        .iter()
+        .filter_map(|v| v["label"].as_str())
+        .collect();
+    assert_eq!(labels[labels.len() - 2..], ["retry_1", "retry_2"]);
+
+    let calls_before = fake_calls(&app).len();
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable_serial": "BBB222",
+        }),
+    )
+    .await
+    .expect_err("serial selection without --cable-index must be refused");
+    assert!(err.contains("番号指定"), "unexpected error: {err}");
+    assert!(
+        !fake_calls(&app)[calls_before..]
+            .iter()
+            .any(|c| c.starts_with("--run")),
+        "must not program without the cable index"
+    );
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+const CHAIN: &str = r#"case "$1" in
+--help)
+    echo "Usage: programmer_cli [options]"
+    echo "  --list-cables            List connected cables"
+    echo "  --run <index>            Operation index"
+    echo "  --device-index <n>       Device position in the JTAG chain"
+    echo "  --fsFile <file>          Bitstream file"
+    echo "Operations:"
+    echo "  0: Read Device Codes"
+    echo "  2: SRAM Program"
+    ;;
+--list-cables)
+    echo "[0] Gowin USB Cable(FT2CH) SN: CCC333 Location: 1-3"
+    ;;
+--run)
+    if [ "$2" = "0" ]; then
+        echo "Device 0: IDCODE 0x0100481B"
+        echo "Device 1: IDCODE 0x4BA00477"
+        echo "Device 2: IDCODE 0x0001281B"
+        echo "Device 2: IDCODE 0x0001281B"
+        echo "Status: 0x00000000"
+    fi
+    ;;
+esac
+exit 0"#;
+
+#[tokio::test]
+async fn scan_chain_and_program_by_position() {
+    let root = temp_project("chain");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, CHAIN);
+
+    let service = start_server(&root).await;
+
+    let scan = call(
+        &service,
+        "gowin.scan_chain",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+        }),
+    )
+    .await
+    .expect("scan chain");
+    let devices = scan["devices"].as_array().expect("devices array");
+    // 他社のデバイス（0x4BA00477）も位置に数え、同じ行の繰り返しは 1 つにする
+    assert_eq!(devices.len(), 3);
+    assert_eq!(devices[0]["family"], "GW1N");
+    assert_eq!(devices[1]["idcode"], "0x4BA00477");
+    assert_eq!(devices[1]["gowin"], false);
+    assert_eq!(devices[2]["idcode"], "0x0001281B");
+    assert_eq!(devices[2]["position"], 2);
+
+    // 位置 0 は GW1N-9 なので GW5A-25 の .fs は弾かれる
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "chain_position": 0,
+        }),
+    )
+    .await
+    .expect_err("device at position 0 must not match");
+    assert!(err.contains("0x0100481B"), "unexpected error: {err}");
+
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "chain_position": 1,
+        }),
+    )
+    .await
+    .expect_err("non-Gowin device must not be programmed");
+    assert!(err.contains("0x4BA00477"), "unexpected error: {err}");
+
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "chain_position": 2,
+        }),
+    )
+    .await
+    .expect("program position 2");
+    assert_eq!(program["exit_code"], 0);
+    assert_eq!(program["chain_devices"].as_array().map(Vec::len), Some(3));
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.contains("--device-index 2"), "unexpected argv: {last}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+const STATUS: &str = r#"case "$1" in
+--help)
+    echo "Usage: programmer_cli [options]"
+    echo "  --list-cables            List connected cables"
+    echo "  --run <index>            Operation index"
+    echo "Operations:"
+    echo "  2: SRAM Program"
+    echo "  25: Read Status Register"
+    echo "  26: Read User Code"
+    ;;
+--list-cables)
+    echo "[0] Gowin USB Cable(FT2CH) SN: DDD444"
+    echo "    IDCODE: 0x0001281B"
+    ;;
+--run)
+    case "$2" in
+    25) echo "${STATUS_LINE:-Status Register: 0x00003020}" ;;
+    26) echo "User Code: 0x0000CAFE" ;;
+    esac
+    ;;
+esac
+exit 0"#;
+
+#[tokio::test]
+async fn device_status_decodes_flags() {
+    let root = temp_project("status");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, STATUS);
+
+    let service = start_server(&root).await;
+
+    let status = call(
+        &service,
+        "gowin.device_status",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+        }),
+    )
+    .await
+    .expect("device status");
+    assert_eq!(status["status"]["status_register"], "0x00003020");
+    assert_eq!(status["status"]["usercode"], "0x0000CAFE");
+    assert_eq!(status["status"]["done"], true);
+    assert_eq!(
+        status["status"]["flags"],
+        json!(["MEMORY_ERASE", "I2C_SRAM_F", "DONE_FINAL"])
+    );
+    assert_eq!(status["status"]["state"], "configured");
+    assert_eq!(status["configured"], true);
+    assert_eq!(status["commands"].as_array().map(Vec::len), Some(2));
+
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "post_check": true,
+        }),
+    )
+    .await
+    .expect("program with post check");
+    assert_eq!(program["exit_code"], 0);
+    assert_eq!(program["post_check"]["done"], true);
+    assert!(program["post_check_error"].is_null());
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn device_status_ignores_unlabeled_hex() {
+    let root = temp_project("status_unknown");
+    let app = fake_gowin_app(
+        &root,
+        &format!("STATUS_LINE='Status: OK, IDCODE 0x0001281B'\n{STATUS}"),
+    );
+
+    let service = start_server(&root).await;
+
+    let status = call(
+        &service,
+        "gowin.device_status",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+        }),
+    )
+    .await
+    .expect("device status");
+    assert!(status["status"]["status_register"].is_null());
+    assert_eq!(status["status"]["state"], "unknown");
+    assert_eq!(status["configured"], false);
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn reconfigure_selects_cable_and_runs_reprogram() {
+    let root = temp_project("reconfigure");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+
+    let service = start_server(&root).await;
+
+    let result = call(
+        &service,
+        "gowin.reconfigure",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "cable_serial": "BBB222",
+        }),
+    )
+    .await
+    .expect("reconfigure");
+    assert_eq!(result["exit_code"], 0);
+    assert_eq!(result["selected_cable_info"]["serial"], "BBB222");
+    assert!(result["log_file"].as_str().is_some_and(|p| !p.is_empty()));
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.starts_with("--run 1 "), "unexpected argv: {last}");
+    assert!(last.contains("--cable-index 1"), "unexpected argv: {last}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn program_fs_board_cable_yields_to_serial() {
+    let root = temp_project("board-serial");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+
+    let service = start_server(&root).await;
+
+    // ボードのケーブル名は、シリアルで選んだときには使わない
+    let program = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "board": "tangprimer25k",
+            "cable_serial": "BBB222",
+        }),
+    )
+    .await
+    .expect("program_fs with board and serial");
+    assert_eq!(program["exit_code"], 0);
+    assert_eq!(program["selected_cable_info"]["serial"], "BBB222");
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.contains("--cable-index 1"), "unexpected argv: {last}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+const READBACK: &str = r#"case "$1" in
+--help)
+    echo "Usage: programmer_cli [options]"
+    echo "  --list-cables            List connected cables"
+    echo "  --run <index>            Operation index"
+    echo "  --outFile <file>         Readback output"
+    echo "  --spiaddr <addr>         SPI flash address"
+    echo "Operations:"
+    echo "  2: SRAM Program"
+    echo "  29: embFlash Read"
+    echo "  30: exFlash Read"
+    ;;
+--list-cables)
+    echo "[0] Gowin USB Cable(FT2CH) SN: EEE555"
+    ;;
+--run)
+    out=""
+    while [ $# -gt 0 ]; do
+        if [ "$1" = "--outFile" ]; then out="$2"; fi
+        shift
+    done
+    printf 'ABCDEFGH' > "$out"
+    ;;
+esac
+exit 0"#;
+
+#[tokio::test]
+async fn read_flash_compares_against_reference() {
+    let root = temp_project("readflash");
+    std::fs::write(root.join("same.bin"), b"ABCD").expect("write same.bin");
+    std::fs::write(root.join("other.bin"), b"ABXD").expect("write other.bin");
+    let app = fake_gowin_app(&root, READBACK);
+
+    let service = start_server(&root).await;
+
+    let same = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "address": "0x100000",
+            "compare_with": "same.bin",
+        }),
+    )
+    .await
+    .expect("read flash");
+    assert_eq!(same["read_bytes"], 4);
+    assert_eq!(same["comparison"]["matches"], true);
+    let out = same["output_file"].as_str().expect("output_file");
+    assert_eq!(std::fs::read(out).expect("readback file"), b"ABCD");
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.starts_with("--run 30 "), "unexpected argv: {last}");
+    assert!(
+        last.contains("--spiaddr 0x100000"),
+        "unexpected argv: {last}"
+    );
+
+    let other = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "address": "0x100000",
+            "compare_with": "other.bin",
+        }),
+    )
+    .await
+    .expect("read flash again");
+    assert_eq!(other["comparison"]["matches"], false);
+    assert_eq!(other["comparison"]["first_diff_address"], 0x100002);
+    assert_eq!(other["comparison"]["expected"], "0x58");
+    assert_eq!(other["comparison"]["actual"], "0x43");
+
+    let err = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "target": "sram",
+            "length": "4",
+        }),
+    )
+    .await
+    .expect_err("sram is not a flash target");
+    assert!(err.contains("embflash"), "unexpected error: {err}");
+
+    // 内蔵フラッシュには SPI アドレスを渡さない
+    let emb = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "target": "embflash",
+            "length": "4",
+        }),
+    )
+    .await
+    .expect("read embflash");
+    assert_eq!(emb["read_bytes"], 4);
+    let last = fake_calls(&app).pop().expect("programmer_cli called");
+    assert!(last.starts_with("--run 29 "), "unexpected argv: {last}");
+    assert!(!last.contains("--spiaddr"), "unexpected argv: {last}");
+    let err = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "target": "embflash",
+            "address": "0x1000",
+            "length": "4",
+        }),
+    )
+    .await
+    .expect_err("embflash has no SPI address");
+    assert!(err.contains("address"), "unexpected error: {err}");
+
+    // 比較対象に読み出し結果を上書きさせない
+    let calls_before = fake_calls(&app).len();
+    let err = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "compare_with": "same.bin",
+            "output_path": "./same.bin",
+        }),
+    )
+    .await
+    .expect_err("output_path equal to compare_with");
+    assert!(err.contains("compare_with"), "unexpected error: {err}");
+    assert_eq!(
+        std::fs::read(root.join("same.bin")).expect("same.bin"),
+        b"ABCD"
+    );
+    assert_eq!(fake_calls(&app).len(), calls_before);
+
+    // 読み出しに失敗しても前回の出力は残す
+    std::fs::write(root.join("keep.bin"), b"KEEP").expect("write keep.bin");
+    let app_fail = fake_gowin_app(
+        &root.join("failing"),
+        &READBACK.replace(
+            "printf 'ABCDEFGH' > \"$out\"",
+            "printf 'AB' > \"$out\"; exit 1",
+        ),
+    );
+    let failed = call(
+        &service,
+        "gowin.read_flash",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app_fail.display().to_string(),
+            "length": "4",
+            "output_path": "keep.bin",
+        }),
+    )
+    .await
+    .expect("failed read still returns a report");
+    assert_eq!(failed["read_bytes"], json!(null));
+    assert_eq!(
+        std::fs::read(root.join("keep.bin")).expect("keep.bin"),
+        b"KEEP"
+    );
+    let leftovers = std::fs::read_dir(root.join(".gowin-mcp/readback"))
+        .expect("readback dir")
+        .filter_map(Result::ok)
+        .filter(|e| e.file_name().to_string_lossy().ends_with(".tmp"))
+        .count();
+    assert_eq!(leftovers, 0);
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn program_fs_reports_cable_lock_holder() {
+    let root = temp_project("lock");
+    write_fake_fs(&root.join("top.fs"));
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    let lock_dir = root.join("locks");
+    std::fs::create_dir_all(&lock_dir).expect("create lock dir");
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .expect("clock")
+        .as_millis() as u64;
+    let holder = |expires_at_ms: u64| {
+        json!({
+            "run_id": "other-run-42",
+            "tool": "gowin.program_fs",
+            "pid": 1,
+            "project_root": "/elsewhere",
+            "cable_key": "sn-AAA111",
+            "acquired_at_ms": now_ms,
+            "expires_at_ms": expires_at_ms,
+        })
+        .to_string()
+    };
+    std::fs::write(lock_dir.join("sn-AAA111.lock"), holder(now_ms + 600_000)).expect("write lock");
+
+    let service = start_server(&root).await;
+    let args = json!({
+        "project_root": root.display().to_string(),
+        "gowin_ide_app_path": app.display().to_string(),
+        "fs_file_path": "top.fs",
+        "cable_serial": "AAA111",
+    });
+
+    let err = call(&service, "gowin.program_fs", args.clone())
+        .await
+        .expect_err("locked cable must be refused");
+    assert!(err.contains("other-run-42"), "unexpected error: {err}");
+    // 使用中のケーブルは列挙もしない
+    assert!(
+        !fake_calls(&app).iter().any(|c| c == "--list-cables"),
+        "cables were enumerated while locked"
+    );
+
+    // 名前で指定しても同じケーブル（先頭の AAA111）のロックに当たる
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable": "Gowin USB Cable(FT2CH)",
+        }),
+    )
+    .await
+    .expect_err("same cable selected by name must be refused");
+    assert!(err.contains("other-run-42"), "unexpected error: {err}");
+
+    // 別のケーブルは使える
+    let other = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "top.fs",
+            "cable_serial": "BBB222",
+        }),
+    )
+    .await
+    .expect("other cable is free");
+    assert_eq!(other["exit_code"], 0);
+
+    // 期限切れのロックは引き継ぎ、終了後に解放する
+    std::fs::write(lock_dir.join("sn-AAA111.lock"), holder(now_ms - 1)).expect("write stale lock");
+    let program = call(&service, "gowin.program_fs", args)
+        .await
+        .expect("stale lock is taken over");
+    assert_eq!(program["exit_code"], 0);
+    assert!(program["run_id"].as_str().is_some_and(|r| !r.is_empty()));
+    assert!(!lock_dir.join("sn-AAA111.lock").exists());
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+// gw_sh の代わり。引数の Tcl と同じディレクトリを project_root とみなし、
+// impl/pnr に .fs とレポートを書き出す。fail_timing.tcl ならタイミング違反
+const FAKE_GW_SH: &str = r#"#!/bin/sh
+root="$(dirname "$1")"
+if [ "$(basename "$1")" = "broken.tcl" ]; then
+    echo "ERROR (EX3863) : Syntax error near 'endmodule'"
+    exit 1
+fi
+mkdir -p "$root/impl/pnr"
+cp "$root/golden.fs" "$root/impl/pnr/top.fs"
+if [ "$(basename "$1")" = "fail_timing.tcl" ]; then
+    actual="42.000(MHz)"
+    tns="-3.250"
+    echo "WARN  (TA1132) : 'clk' was determined to be a clock but was not created"
+else
+    actual="120.500(MHz)"
+    tns="0.000"
+fi
+cat > "$root/impl/pnr/top.tr" <<EOF2
+  NO.   Clock Name   Constraint    Actual Fmax    Logic Level   Entity
+  1     clk          50.000(MHz)   $actual    4             TOP
+
+  Clock Name   Analysis Type   Endpoints TNS   Number of Endpoints
+  clk          setup           $tns          0
+EOF2
+cat > "$root/impl/pnr/top.rpt.txt" <<EOF2
+  Resources                   | Usage
+  Logic                       | 2000/20736  10%
+  Register                    | 900/15750  6%
+  I/O Port                    | 60/66  91%
+EOF2
+echo "GowinSynthesis start"
+echo "WARN  (EX3780) : Using initial value of 'x' since it is never assigned(\"$root/src/top.v\":3)"
+exit 0
+"#;
+
+fn fake_gw_sh(app: &Path) {
+    use std::os::unix::fs::PermissionsExt;
+
+    let bin = app.join("Contents/Resources/Gowin_EDA/IDE/bin");
+    std::fs::create_dir_all(&bin).expect("create fake IDE dir");
+    let gw_sh = bin.join("gw_sh");
+    std::fs::write(&gw_sh, FAKE_GW_SH).expect("write fake gw_sh");
+    std::fs::set_permissions(&gw_sh, std::fs::Permissions::from_mode(0o755))
+        .expect("chmod fake gw_sh");
+}
+
+#[tokio::test]
+async fn build_and_program_applies_gates() {
+    let root = temp_project("pipeline");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::write(root.join("fail_timing.tcl"), "run all -strict\n").expect("write tcl");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+
+    let ok = call(
+        &service,
+        "gowin.build_and_program",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "max_utilization_percent": 95.0,
+        }),
+    )
+    .await
+    .expect("build and program");
+    assert_eq!(ok["gates_passed"], true, "gates: {}", ok["gates"]);
+    assert_eq!(ok["programmed"], true);
+    assert_eq!(ok["timing"]["met"], true);
+    assert_eq!(ok["timing"]["clocks"][0]["fmax_mhz"], 120.5);
+    assert_eq!(ok["utilization"][2]["resource"], "I/O Port");
+    assert_eq!(ok["warnings"][0]["code"], "EX3780");
+    assert_eq!(ok["warnings"][0]["line"], 3);
+    let stages: Vec<&str> = ok["stages"]
+        .as_array()
+        .expect("stages")
+        .iter()
+        .map(|s| s["name"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(stages, ["build", "analyze", "gates", "program"]);
+    assert!(fake_calls(&app).iter().any(|c| c.contains("top.fs")));
+
+    let blocked = call(
+        &service,
+        "gowin.build_and_program",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "tcl_path": "fail_timing.tcl",
+            "max_utilization_percent": 80.0,
+        }),
+    )
+    .await
+    .expect("build with violations");
+    assert_eq!(blocked["gates_passed"], false);
+    assert_eq!(blocked["programmed"], false);
+    assert!(blocked["program"].is_null());
+    assert_eq!(blocked["stages"][3]["status"], "skipped");
+    let failed: Vec<&str> = blocked["gates"]
+        .as_array()
+        .expect("gates")
+        .iter()
+        .filter(|g| g["passed"] == false)
+        .map(|g| g["name"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(failed, ["timing_met", "utilization"]);
+
+    // ビルド前からある .fs は書き込まない
+    let old = std::fs::File::options()
+        .write(true)
+        .open(root.join("golden.fs"))
+        .expect("open golden.fs");
+    old.set_modified(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000))
+        .expect("set mtime");
+    let outdated = call(
+        &service,
+        "gowin.build_and_program",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "golden.fs",
+        }),
+    )
+    .await
+    .expect("build with an old bitstream");
+    assert_eq!(outdated["gates_passed"], false);
+    assert_eq!(outdated["programmed"], false);
+    let fresh = outdated["gates"]
+        .as_array()
+        .expect("gates")
+        .iter()
+        .find(|g| g["name"] == "fresh_outputs")
+        .expect("fresh_outputs gate");
+    assert_eq!(fresh["passed"], false);
+    assert!(
+        fresh["detail"].as_str().unwrap_or("").contains("golden.fs"),
+        "unexpected detail: {fresh}"
+    );
+
+    // 書き込みのエラーもビルド結果と一緒に返す
+    let unplugged = call(
+        &service,
+        "gowin.build_and_program",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "cable_serial": "NO-SUCH-SERIAL",
+        }),
+    )
+    .await
+    .expect("program errors are reported, not raised");
+    assert_eq!(unplugged["gates_passed"], true);
+    assert_eq!(unplugged["programmed"], false);
+    assert_eq!(unplugged["stages"][3]["status"], "failed");
+    assert!(
+        unplugged["program_error"]
+            .as_str()
+            .unwrap_or("")
+            .contains("NO-SUCH-SERIAL"),
+        "unexpected error: {}",
+        unplugged["program_error"]
+    );
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn program_fs_detects_stale_bitstream() {
+    let root = temp_project("stale");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(root.join("src/top.v"), "module top; endmodule\n").expect("write top.v");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project>
+    <FileList>
+        <File path="src/top.v" type="file.verilog" enable="1"/>
+        <File path="src/old.v" type="file.verilog" enable="0"/>
+    </FileList>
+</Project>
+"#,
+    )
+    .expect("write gprj");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+
+    let build = call(
+        &service,
+        "gowin.run_tcl",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "tcl_path": "run_gowin.tcl",
+        }),
+    )
+    .await
+    .expect("build");
+    assert_eq!(build["exit_code"], 0);
+
+    let program_args = |policy: &str| {
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "fs_file_path": "impl/pnr/top.fs",
+            "stale_policy": policy,
+        })
+    };
+
+    let fresh = call(&service, "gowin.program_fs", program_args("block"))
+        .await
+        .expect("fresh bitstream");
+    assert_eq!(fresh["staleness"]["stale"], false);
+    assert_eq!(fresh["staleness"]["source_origin"], "gprj");
+    assert_eq!(fresh["staleness"]["sources_checked"], 2);
+
+    std::thread::sleep(std::time::Duration::from_millis(50));
+    std::fs::write(root.join("src/top.v"), "module top(input a); endmodule\n").expect("edit top.v");
+
+    let err = call(&service, "gowin.program_fs", program_args("block"))
+        .await
+        .expect_err("stale bitstream must be blocked");
+    assert!(err.contains("src/top.v"), "unexpected error: {err}");
+
+    let warned = call(&service, "gowin.program_fs", program_args("warn"))
+        .await
+        .expect("stale bitstream is only a warning");
+    assert_eq!(warned["exit_code"], 0);
+    assert_eq!(warned["staleness"]["newer_than_fs"], json!(["src/top.v"]));
+    assert_eq!(
+        warned["staleness"]["changed_since_build"],
+        json!(["src/top.v"])
+    );
+    assert_eq!(
+        warned["validation_warnings"].as_array().map(Vec::len),
+        Some(1)
+    );
+
+    // .gprj が読めず判定できない場合、block なら止め、warn なら警告に残す
+    std::fs::write(root.join("top.gprj"), [0xFF, 0xFE, 0x00]).expect("write broken gprj");
+    let err = call(&service, "gowin.program_fs", program_args("block"))
+        .await
+        .expect_err("unknown staleness must be blocked");
+    assert!(
+        err.contains("新旧を確認できません"),
+        "unexpected error: {err}"
+    );
+    let warned = call(&service, "gowin.program_fs", program_args("warn"))
+        .await
+        .expect("unknown staleness is only a warning");
+    assert!(warned["staleness"].is_null());
+    assert!(
+        warned["validation_warnings"][0]
+            .as_str()
+            .unwrap_or("")
+            .contains("新旧を確認できません"),
+        "unexpected warnings: {}",
+        warned["validation_warnings"]
+    );
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn build_cache_skips_unchanged_builds() {
+    let root = temp_project("cache");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(root.join("src/top.v"), "module top; endmodule\n").expect("write top.v");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project><FileList><File path="src/top.v" type="file.verilog" enable="1"/><File path="top.cst" type="file.cst" enable="1"/></FileList></Project>"#,
+    )
+    .expect("write gprj");
+    std::fs::write(root.join("top.cst"), "IO_LOC \"led\" 10;\n").expect("write cst");
+    // sources を指定しても .gprj とその制約ファイルはキーに含まれる
+    std::fs::create_dir_all(root.join(".gowin-mcp")).expect("create .gowin-mcp");
+    std::fs::write(root.join(".gowin-mcp/config.toml"), "sources = [\"src\"]\n")
+        .expect("write config");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+    let args = json!({
+        "project_root": root.display().to_string(),
+        "gowin_ide_app_path": app.display().to_string(),
+        "program": false,
+    });
+
+    let first = call(&service, "gowin.build_and_program", args.clone())
+        .await
+        .expect("first build");
+    assert_eq!(first["cache_hit"], false);
+    assert_eq!(first["stages"][0]["status"], "ok");
+
+    let second = call(&service, "gowin.build_and_program", args.clone())
+        .await
+        .expect("second build");
+    assert_eq!(second["cache_hit"], true);
+    assert_eq!(second["stages"][0]["status"], "cached");
+    assert_eq!(second["gates_passed"], true);
+    // 診断は前回のビルド出力から引き継ぐ
+    assert_eq!(second["warnings"][0]["code"], "EX3780");
+
+    std::fs::write(root.join("top.cst"), "IO_LOC \"led\" 11;\n").expect("edit cst");
+    let constrained = call(&service, "gowin.build_and_program", args.clone())
+        .await
+        .expect("build after a constraint change");
+    assert_eq!(constrained["cache_hit"], false);
+
+    std::fs::write(root.join("src/top.v"), "module top(input a); endmodule\n").expect("edit top.v");
+    let third = call(&service, "gowin.build_and_program", args)
+        .await
+        .expect("third build");
+    assert_eq!(third["cache_hit"], false);
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn program_fs_flashes_stored_artifact() {
+    let root = temp_project("artifacts");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project><Device name="GW5A-25A" pn="GW5A-LV25MG121NC1/I0">gw5a25a-002</Device></Project>"#,
+    )
+    .expect("write gprj");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+
+    let build = call(
+        &service,
+        "gowin.run_tcl",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "tcl_path": "run_gowin.tcl",
+        }),
+    )
+    .await
+    .expect("build");
+    let artifact_id = build["artifact_id"]
+        .as_str()
+        .expect("artifact_id")
+        .to_string();
+    let dir = root.join(".gowin-mcp/artifacts").join(&artifact_id);
+    let manifest: serde_json::Value =
+        serde_json::from_slice(&std::fs::read(dir.join("manifest.json")).expect("read manifest"))
+            .expect("parse manifest");
+    assert_eq!(manifest["device"], "GW5A-25A");
+    assert_eq!(manifest["part_number"], "GW5A-LV25MG121NC1/I0");
+    assert_eq!(manifest["fs_file"], "top.fs");
+    let names: Vec<&str> = manifest["files"]
+        .as_array()
+        .expect("files")
+        .iter()
+        .map(|f| f["name"].as_str().unwrap_or(""))
+        .collect();
+    assert_eq!(names, ["top.fs", "top.rpt.txt", "top.tr", "build.tcl"]);
+
+    // 次のビルドで impl/pnr が上書きされても、保存した .fs を書き込める
+    std::fs::write(root.join("impl/pnr/top.fs"), "garbage\n").expect("overwrite fs");
+    let programmed = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "artifact_id": artifact_id,
+        }),
+    )
+    .await
+    .expect("program artifact");
+    assert_eq!(programmed["exit_code"], 0);
+    assert_eq!(programmed["artifact_id"], artifact_id.as_str());
+    assert!(programmed["staleness"].is_null());
+    let fs = dir.join("top.fs").display().to_string();
+    assert!(fake_calls(&app).iter().any(|c| c.contains(&fs)));
+
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "artifact_id": "no-such-build",
+        }),
+    )
+    .await
+    .expect_err("unknown artifact");
+    assert!(err.contains("no-such-build"), "unexpected error: {err}");
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+fn git(root: &Path, args: &[&str]) -> String {
+    let out = std::process::Command::new("git")
+        .args(["-c", "user.name=test", "-c", "user.email=test@example.com"])
+        .args(args)
+        .current_dir(root)
+        .output()
+        .expect("run git");
+    assert!(out.status.success(), "git {args:?}: {out:?}");
+    String::from_utf8_lossy(&out.stdout).trim().to_string()
+}
+
+#[tokio::test]
+async fn run_meta_records_git_state() {
+    let root = temp_project("git");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    // pack で delta になるよう、ある程度の長さにする
+    let body: String = (0..64).map(|i| format!("// line {i}\n")).collect();
+    std::fs::write(
+        root.join("src/top.v"),
+        format!("module top; endmodule\n{body}"),
+    )
+    .expect("write top.v");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project><FileList><File path="src/top.v" type="file.verilog" enable="1"/><File path="src/new.v" type="file.verilog" enable="1"/></FileList></Project>"#,
+    )
+    .expect("write gprj");
+    std::fs::write(
+        root.join(".gitignore"),
+        "/impl/\n/.gowin-mcp/\n/FakeGowinIDE.app/\n",
+    )
+    .expect("write .gitignore");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+    git(&root, &["init", "-q", "-b", "main"]);
+    git(&root, &["add", "."]);
+    git(&root, &["commit", "-q", "-m", "init"]);
+    let head = git(&root, &["rev-parse", "HEAD"]);
+
+    let service = start_server(&root).await;
+    let build_meta = || async {
+        let build = call(
+            &service,
+            "gowin.run_tcl",
+            json!({
+                "project_root": root.display().to_string(),
+                "gowin_ide_app_path": app.display().to_string(),
+                "tcl_path": "run_gowin.tcl",
+            }),
+        )
+        .await
+        .expect("build");
+        let meta_file = build["meta_file"].as_str().expect("meta_file").to_string();
+        serde_json::from_slice::<serde_json::Value>(&std::fs::read(meta_file).expect("read meta"))
+            .expect("parse meta")
+    };
+
+    let clean = build_meta().await;
+    assert_eq!(clean["git"]["commit"], head.as_str());
+    assert_eq!(clean["git"]["branch"], "main");
+    assert_eq!(clean["git"]["dirty"], false);
+    assert!(clean["git"]["diff_hash"].is_null());
+
+    std::fs::write(
+        root.join("src/top.v"),
+        format!("module top(input a); endmodule\n{body}"),
+    )
+    .expect("edit top.v");
+    let dirty = build_meta().await;
+    assert_eq!(dirty["git"]["dirty"], true);
+    assert_eq!(dirty["git"]["changed_files"], json!(["src/top.v"]));
+    let diff_hash = dirty["git"]["diff_hash"].as_str().expect("diff_hash");
+    assert_eq!(diff_hash.len(), 64);
+
+    // 同じ差分なら同じハッシュ
+    let again = build_meta().await;
+    assert_eq!(again["git"]["diff_hash"], diff_hash);
+
+    // 作業ツリーと同じ内容でステージしても HEAD との差分は残る
+    git(&root, &["add", "src/top.v"]);
+    let staged = build_meta().await;
+    assert_eq!(staged["git"]["dirty"], true);
+    assert_eq!(staged["git"]["changed_files"], json!(["src/top.v"]));
+    git(&root, &["commit", "-q", "-m", "edit"]);
+
+    // 未追跡のファイルはソースに入っているものだけ数える
+    std::fs::write(root.join("src/new.v"), "module sub; endmodule\n").expect("write new.v");
+    std::fs::write(root.join("notes.txt"), "scratch\n").expect("write notes");
+    let untracked = build_meta().await;
+    assert_eq!(untracked["git"]["dirty"], true);
+    assert_eq!(untracked["git"]["changed_files"], json!(["src/new.v"]));
+
+    // pack に入った HEAD のツリーとも比べる（削除のステージも変更）
+    git(&root, &["add", "src/new.v"]);
+    git(&root, &["commit", "-q", "-m", "add"]);
+    git(&root, &["gc", "-q"]);
+    let packed = build_meta().await;
+    assert_eq!(packed["git"]["dirty"], false, "{}", packed["git"]);
+    git(&root, &["rm", "-q", "--cached", "run_gowin.tcl"]);
+    let removed = build_meta().await;
+    assert_eq!(removed["git"]["changed_files"], json!(["run_gowin.tcl"]));
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn build_trends_tracks_timing_and_utilization() {
+    let root = temp_project("trends");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::write(root.join("fail_timing.tcl"), "run all -strict\n").expect("write tcl");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+    let mut run_ids = Vec::new();
+    for tcl in ["run_gowin.tcl", "fail_timing.tcl"] {
+        let build = call(
+            &service,
+            "gowin.run_tcl",
+            json!({
+                "project_root": root.display().to_string(),
+                "gowin_ide_app_path": app.display().to_string(),
+                "tcl_path": tcl,
+            }),
+        )
+        .await
+        .expect("build");
+        run_ids.push(build["run_id"].as_str().expect("run_id").to_string());
+    }
+
+    let trends = call(
+        &service,
+        "gowin.build_trends",
+        json!({
+            "project_root": root.display().to_string(),
+            "metrics": ["fmax_mhz", "wns_ns", "util_percent:Logic", "warnings"],
+        }),
+    )
+    .await
+    .expect("trends");
+    assert_eq!(trends["builds"], 2);
+    assert_eq!(trends["timing_broken"], json!([run_ids[1]]));
+    let series = |metric: &str| {
+        trends["series"]
+            .as_array()
+            .expect("series")
+            .iter()
+            .find(|s| s["metric"] == metric)
+            .cloned()
+            .unwrap_or_else(|| panic!("missing series {metric}: {}", trends["series"]))
+    };
+    let fmax = series("fmax_mhz:clk");
+    let values: Vec<f64> = fmax["points"]
+        .as_array()
+        .expect("points")
+        .iter()
+        .map(|p| p["value"].as_f64().unwrap_or(0.0))
+        .collect();
+    assert_eq!(values, [120.5, 42.0]);
+    assert_eq!(series("wns_ns")["last"], -3.81);
+    assert_eq!(series("wns_ns")["first"], 11.701);
+    assert_eq!(series("util_percent:Logic")["last"], 9.65);
+    assert_eq!(series("warnings")["max"], 2.0);
+    assert_eq!(trends["series"].as_array().map(Vec::len), Some(4));
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn compare_runs_reports_regressions() {
+    let root = temp_project("compare");
+    write_fake_fs(&root.join("golden.fs"));
+    std::fs::write(root.join("run_gowin.tcl"), "run all\n").expect("write tcl");
+    std::fs::write(root.join("fail_timing.tcl"), "run all -strict\n").expect("write tcl");
+    std::fs::create_dir_all(root.join("impl")).expect("create impl");
+    let app = fake_gowin_app(&root, TWO_BOARDS);
+    fake_gw_sh(&app);
+
+    let service = start_server(&root).await;
+    let mut run_ids = Vec::new();
+    for (tcl, place) in [("run_gowin.tcl", "0"), ("fail_timing.tcl", "1")] {
+        std::fs::write(
+            root.join("impl/project_process_config.json"),
+            json!({"Synthesize": {"Top_Module": "top"}, "Place": {"Place_Option": place}})
+                .to_string(),
+        )
+        .expect("write options");
+        let build = call(
+            &service,
+            "gowin.run_tcl",
+            json!({
+                "project_root": root.display().to_string(),
+                "gowin_ide_app_path": app.display().to_string(),
+                "tcl_path": tcl,
+            }),
+        )
+        .await
+        .expect("build");
+        run_ids.push(build["run_id"].as_str().expect("run_id").to_string());
+    }
+
+    // base_run_id / run_id を省略すると、最新と、その前の正常なビルドを比べる
+    let diff = call(
+        &service,
+        "gowin.compare_runs",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("compare");
+    assert_eq!(diff["base_run_id"], run_ids[0].as_str());
+    assert_eq!(diff["run_id"], run_ids[1].as_str());
+    assert_eq!(diff["clocks"][0]["clock"], "clk");
+    assert_eq!(diff["clocks"][0]["delta_fmax_mhz"], -78.5);
+    assert_eq!(diff["utilization"][0]["delta_percent"], 0.0);
+    assert_eq!(diff["new_warning_codes"], json!(["TA1132"]));
+    assert_eq!(diff["removed_warning_codes"], json!([]));
+    assert_eq!(diff["tcl"]["added"], json!(["run all -strict"]));
+    assert_eq!(diff["tcl"]["removed"], json!(["run all"]));
+    assert_eq!(diff["options"][0]["key"], "Place.Place_Option");
+    assert_eq!(diff["options"][0]["before"], "0");
+    assert_eq!(diff["options"][0]["after"], "1");
+    assert_eq!(diff["options"].as_array().map(Vec::len), Some(1));
+
+    let err = call(
+        &service,
+        "gowin.compare_runs",
+        json!({ "project_root": root.display().to_string(), "run_id": "nope" }),
+    )
+    .await
+    .expect_err("unknown run id");
+    assert!(err.contains("nope"), "unexpected error: {err}");
+
+    // 失敗したビルド（成果物なし）も Tcl とビルドオプションを比べられる
+    std::fs::write(root.join("broken.tcl"), "run syn\n").expect("write tcl");
+    std::fs::write(
+        root.join("impl/project_process_config.json"),
+        json!({"Synthesize": {"Top_Module": "core"}, "Place": {"Place_Option": "0"}}).to_string(),
+    )
+    .expect("write options");
+    let broken = call(
+        &service,
+        "gowin.run_tcl",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "tcl_path": "broken.tcl",
+        }),
+    )
+    .await
+    .expect("failed build");
+    assert_eq!(broken["exit_code"], 1);
+    assert!(broken["artifact_id"].is_null());
+    let failed = call(
+        &service,
+        "gowin.compare_runs",
+        json!({
+            "project_root": root.display().to_string(),
+            "base_run_id": run_ids[0],
+            "run_id": broken["run_id"],
+        }),
+    )
+    .await
+    .expect("compare with a failed build");
+    assert_eq!(failed["tcl"]["added"], json!(["run syn"]));
+    assert_eq!(failed["options"][0]["key"], "Synthesize.Top_Module");
+    assert_eq!(failed["options"][0]["after"], "core");
+    assert_eq!(failed["notes"], json!([]));
+
+    service.cancel().await.expect("cancel");
+    let _ = std::fs::remove_dir_all(&root);
+}
+
+#[tokio::test]
+async fn sdc_list_and_edit_validate_ports() {
+    let root = temp_project("sdc");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(
+        root.join("src/top.v"),
+        "module top (\n    input wire clk,\n    input wire [3:0] btn,\n    output reg [7:0] led\n);\n    blink u_blink (.clk(clk));\nendmodule\n\nmodule blink (input clk);\nendmodule\n",
+    )
+    .expect("write top.v");
+    std::fs::write(
+        root.join("src/top.sdc"),
+        "# clocks\ncreate_clock -name clk -period 37.037 -waveform {0 18.518} [get_ports {clk}]\nset_false_path -from [get_ports {btn[*]}]\n",
+    )
+    .expect("write sdc");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project>
+    <FileList>
+        <File path="src/top.v" type="file.verilog" enable="1"/>
+        <File path="src/top.sdc" type="file.sdc" enable="1"/>
+    </FileList>
+</Project>
+"#,
+    )
+    .expect("write gprj");
+
+    let service = start_server(&root).await;
+    let list = call(
+        &service,
+        "gowin.sdc_list",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("sdc_list");
+    assert_eq!(list["top_module"], "top");
+    assert_eq!(list["valid"], true);
+    assert_eq!(list["clocks"][0]["name"], "clk");
+    assert_eq!(list["clocks"][0]["period_ns"], 37.037);
+    assert_eq!(list["exceptions"][0]["kind"], "false_path");
+
+    // 存在しないポートを参照する制約は書き込まない
+    let err = call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "command": "set_input_delay -clock clk 2 [get_ports {uart_rx}]",
+        }),
+    )
+    .await
+    .expect_err("unknown port");
+    assert!(err.contains("uart_rx"), "{err}");
+
+    call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "command": "set_output_delay -clock clk 3 [get_ports {led[*]}]",
+        }),
+    )
+    .await
+    .expect("add");
+    let modified = call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "modify",
+            "clock": "clk",
+            "command": "create_clock -name clk -period 20 [get_ports {clk}]",
+        }),
+    )
+    .await
+    .expect("modify");
+    assert_eq!(modified["clocks"][0]["period_ns"], 20.0);
+
+    let sdc = std::fs::read_to_string(root.join("src/top.sdc")).expect("read sdc");
+    assert!(sdc.starts_with("# clocks\ncreate_clock -name clk -period 20 [get_ports {clk}]\n"));
+    assert!(sdc.ends_with("set_output_delay -clock clk 3 [get_ports {led[*]}]\n"));
+
+    // クロックを消すと、それを参照する制約が残るのでエラーになる
+    let err = call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "remove",
+            "clock": "clk",
+        }),
+    )
+    .await
+    .expect_err("dangling clock reference");
+    assert!(err.contains("clk"), "{err}");
+
+    let dry = call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "remove",
+            "index": 1,
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect("dry run remove");
+    assert_eq!(dry["written"], false);
+    assert_eq!(dry["before"], "set_false_path -from [get_ports {btn[*]}]");
+    assert!(
+        !dry["content"]
+            .as_str()
+            .expect("content")
+            .contains("set_false_path")
+    );
+}
+
+#[tokio::test]
+async fn cst_list_and_edit_pin_assignments() {
+    let root = temp_project("cst");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(
+        root.join("src/top.v"),
+        "module top (input clk, input rst_n, output [1:0] led);\nendmodule\n",
+    )
+    .expect("write top.v");
+    std::fs::write(
+        root.join("src/top.cst"),
+        r#"//Copyright (C)2014-2024 Gowin Semiconductor Corporation.
+IO_LOC "clk" 52;
+IO_PORT "clk" IO_TYPE=LVCMOS33 PULL_MODE=UP;
+IO_LOC "led[0]" 10;
+IO_PORT "led[0]" IO_TYPE=LVCMOS18 DRIVE=8 SLEWRATE=FAST;
+IO_LOC "led[1]" 10;
+IO_LOC "led[2]" 12;
+"#,
+    )
+    .expect("write cst");
+
+    let service = start_server(&root).await;
+    let list = call(
+        &service,
+        "gowin.cst_list",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("cst_list");
+    assert_eq!(list["top_module"], "top");
+    assert_eq!(list["valid"], false);
+    assert_eq!(list["pins"][0]["port"], "clk");
+    assert_eq!(list["pins"][0]["pins"], json!(["52"]));
+    assert_eq!(list["pins"][0]["pull_mode"], "UP");
+    assert_eq!(list["pins"][1]["drive"], "8");
+    let messages: Vec<&str> = list["issues"]
+        .as_array()
+        .expect("issues")
+        .iter()
+        .filter_map(|i| i["message"].as_str())
+        .collect();
+    assert!(
+        messages.iter().any(|m| m.contains("SLEWRATE")),
+        "{messages:?}"
+    );
+    assert!(
+        messages.iter().any(|m| m.contains("ピン 10")),
+        "{messages:?}"
+    );
+    assert!(
+        messages.iter().any(|m| m.contains("led[2]")),
+        "{messages:?}"
+    );
+
+    // 別ポートと同じピンへの追加は書き込まない
+    let err = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "port": "rst_n",
+            "pin": "52",
+        }),
+    )
+    .await
+    .expect_err("duplicate pin");
+    assert!(err.contains("52"), "{err}");
+
+    call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "port": "rst_n",
+            "pin": "4",
+            "io_type": "LVCMOS33",
+            "pull_mode": "UP",
+        }),
+    )
+    .await
+    .expect("add");
+    let modified = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "modify",
+            "port": "led[1]",
+            "pin": "11",
+            "drive": "16",
+        }),
+    )
+    .await
+    .expect("modify");
+    assert_eq!(modified["after"]["pins"], json!(["11"]));
+    assert_eq!(modified["after"]["drive"], "16");
+    call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "modify",
+            "port": "led[0]",
+            "attributes": { "SLEWRATE": "" },
+        }),
+    )
+    .await
+    .expect("drop attribute");
+    call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "remove",
+            "port": "led[2]",
+        }),
+    )
+    .await
+    .expect("remove");
+
+    let cst = std::fs::read_to_string(root.join("src/top.cst")).expect("read cst");
+    assert_eq!(
+        cst,
+        r#"//Copyright (C)2014-2024 Gowin Semiconductor Corporation.
+IO_LOC "clk" 52;
+IO_PORT "clk" IO_TYPE=LVCMOS33 PULL_MODE=UP;
+IO_LOC "led[0]" 10;
+IO_PORT "led[0]" IO_TYPE=LVCMOS18 DRIVE=8;
+IO_LOC "led[1]" 11;
+IO_LOC "rst_n" 4;
+IO_PORT "rst_n" IO_TYPE=LVCMOS33 PULL_MODE=UP;
+IO_PORT "led[1]" DRIVE=16;
+"#
+    );
+    let list = call(
+        &service,
+        "gowin.cst_list",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("cst_list");
+    assert_eq!(list["valid"], true, "{}", list["issues"]);
+}
+
+const PINOUT_CSV: &str = r#"GW1NR-9 Pinout,,,,,,,
+Pin Name,Function,BANK,Dual Function,Differential Pair,LVDS,QN88P,LQ144P
+IOL2A,I/O,3,,True_of_IOL2B,True LVDS,22,28
+IOL2B,I/O,3,,Comp_of_IOL2A,True LVDS,23,29
+IOL5A,I/O,3,,True_of_IOL5B,,24,30
+IOL5B,I/O,3,,Comp_of_IOL5A,,25,31
+IOT10A,I/O,0,MODE0,True_of_IOT10B,,86,141
+IOT10B,I/O,0,,Comp_of_IOT10A,,NC,140
+VCCIO0,VCCIO0,0,,,,84,138
+VSS,VSS,,,,,87,142
+VSS,VSS,,,,,88,143
+"#;
+
+#[tokio::test]
+async fn import_pinout_validates_cst_pins() {
+    let root = temp_project("pinout");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(
+        root.join("src/top.v"),
+        "module top (input rx_p, input btn, output [1:0] led, output tx);\nendmodule\n",
+    )
+    .expect("write top.v");
+    std::fs::write(root.join("pinout.csv"), PINOUT_CSV).expect("write csv");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project>
+    <Device name="GW1NR-9C" pn="GW1NR-LV9QN88PC6/I5">gw1nr9c-004</Device>
+    <FileList>
+        <File path="src/top.v" type="file.verilog" enable="1"/>
+        <File path="src/top.cst" type="file.cst" enable="1"/>
+    </FileList>
+</Project>
+"#,
+    )
+    .expect("write gprj");
+    std::fs::write(
+        root.join("src/top.cst"),
+        r#"IO_LOC "rx_p" 24;
+IO_PORT "rx_p" IO_TYPE=LVDS25;
+IO_LOC "btn" 87;
+IO_LOC "led[0]" 22;
+IO_PORT "led[0]" IO_TYPE=LVCMOS33;
+IO_LOC "led[1]" 23;
+IO_PORT "led[1]" IO_TYPE=LVCMOS18;
+IO_LOC "tx" 99;
+"#,
+    )
+    .expect("write cst");
+
+    let service = start_server(&root).await;
+    // ピン配置表は同梱しないので、取り込むまではピンの検証を省き、その旨を返す
+    let unchecked = call(
+        &service,
+        "gowin.cst_list",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("cst_list without pinout");
+    assert!(unchecked["pinout_file"].is_null());
+    let note = unchecked["pinout_note"].as_str().unwrap_or("");
+    assert!(
+        note.contains("GW1NR-9") && note.contains("gowin.import_pinout"),
+        "unexpected note: {note}"
+    );
+
+    let imported = call(
+        &service,
+        "gowin.import_pinout",
+        json!({
+            "project_root": root.display().to_string(),
+            "csv_path": "pinout.csv",
+        }),
+    )
+    .await
+    .expect("import_pinout");
+    assert_eq!(imported["device"], "GW1NR-9");
+    assert_eq!(imported["packages"], json!(["QN88P", "LQ144P"]));
+    assert_eq!(imported["pads"], 9);
+    assert_eq!(imported["io_pads"], 6);
+    assert_eq!(imported["differential_pairs"], 3);
+    assert!(root.join(".gowin-mcp/pinout/GW1NR-9.json").is_file());
+
+    let list = call(
+        &service,
+        "gowin.cst_list",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("cst_list");
+    assert_eq!(list["package"], "QN88P");
+    assert!(list["pinout_note"].is_null());
+    let messages: Vec<&str> = list["issues"]
+        .as_array()
+        .expect("issues")
+        .iter()
+        .filter_map(|i| i["message"].as_str())
+        .collect();
+    assert_eq!(messages.len(), 3, "{messages:?}");
+    assert!(messages[0].contains("87（VSS）"), "{messages:?}");
+    assert!(messages[1].contains("99"), "{messages:?}");
+    assert!(messages[2].contains("バンク 3"), "{messages:?}");
+
+    // N 側がこのパッケージにない差動ペアや、ペアの片割れは拒否する
+    for pin in ["86", "25,24"] {
+        let err = call(
+            &service,
+            "gowin.cst_edit",
+            json!({
+                "project_root": root.display().to_string(),
+                "action": "modify",
+                "port": "rx_p",
+                "pin": pin,
+            }),
+        )
+        .await
+        .expect_err("bad differential pair");
+        assert!(err.contains("差動ペア"), "{err}");
+    }
+    let ok = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "modify",
+            "port": "rx_p",
+            "pin": "24,25",
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect("valid pair");
+    assert_eq!(ok["after"]["pins"], json!(["24", "25"]));
+
+    // 差動ペアの列がなく、名前の末尾が壊れた（多バイト文字になった）表でも取り込める
+    let mut broken = b"Pin Name,Function,BANK,QN88P\nIOL2A,I/O,3,22\nIOL2B,I/O,3,23\nIOL3".to_vec();
+    broken.extend_from_slice(b"\xff,I/O,3,24\n");
+    std::fs::write(root.join("broken.csv"), broken).expect("write broken csv");
+    let broken = call(
+        &service,
+        "gowin.import_pinout",
+        json!({
+            "project_root": root.display().to_string(),
+            "csv_path": "broken.csv",
+        }),
+    )
+    .await
+    .expect("import_pinout with a broken pad name");
+    assert_eq!(broken["pads"], 3);
+    assert_eq!(broken["differential_pairs"], 1);
+}
+
+#[tokio::test]
+async fn device_catalogue_validates_parameters() {
+    let root = temp_project("catalogue");
+    write_fake_fs(&root.join("top.fs"));
+
+    let service = start_server(&root).await;
+    let list = call(
+        &service,
+        "gowin.list_devices",
+        json!({ "project_root": root.display().to_string(), "family": "gw1nr" }),
+    )
+    .await
+    .expect("list_devices");
+    let names: Vec<&str> = list["devices"]
+        .as_array()
+        .expect("devices")
+        .iter()
+        .filter_map(|d| d["name"].as_str())
+        .collect();
+    assert!(names.contains(&"GW1NR-9C"), "{names:?}");
+    assert!(names.iter().all(|n| n.starts_with("GW1NR-")), "{names:?}");
+    let gw1nr9c = &list["devices"][names.iter().position(|n| *n == "GW1NR-9C").expect("9C")];
+    assert_eq!(gw1nr9c["base"], "GW1NR-9");
+    assert_eq!(gw1nr9c["luts"], 8640);
+    assert_eq!(gw1nr9c["idcodes"], json!(["0x0100481B", "0x0100581B"]));
+    assert_eq!(
+        gw1nr9c["frequencies"],
+        json!(["2.5MHz", "5MHz", "6MHz", "10MHz", "12MHz", "15MHz"])
+    );
+    assert!(
+        list["frequencies"]
+            .as_array()
+            .expect("frequencies")
+            .contains(&json!("15MHz"))
+    );
+
+    let err = call(
+        &service,
+        "gowin.list_devices",
+        json!({ "project_root": root.display().to_string(), "family": "GW5AS" }),
+    )
+    .await
+    .expect_err("unknown family");
+    assert!(err.contains("もしかして: GW5A"), "{err}");
+
+    let program = |device: &str, frequency: &str| {
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+            "fs_file_path": "top.fs",
+            "device": device,
+            "frequency": frequency,
+        })
+    };
+    let err = call(&service, "gowin.program_fs", program("GW5A-25", "15MHz"))
+        .await
+        .expect_err("unknown device");
+    assert!(err.contains("もしかして: GW5A-25A"), "{err}");
+    let err = call(&service, "gowin.program_fs", program("GW1NR-9C", "16MHz"))
+        .await
+        .expect_err("unknown frequency");
+    assert!(err.contains("15MHz"), "{err}");
+    let err = call(&service, "gowin.program_fs", program("GW1NR-9C", "30MHz"))
+        .await
+        .expect_err("too fast for GW1NR");
+    assert!(err.contains("GW1NR-9C では選べません"), "{err}");
+
+    // 目録にない名前は config.toml の extra_devices で許可できる
+    std::fs::create_dir_all(root.join(".gowin-mcp")).expect("create .gowin-mcp");
+    std::fs::write(
+        root.join(".gowin-mcp/config.toml"),
+        "extra_devices = [\"GW1N-9X\"]\n",
+    )
+    .expect("write config");
+    let err = call(&service, "gowin.program_fs", program("GW1N-9X", "15MHz"))
+        .await
+        .expect_err("no programmer in the fake install");
+    assert!(!err.contains("目録"), "{err}");
+}
+
+#[tokio::test]
+async fn board_profiles_fill_defaults() {
+    let root = temp_project("boards");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(
+        root.join("src/top.v"),
+        "module top (input clk, output [1:0] led);\nendmodule\n",
+    )
+    .expect("write top.v");
+    std::fs::write(root.join("src/top.cst"), "").expect("write cst");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project><Device name="GW5A-25A" pn="GW5A-LV25MG121NC1/I0">gw5a25a-002</Device></Project>"#,
+    )
+    .expect("write gprj");
+    std::fs::create_dir_all(root.join(".gowin-mcp")).expect("create .gowin-mcp");
+    std::fs::write(
+        root.join(".gowin-mcp/config.toml"),
+        r#"[boards.mylab]
+device = "GW5A-25A"
+part_number = "GW5A-LV25MG121NC1/I0"
+frequency = "6MHz"
+flash = "exflash"
+[boards.mylab.pins]
+"led[0]" = { pin = "L14", io_type = "LVCMOS33" }
+"#,
+    )
+    .expect("write config");
+
+    let service = start_server(&root).await;
+    let list = call(
+        &service,
+        "gowin.list_boards",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("list_boards");
+    let boards = list["boards"].as_array().expect("boards");
+    let find = |name: &str| {
+        boards
+            .iter()
+            .find(|b| b["name"] == name)
+            .unwrap_or_else(|| panic!("{name}: {boards:?}"))
+    };
+    assert_eq!(find("tangnano9k")["package"], "QN88P");
+    assert_eq!(find("tangnano9k")["origin"], "builtin");
+    assert_eq!(find("mylab")["origin"], "config");
+    assert_eq!(find("mylab")["package"], "MG121N");
+
+    // 既定のピンで IO_LOC / IO_PORT を埋める
+    let edit = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "add",
+            "port": "led[0]",
+            "board": "mylab",
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect("cst_edit with board");
+    let content = edit["content"].as_str().expect("content");
+    assert!(content.contains("IO_LOC \"led[0]\" L14;"), "{content}");
+    assert!(content.contains("IO_TYPE=LVCMOS33"), "{content}");
+
+    let err = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "add",
+            "port": "led[1]",
+            "board": "mylab",
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect_err("no default pin");
+    assert!(err.contains("led[0]"), "{err}");
+
+    let err = call(
+        &service,
+        "gowin.cst_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "action": "add",
+            "port": "clk",
+            "board": "tangnano9",
+        }),
+    )
+    .await
+    .expect_err("unknown board");
+    assert!(err.contains("もしかして: tangnano9k"), "{err}");
+
+    // .gprj の品番が違うボードではビルドしない
+    let err = call(
+        &service,
+        "gowin.build_and_program",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+            "tcl_inline": "run all",
+            "board": "tangnano9k",
+        }),
+    )
+    .await
+    .expect_err("part number mismatch");
+    assert!(err.contains("GW1NR-LV9QN88PC6/I5"), "{err}");
+
+    // run_tcl も品番を照合してからビルドする
+    for (board, mismatch) in [("tangnano9k", true), ("tangprimer25k", false)] {
+        let err = call(
+            &service,
+            "gowin.run_tcl",
+            json!({
+                "project_root": root.display().to_string(),
+                "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+                "tcl_inline": "run all",
+                "board": board,
+            }),
+        )
+        .await
+        .expect_err("no gw_sh");
+        assert_eq!(err.contains("GW1NR-LV9QN88PC6/I5"), mismatch, "{err}");
+    }
+
+    let err = call(
+        &service,
+        "gowin.program_fs",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+            "fs_file_path": "top.fs",
+            "board": "tangprimer25k",
+            "target": "flash",
+            "frequency": "16MHz",
+        }),
+    )
+    .await
+    .expect_err("bad frequency");
+    assert!(err.contains("15MHz"), "{err}");
+}
+
+// Gowin の合成後ネットリスト（.vg）の形: 非 ANSI のポート、エスケープ識別子、プリミティブのインスタンス
+const NETLIST_VG: &str = r#"//
+//Written by GowinSynthesis
+module top (
+  clk,
+  btn,
+  led,
+  uart_tx
+)
+;
+input clk;
+input btn;
+output [1:0] led;
+output uart_tx;
+wire clk_d;
+wire btn_d;
+wire pll_clk;
+wire [1:0] led_d;
+wire \cnt_0_s0/Q ;
+wire VCC;
+wire GND;
+IBUF clk_ibuf (
+    .O(clk_d),
+    .I(clk)
+);
+IBUF btn_ibuf (
+    .O(btn_d),
+    .I(btn)
+);
+rPLL \u_pll/rpll_inst  (
+    .CLKOUT(pll_clk),
+    .CLKIN(clk_d)
+);
+DFFR \cnt_0_s0  (
+    .Q(\cnt_0_s0/Q ),
+    .D(btn_d),
+    .CLK(pll_clk),
+    .RESET(GND)
+);
+DFF \led_d_0_s0  (
+    .Q(led_d[0]),
+    .D(\cnt_0_s0/Q ),
+    .CLK(clk_d)
+);
+OBUF led_0_obuf (
+    .O(led[0]),
+    .I(led_d[0])
+);
+OBUF led_1_obuf (
+    .O(led[1]),
+    .I(led_d[0])
+);
+OBUF uart_tx_obuf (
+    .O(uart_tx),
+    .I(GND)
+);
+GSR GSR (
+    .GSRI(VCC)
+);
+endmodule
+"#;
+
+#[tokio::test]
+async fn constraint_coverage_finds_gaps() {
+    let root = temp_project("coverage");
+    std::fs::create_dir_all(root.join("impl/gwsynthesis")).expect("create gwsynthesis");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(root.join("impl/gwsynthesis/top.vg"), NETLIST_VG).expect("write vg");
+    std::fs::write(
+        root.join("src/top.cst"),
+        r#"IO_LOC "clk" 52;
+IO_PORT "clk" IO_TYPE=LVCMOS33;
+IO_LOC "btn" 3;
+IO_LOC "led[0]" 10;
+IO_LOC "uart_rx" 18;
+"#,
+    )
+    .expect("write cst");
+    std::fs::write(
+        root.join("src/top.sdc"),
+        r#"create_clock -name clk -period 37.037 [get_ports {clk}]
+set_false_path -from [get_ports {rst_n}]
+set_max_delay 5 -to [get_pins {u_pll/*}]
+"#,
+    )
+    .expect("write sdc");
+
+    let service = start_server(&root).await;
+    let result = call(
+        &service,
+        "gowin.constraint_coverage",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("constraint_coverage");
+    assert_eq!(result["top_module"], "top");
+    assert_eq!(result["ports"], 5);
+    assert_eq!(result["covered"], false);
+
+    let unconstrained: Vec<&str> = result["unconstrained_ports"]
+        .as_array()
+        .expect("unconstrained_ports")
+        .iter()
+        .filter_map(|p| p["port"].as_str())
+        .collect();
+    assert_eq!(unconstrained, ["led[1]", "uart_tx"]);
+
+    // clk は IBUF を通って DFF と PLL に、PLL の出力は別のクロック
+    let clocks = result["clocks"].as_array().expect("clocks");
+    let clk = clocks
+        .iter()
+        .find(|c| c["root"] == "clk")
+        .expect("clk clock");
+    assert_eq!(clk["kind"], "port");
+    assert_eq!(clk["sinks"], 2);
+    assert_eq!(clk["constrained_by"], json!(["clk"]));
+    let pll = clocks
+        .iter()
+        .find(|c| c["root"] == "u_pll/rpll_inst/CLKOUT")
+        .expect("pll clock");
+    assert_eq!(pll["kind"], "pin");
+    assert_eq!(pll["nets"], json!(["pll_clk"]));
+    assert_eq!(
+        result["unconstrained_clocks"],
+        json!(["u_pll/rpll_inst/CLKOUT"])
+    );
+
+    let unmatched: Vec<(&str, &str)> = result["unmatched_constraints"]
+        .as_array()
+        .expect("unmatched_constraints")
+        .iter()
+        .map(|u| {
+            (
+                u["file"].as_str().unwrap_or(""),
+                u["reference"].as_str().unwrap_or(""),
+            )
+        })
+        .collect();
+    assert_eq!(unmatched, [("cst", "uart_rx"), ("sdc", "rst_n")]);
+
+    // PLL の出力にも制約を付けると、クロックはすべて網羅される
+    std::fs::write(
+        root.join("src/top.sdc"),
+        r#"create_clock -name clk -period 37.037 [get_ports {clk}]
+create_generated_clock -name pll_clk -source [get_ports {clk}] -multiply_by 4 [get_nets {pll_clk}]
+"#,
+    )
+    .expect("rewrite sdc");
+    let result = call(
+        &service,
+        "gowin.constraint_coverage",
+        json!({ "project_root": root.display().to_string() }),
+    )
+    .await
+    .expect("constraint_coverage again");
+    assert_eq!(result["unconstrained_clocks"], json!([]));
+
+    let err = call(
+        &service,
+        "gowin.constraint_coverage",
+        json!({ "project_root": temp_project("coverage-none").display().to_string() }),
+    )
+    .await
+    .expect_err("no netlist");
+    assert!(err.contains(".vg"), "{err}");
+}
+
+#[tokio::test]
+async fn pll_solve_enumerates_dividers() {
+    let root = temp_project("pll");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<Project><Device name="GW1NR-9C" pn="GW1NR-LV9QN88PC6/I5">gw1nr9c-004</Device></Project>"#,
+    )
+    .expect("write gprj");
+
+    let service = start_server(&root).await;
+
+    // デバイスは .gprj から（GW1NR → rPLL）
+    let result = call(
+        &service,
+        "gowin.pll_solve",
+        json!({
+            "project_root": root.display().to_string(),
+            "input_mhz": 27.0,
+            "outputs_mhz": [108.0, 54.0],
+        }),
+    )
+    .await
+    .expect("pll_solve rPLL");
+    assert_eq!(result["device"], "GW1NR-9C");
+    assert_eq!(result["primitive"], "rPLL");
+    let best = &result["solutions"][0];
+    assert_eq!(best["max_error_ppm"], 0.0);
+    assert_eq!(best["idiv_sel"], 0);
+    assert_eq!(best["fbdiv_sel"], 3);
+    // 同じ出力なら VCO の高いもの
+    assert_eq!(best["odiv_sel"], 8);
+    assert_eq!(best["vco_mhz"], 864.0);
+    assert_eq!(best["outputs"][1]["pin"], "CLKOUTD");
+    assert_eq!(best["outputs"][1]["divider"], 2);
+    let verilog = best["verilog"].as_str().expect("verilog");
+    assert!(verilog.contains(".FCLKIN(\"27\")"), "{verilog}");
+    assert!(verilog.contains(".DEVICE(\"GW1NR-9C\")"), "{verilog}");
+    assert!(verilog.contains(".FBDIV_SEL(3)"), "{verilog}");
+    assert!(verilog.contains(".CLKOUTD(pll_inst_clkoutd)"), "{verilog}");
+    let sdc = best["sdc"].as_str().expect("sdc");
+    assert!(
+        sdc.contains(
+            "create_generated_clock -name pll_inst_clkout -source [get_ports {clk}] -master_clock clk -multiply_by 4 -divide_by 1 [get_pins {pll_inst/CLKOUT}]"
+        ),
+        "{sdc}"
+    );
+    let solutions = result["solutions"].as_array().expect("solutions");
+    assert_eq!(solutions.len(), 5);
+    assert!(
+        solutions
+            .windows(2)
+            .all(|w| w[0]["max_error_ppm"].as_f64() <= w[1]["max_error_ppm"].as_f64())
+    );
+
+    // 生成した制約は sdc_edit でそのまま使える
+    let edit = call(
+        &service,
+        "gowin.sdc_edit",
+        json!({
+            "project_root": root.display().to_string(),
+            "sdc_path": "src/top.sdc",
+            "action": "add",
+            "command": sdc.lines().next().expect("line"),
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect("sdc_edit");
+    assert!(
+        edit["issues"]
+            .as_array()
+            .expect("issues")
+            .iter()
+            .all(|i| i["level"] != "error"),
+        "{edit}"
+    );
+
+    // GW5A → PLLA、出力は 7 つまで
+    let result = call(
+        &service,
+        "gowin.pll_solve",
+        json!({
+            "project_root": root.display().to_string(),
+            "board": "tangprimer25k",
+            "input_mhz": 50.0,
+            "outputs_mhz": [100.0, 25.0, 40.0],
+            "max_solutions": 3,
+            "instance": "u_pll",
+        }),
+    )
+    .await
+    .expect("pll_solve PLLA");
+    assert_eq!(result["primitive"], "PLLA");
+    let best = &result["solutions"][0];
+    let actual: Vec<f64> = best["outputs"]
+        .as_array()
+        .expect("outputs")
+        .iter()
+        .filter_map(|o| o["actual_mhz"].as_f64())
+        .collect();
+    assert_eq!(actual, [100.0, 25.0, 40.0]);
+    assert_eq!(best["max_error_ppm"], 0.0);
+    let verilog = best["verilog"].as_str().expect("verilog");
+    assert!(verilog.contains("PLLA #("), "{verilog}");
+    assert!(verilog.contains(".CLKOUT2_EN(\"TRUE\")"), "{verilog}");
+    assert!(verilog.contains(".CLKOUT3_EN(\"FALSE\")"), "{verilog}");
+    assert!(
+        best["sdc"]
+            .as_str()
+            .expect("sdc")
+            .contains("[get_pins {u_pll/CLKOUT1}]")
+    );
+
+    let err = call(
+        &service,
+        "gowin.pll_solve",
+        json!({
+            "project_root": root.display().to_string(),
+            "device": "GW1NR-9X",
+            "input_mhz": 27.0,
+            "outputs_mhz": [100.0],
+        }),
+    )
+    .await
+    .expect_err("unknown device");
+    assert!(err.contains("もしかして: GW1NR-9"), "{err}");
+
+    let err = call(
+        &service,
+        "gowin.pll_solve",
+        json!({
+            "project_root": root.display().to_string(),
+            "input_mhz": 27.0,
+            "outputs_mhz": [2000.0],
+        }),
+    )
+    .await
+    .expect_err("out of range");
+    assert!(err.contains("3.125〜600 MHz"), "{err}");
+}
+
+// .mod の -path / -file_name / -mod_name を読んで HDL と .ipc を書く GowinModGen。
+// Gowin_Broken は何も書かずに終了コード 0 で終わる
+const FAKE_MODGEN: &str = r#"#!/bin/sh
+[ "$1" = "-do" ] || { echo "usage: GowinModGen -do <file>.mod" >&2; exit 2; }
+dir=$(sed -n 's/^-path //p' "$2" | sed 's/^"\(.*\)"$/\1/')
+name=$(sed -n 's/^-file_name //p' "$2")
+module=$(sed -n 's/^-mod_name //p' "$2")
+[ "$module" = "Gowin_Broken" ] && exit 0
+mkdir -p "$dir"
+printf 'module %s (output clkout, input clkin);\nendmodule\n' "$module" > "$dir$name.v"
+printf '[General]\nType=PLL\n' > "$dir$name.ipc"
+echo "Generate $module successfully"
+"#;
+
+#[tokio::test]
+async fn ip_generate_runs_modgen_and_registers_files() {
+    use std::os::unix::fs::PermissionsExt;
+
+    let root = temp_project("ipgen");
+    std::fs::create_dir_all(root.join("src")).expect("create src");
+    std::fs::write(
+        root.join("src/top.v"),
+        "module top (input clk, output led);\nendmodule\n",
+    )
+    .expect("write top.v");
+    std::fs::write(
+        root.join("top.gprj"),
+        r#"<?xml version="1" encoding="UTF-8"?>
+<Project>
+    <Device name="GW1NR-9C" pn="GW1NR-LV9QN88PC6/I5">gw1nr9c-004</Device>
+    <FileList>
+        <File path="src/top.v" type="file.verilog" enable="1"/>
+    </FileList>
+</Project>
+"#,
+    )
+    .expect("write gprj");
+    let app = root.join("FakeGowinIDE.app");
+    let bin = app.join("Contents/Resources/Gowin_EDA/IDE/bin");
+    std::fs::create_dir_all(&bin).expect("create IDE bin");
+    std::fs::write(bin.join("GowinModGen"), FAKE_MODGEN).expect("write GowinModGen");
+    std::fs::set_permissions(
+        bin.join("GowinModGen"),
+        std::fs::Permissions::from_mode(0o755),
+    )
+    .expect("chmod GowinModGen");
+
+    let service = start_server(&root).await;
+    let request = |dry_run: bool| {
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "ip_type": "PLL",
+            "module_name": "Gowin_rPLL",
+            "parameters": { "idiv_sel": 0, "fbdiv_sel": 3, "-odiv_sel": 8, "dyn_sdiv": false },
+            "dry_run": dry_run,
+        })
+    };
+
+    let dry = call(&service, "gowin.ip_generate", request(true))
+        .await
+        .expect("dry_run");
+    let content = dry["mod_content"].as_str().expect("mod_content");
+    for line in [
+        "-series GW1NR",
+        "-device GW1NR-9C",
+        "-device_version C",
+        "-package QFN88P",
+        "-part_number GW1NR-LV9QN88PC6/I5",
+        "-mod_name Gowin_rPLL",
+        "-file_name gowin_rpll",
+        "-type PLL",
+        "-file_type vlg",
+        "-fbdiv_sel 3",
+        "-odiv_sel 8",
+        "-dyn_sdiv false",
+    ] {
+        assert!(content.lines().any(|l| l == line), "{line}: {content}");
+    }
+    assert!(
+        content.contains(&format!("-path {}/", root.join("src/gowin_rpll").display())),
+        "{content}"
+    );
+    assert!(!root.join("src/gowin_rpll").exists());
+    assert_eq!(dry["success"], false);
+
+    let generated = call(&service, "gowin.ip_generate", request(false))
+        .await
+        .expect("ip_generate");
+    assert_eq!(generated["success"], true, "{generated}");
+    assert_eq!(generated["command"]["args"][0], "-do");
+    assert_eq!(
+        generated["registered"],
+        json!(["src/gowin_rpll/gowin_rpll.v"])
+    );
+    let files: Vec<&str> = generated["generated_files"]
+        .as_array()
+        .expect("generated_files")
+        .iter()
+        .filter_map(|f| f.as_str())
+        .collect();
+    assert!(
+        files.iter().any(|f| f.ends_with("gowin_rpll.ipc")),
+        "{files:?}"
+    );
+    assert!(root.join("src/gowin_rpll/gowin_rpll.mod").is_file());
+    let gprj = std::fs::read_to_string(root.join("top.gprj")).expect("read gprj");
+    assert!(
+        gprj.contains(
+            "        <File path=\"src/gowin_rpll/gowin_rpll.v\" type=\"file.verilog\" enable=\"1\"/>\n    </FileList>"
+        ),
+        "{gprj}"
+    );
+
+    // 2 回目は登録済みなので .gprj は変わらない
+    let again = call(&service, "gowin.ip_generate", request(false))
+        .await
+        .expect("ip_generate again");
+    assert_eq!(again["registered"], json!([]));
+    assert_eq!(
+        std::fs::read_to_string(root.join("top.gprj")).expect("read gprj"),
+        gprj
+    );
+
+    let err = call(
+        &service,
+        "gowin.ip_generate",
+        json!({
+            "project_root": root.display().to_string(),
+            "ip_type": "PLL",
+            "parameters": { "device": "GW5A-25A" },
+            "dry_run": true,
+        }),
+    )
+    .await
+    .expect_err("reserved parameter");
+    assert!(err.contains("device"), "{err}");
+
+    let err = call(
+        &service,
+        "gowin.ip_generate",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": root.join("NoGowinIDE.app").display().to_string(),
+            "ip_type": "FIFO_HS",
+        }),
+    )
+    .await
+    .expect_err("no GowinModGen");
+    assert!(err.contains("GowinModGen"), "{err}");
+
+    // 登録できない出力先は GowinModGen を動かす前に断る
+    let err = call(
+        &service,
+        "gowin.ip_generate",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "ip_type": "OSC",
+            "output_dir": "src/../../outside",
+        }),
+    )
+    .await
+    .expect_err("output_dir outside the .gprj directory");
+    assert!(err.contains(".gprj のディレクトリ"), "{err}");
+    assert!(!root.join("../outside").exists());
+
+    // 空白を含む出力先は -path を引用符で囲む
+    let spaced = call(
+        &service,
+        "gowin.ip_generate",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "ip_type": "OSC",
+            "output_dir": "src/my ip",
+        }),
+    )
+    .await
+    .expect("ip_generate into a path with spaces");
+    assert!(
+        spaced["mod_content"]
+            .as_str()
+            .unwrap_or("")
+            .contains(&format!("-path \"{}/\"", root.join("src/my ip").display())),
+        "{spaced}"
+    );
+    assert_eq!(spaced["success"], true, "{spaced}");
+    assert_eq!(spaced["registered"], json!(["src/my ip/gowin_osc.v"]));
+
+    // 前回の HDL が残っているだけなら成功にしない
+    let old_dir = root.join("src/gowin_broken");
+    std::fs::create_dir_all(&old_dir).expect("create old dir");
+    let old = std::fs::File::create(old_dir.join("gowin_broken.v")).expect("write old HDL");
+    old.set_modified(std::time::SystemTime::UNIX_EPOCH + std::time::Duration::from_secs(1_000_000))
+        .expect("set mtime");
+    let broken = call(
+        &service,
+        "gowin.ip_generate",
+        json!({
+            "project_root": root.display().to_string(),
+            "gowin_ide_app_path": app.display().to_string(),
+            "ip_type": "PLL",
+            "module_name": "Gowin_Broken",
+        }),
+    )
+    .await
+    .expect("ip_generate with a stale HDL");
+    assert_eq!(broken["success"], false, "{broken}");
+    assert_eq!(broken["registered"], json!([]));
+}
@@ -65,6 +65,25 @@ async fn list_tools_smoke() {
     assert!(names.iter().any(|n| n == "gowin.run_tcl"));
     assert!(names.iter().any(|n| n == "gowin.list_cables"));
     assert!(names.iter().any(|n| n == "gowin.program_fs"));
+    assert!(names.iter().any(|n| n == "gowin.convert_bitstream"));
+    assert!(names.iter().any(|n| n == "gowin.build_flash_image"));
+    assert!(names.iter().any(|n| n == "gowin.scan_chain"));
+    assert!(names.iter().any(|n| n == "gowin.device_status"));
+    assert!(names.iter().any(|n| n == "gowin.reconfigure"));
+    assert!(names.iter().any(|n| n == "gowin.read_flash"));
+    assert!(names.iter().any(|n| n == "gowin.build_and_program"));
+    assert!(names.iter().any(|n| n == "gowin.build_trends"));
+    assert!(names.iter().any(|n| n == "gowin.compare_runs"));
+    assert!(names.iter().any(|n| n == "gowin.sdc_list"));
+    assert!(names.iter().any(|n| n == "gowin.sdc_edit"));
+    assert!(names.iter().any(|n| n == "gowin.cst_list"));
+    assert!(names.iter().any(|n| n == "gowin.cst_edit"));
+    assert!(names.iter().any(|n| n == "gowin.import_pinout"));
+    assert!(names.iter().any(|n| n == "gowin.list_devices"));
+    assert!(names.iter().any(|n| n == "gowin.list_boards"));
+    assert!(names.iter().any(|n| n == "gowin.constraint_coverage"));
+    assert!(names.iter().any(|n| n == "gowin.pll_solve"));
+    assert!(names.iter().any(|n| n == "gowin.ip_generate"));
 
     service.cancel().await.expect("cancel");
 }